@@ -0,0 +1,121 @@
+use crate::security::{hash_password, OidcConfig, Role, UserRecord, UserStore};
+use std::io::{self, BufRead, Write};
+use uuid::Uuid;
+
+const USAGE: &str = "Usage:
+    worker-api user add <name> [<id>]
+    worker-api user passwd <name>
+    worker-api user disable <name>
+    worker-api user role <name> admin|operator|submitter|viewer
+    worker-api user teams <name> [<team>,...]
+
+The passwords are read from the standard input.";
+
+/// Run an administration command instead of the server.  It returns
+/// the exit code of the process.
+///
+/// * `users` - Store of the users.
+/// * `args` - Arguments after `user`.
+pub fn run_user_command(users: &dyn UserStore, args: &[String]) -> i32 {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["add", name] => add(users, name, &Uuid::new_v4().to_string()),
+        ["add", name, id] => add(users, name, id),
+        ["passwd", name] => change_password(users, name),
+        ["disable", name] => disable(users, name),
+        ["role", name, role] => change_role(users, name, role),
+        ["teams", name] => change_teams(users, name, ""),
+        ["teams", name, teams] => change_teams(users, name, teams),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("ERR: {}", err);
+            1
+        }
+    }
+}
+
+// The id can be given to keep the owner of existing jobs.  The prefix
+// of the users of the OpenID Connect issuer is reserved.
+fn add(users: &dyn UserStore, name: &str, id: &str) -> Result<(), String> {
+    if name.starts_with(OidcConfig::PREFIX) || id.starts_with(OidcConfig::PREFIX) {
+        return Err(format!(
+            "The names and ids of the users can't start with '{}'",
+            OidcConfig::PREFIX
+        ));
+    }
+    if users.find(name).map_err(|err| err.to_string())?.is_some() {
+        return Err(format!("User '{}' already exists", name));
+    }
+    let password_hash = read_password_hash()?;
+    users
+        .save(UserRecord {
+            id: id.to_string(),
+            name: name.to_string(),
+            password_hash,
+            role: Role::default(),
+            teams: Vec::new(),
+            disabled: false,
+        })
+        .map_err(|err| err.to_string())?;
+    println!("User '{}' added with id '{}'", name, id);
+    Ok(())
+}
+
+fn change_password(users: &dyn UserStore, name: &str) -> Result<(), String> {
+    let mut user = find(users, name)?;
+    user.password_hash = read_password_hash()?;
+    users.save(user).map_err(|err| err.to_string())
+}
+
+fn disable(users: &dyn UserStore, name: &str) -> Result<(), String> {
+    let mut user = find(users, name)?;
+    user.disabled = true;
+    users.save(user).map_err(|err| err.to_string())
+}
+
+fn change_role(users: &dyn UserStore, name: &str, role: &str) -> Result<(), String> {
+    let mut user = find(users, name)?;
+    user.role = role.parse()?;
+    users.save(user).map_err(|err| err.to_string())
+}
+
+// The teams are replaced by the list, that can be empty.
+fn change_teams(users: &dyn UserStore, name: &str, teams: &str) -> Result<(), String> {
+    let mut user = find(users, name)?;
+    user.teams = teams
+        .split(',')
+        .map(str::trim)
+        .filter(|team| !team.is_empty())
+        .map(str::to_string)
+        .collect();
+    users.save(user).map_err(|err| err.to_string())
+}
+
+fn find(users: &dyn UserStore, name: &str) -> Result<UserRecord, String> {
+    users
+        .find(name)
+        .map_err(|err| err.to_string())?
+        .ok_or_else(|| format!("User '{}' doesn't exist", name))
+}
+
+// Read the first line of stdin, so the password can also be piped.
+fn read_password_hash() -> Result<String, String> {
+    eprint!("Password: ");
+    io::stderr().flush().map_err(|err| err.to_string())?;
+    let mut password = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut password)
+        .map_err(|err| err.to_string())?;
+    let password = password.trim_end_matches(&['\r', '\n'][..]);
+    if password.is_empty() {
+        return Err("The password can't be empty".to_string());
+    }
+    hash_password(password).map_err(|err| err.to_string())
+}
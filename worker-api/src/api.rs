@@ -1,5 +1,10 @@
+pub mod api_keys;
+pub mod audit_log;
 pub mod auth;
+pub mod events;
 pub mod health;
 pub mod jobs;
+pub mod jwks;
+pub mod me;
 pub mod request;
 pub mod response;
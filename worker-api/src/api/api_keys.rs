@@ -0,0 +1,112 @@
+use super::{request, response};
+use crate::{
+    audit::{AuditAction, AuditEntry, AuditOutcome},
+    security::{ApiKeys, Claims, UserStore},
+};
+use rocket::{delete, get, http::Status, post, response::status::Custom, State};
+use rocket_contrib::json::Json;
+use std::sync::Arc;
+
+const DEFAULT_VALID_DAYS: u32 = 90;
+const MAX_VALID_DAYS: u32 = 365;
+
+// API keys are managed with the access tokens of a login, so a leaked
+// key can't be used to create more.
+
+/// HTTP handler that creates an API key for the user.  Only the users
+/// of the `UserStore` can have keys, not the ones of an OIDC issuer.
+#[post("/", format = "application/json", data = "<new_key>")]
+pub async fn create(
+    claims: Claims,
+    audit: request::Auditor<'_>,
+    new_key: Json<request::ApiKey>,
+    keys: State<'_, ApiKeys>,
+    users: State<'_, Arc<dyn UserStore>>,
+) -> Result<Custom<Json<response::NewApiKey>>, Status> {
+    eprintln!("claim subject: {}", claims.sub);
+    let new_key = new_key.into_inner();
+    let valid_days = new_key.expires_in_days.unwrap_or(DEFAULT_VALID_DAYS);
+    if new_key.scopes.is_empty() || valid_days == 0 || valid_days > MAX_VALID_DAYS {
+        return Err(Status::BadRequest);
+    }
+    let users = Arc::clone(&users);
+    let user_name = claims.user_name.clone();
+    let user = tokio::task::spawn_blocking(move || users.find(&user_name))
+        .await
+        .map_err(|_| Status::InternalServerError)?
+        .map_err(|_| Status::InternalServerError)?;
+    let entry = |outcome| {
+        AuditEntry::new(AuditAction::ApiKeyCreate, outcome)
+            .actor(&claims.user_name, Some(&claims.sub))
+            .detail(new_key.name.as_str())
+    };
+    if !matches!(user, Some(user) if user.id == claims.sub) {
+        audit.record(entry(AuditOutcome::Denied));
+        return Err(Status::Forbidden);
+    }
+    let entry = entry(AuditOutcome::Success);
+    match keys.create(
+        &claims.sub,
+        &claims.user_name,
+        &new_key.name,
+        new_key.scopes,
+        i64::from(valid_days),
+    ) {
+        Ok((record, key)) => {
+            audit.record(entry.target(&record.id));
+            Ok(Custom(
+                Status::Created,
+                Json(response::NewApiKey {
+                    api_key: response::ApiKey::from(record),
+                    key,
+                }),
+            ))
+        }
+        Err(err) => {
+            eprintln!("The API keys can't be stored: {}", err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// HTTP handler that lists the API keys of the user that haven't
+/// expired.
+#[get("/")]
+pub async fn list(claims: Claims, keys: State<'_, ApiKeys>) -> Json<Vec<response::ApiKey>> {
+    eprintln!("claim subject: {}", claims.sub);
+    Json(
+        keys.list(&claims.sub)
+            .into_iter()
+            .map(response::ApiKey::from)
+            .collect(),
+    )
+}
+
+/// HTTP handler that revokes an API key of the user.
+#[delete("/<id>")]
+pub async fn revoke(
+    claims: Claims,
+    audit: request::Auditor<'_>,
+    id: String,
+    keys: State<'_, ApiKeys>,
+) -> Status {
+    eprintln!("claim subject: {}", claims.sub);
+    let revoked = keys.revoke(&claims.sub, &id);
+    let outcome = match revoked {
+        Ok(true) => AuditOutcome::Success,
+        _ => AuditOutcome::Failure,
+    };
+    audit.record(
+        AuditEntry::new(AuditAction::ApiKeyRevoke, outcome)
+            .actor(&claims.user_name, Some(&claims.sub))
+            .target(&id),
+    );
+    match revoked {
+        Ok(true) => Status::NoContent,
+        Ok(false) => Status::NotFound,
+        Err(err) => {
+            eprintln!("The API keys can't be stored: {}", err);
+            Status::InternalServerError
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use super::request;
+use crate::{
+    audit::{AuditAction, AuditEntry, AuditLog, AuditOutcome, AuditQuery},
+    security::{Claims, Role},
+};
+use rocket::{get, http::Status, State};
+use rocket_contrib::json::Json;
+
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+/// HTTP handler that returns the most recent entries of the audit log
+/// that match the filters, from the oldest to the most recent.  Only
+/// admins can query it, and the queries are audited too.
+///
+/// * actor: Name or subject of the user.
+/// * action: Action of the entries, e.g. `login` or `job_create`.
+/// * outcome: `success`, `failure`, `denied` or `limited`.
+/// * since: Seconds since the epoch of the oldest entry.
+/// * limit: Maximum number of entries, 100 by default and at most 1000.
+#[get("/?<actor>&<action>&<outcome>&<since>&<limit>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn query(
+    claims: Claims,
+    audit: request::Auditor<'_>,
+    log: State<'_, AuditLog>,
+    actor: Option<String>,
+    action: Option<String>,
+    outcome: Option<String>,
+    since: Option<i64>,
+    limit: Option<usize>,
+) -> Result<Json<Vec<AuditEntry>>, Status> {
+    eprintln!("claim subject: {}", claims.sub);
+    let entry = |outcome| {
+        AuditEntry::new(AuditAction::AuditQuery, outcome)
+            .actor(&claims.user_name, Some(&claims.sub))
+    };
+    if claims.role != Role::Admin {
+        audit.record(entry(AuditOutcome::Denied));
+        return Err(Status::Forbidden);
+    }
+    let query = AuditQuery {
+        actor,
+        action: match action {
+            Some(action) => Some(action.parse().map_err(|_| Status::BadRequest)?),
+            None => None,
+        },
+        outcome: match outcome {
+            Some(outcome) => Some(outcome.parse().map_err(|_| Status::BadRequest)?),
+            None => None,
+        },
+        since,
+        limit: limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT),
+    };
+    audit.record(entry(AuditOutcome::Success));
+    match log.query(query).await {
+        Ok(entries) => Ok(Json(entries)),
+        Err(err) => {
+            eprintln!("The audit log can't be read: {}", err);
+            Err(Status::InternalServerError)
+        }
+    }
+}
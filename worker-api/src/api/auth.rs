@@ -1,21 +1,128 @@
-use crate::security::{self, Token, User};
-use rocket::{http::Status, post};
+use super::{request, response};
+use crate::{
+    audit::{AuditAction, AuditEntry, AuditOutcome},
+    limits::LoginLimits,
+    security::{self, Claims, RefreshToken, RevocationList, SigningKeys, Token, User, UserStore},
+};
+use rocket::{http::Status, post, State};
 use rocket_contrib::json::Json;
+use std::{io, sync::Arc};
 
 /// HTTP handler for user login.
 ///
 /// * user: `User` - User data (name and password).
+/// * users: `UserStore` - Users that can log in.
+/// * keys: `SigningKeys` - Keys that sign the tokens.
+/// * limits: `LoginLimits` - Rate limits of the login attempts.
+/// * audit: `Auditor` - Audit log of the logins.
 #[post("/login", format = "application/json", data = "<user>")]
-pub async fn login(user: Json<User>) -> Result<Json<Token>, Status> {
-    //! This is a hardwired process at the moment, it should check
-    //! hashes in a user database to authenticate the user.
+pub async fn login(
+    audit: request::Auditor<'_>,
+    user: Json<User>,
+    users: State<'_, Arc<dyn UserStore>>,
+    keys: State<'_, Arc<SigningKeys>>,
+    limits: State<'_, LoginLimits>,
+) -> Result<Result<Json<Token>, Status>, response::TooManyRequests> {
+    //! The password is verified against the Argon2id hash stored for
+    //! the user.  Hashing is slow by design, so it doesn't run in the
+    //! threads of the server.  Attempts over the rate limits are
+    //! refused with `429` before the password is verified.  Every
+    //! attempt is audited, but never its password.
     let user = user.into_inner();
-    eprintln!("Login user: {:?}", user);
-    // https://github.com/rust-lang/rust-clippy/issues/7024
-    #[allow(clippy::suspicious_else_formatting)]
-    if let Some(token) = security::login(user) {
-        Ok(Json(Token { token }))
+    let user_name = user.name.clone();
+    eprintln!("Login user: {}", user_name);
+    if let Err(exceeded) = limits.check(audit.address(), &user_name) {
+        eprintln!("Login refused: {}", exceeded.reason);
+        audit.record(
+            AuditEntry::new(AuditAction::Login, AuditOutcome::Limited)
+                .actor(&user_name, None)
+                .detail(exceeded.reason.as_str()),
+        );
+        return Err(exceeded.into());
+    }
+    let users = Arc::clone(&users);
+    let keys = Arc::clone(&keys);
+    let token =
+        tokio::task::spawn_blocking(move || security::login(user, users.as_ref(), &keys)).await;
+    let token = match token {
+        Ok(token) => token.map(Json).ok_or(Status::Unauthorized),
+        Err(_) => Err(Status::InternalServerError),
+    };
+    let outcome = if token.is_ok() {
+        AuditOutcome::Success
+    } else {
+        AuditOutcome::Failure
+    };
+    audit.record(AuditEntry::new(AuditAction::Login, outcome).actor(&user_name, None));
+    Ok(token)
+}
+
+/// HTTP handler that exchanges a refresh token for new tokens.
+///
+/// * refresh: `RefreshToken` - Refresh token returned with the last tokens.
+#[post("/refresh", format = "application/json", data = "<refresh>")]
+pub async fn refresh(
+    audit: request::Auditor<'_>,
+    refresh: Json<RefreshToken>,
+    users: State<'_, Arc<dyn UserStore>>,
+    revocations: State<'_, Arc<RevocationList>>,
+    keys: State<'_, Arc<SigningKeys>>,
+) -> Result<Json<Token>, Status> {
+    //! The user store and the revoked tokens are files, so they are
+    //! read and written out of the threads of the server.
+    let refresh_token = refresh.into_inner().refresh_token;
+    let users = Arc::clone(&users);
+    let revocations = Arc::clone(&revocations);
+    let keys = Arc::clone(&keys);
+    let token = tokio::task::spawn_blocking(move || {
+        security::refresh(&refresh_token, users.as_ref(), &revocations, &keys)
+    })
+    .await
+    .unwrap_or(None);
+    let outcome = if token.is_some() {
+        AuditOutcome::Success
     } else {
-        Err(Status::Unauthorized)
+        AuditOutcome::Failure
+    };
+    audit.record(AuditEntry::new(AuditAction::Refresh, outcome));
+    token.map(Json).ok_or(Status::Unauthorized)
+}
+
+/// HTTP handler that revokes the tokens of the user.
+///
+/// * refresh: `RefreshToken` - Refresh token returned with the access token.
+#[post("/logout", format = "application/json", data = "<refresh>")]
+pub async fn logout(
+    audit: request::Auditor<'_>,
+    claims: Claims,
+    refresh: Json<RefreshToken>,
+    revocations: State<'_, Arc<RevocationList>>,
+    keys: State<'_, Arc<SigningKeys>>,
+) -> Status {
+    //! The revoked tokens are written out of the threads of the server.
+    eprintln!("Logout user: {}", claims.user_name);
+    let entry = AuditEntry::new(AuditAction::Logout, AuditOutcome::Success)
+        .actor(&claims.user_name, Some(&claims.sub));
+    let refresh_token = refresh.into_inner().refresh_token;
+    let revocations = Arc::clone(&revocations);
+    let keys = Arc::clone(&keys);
+    let revoked = tokio::task::spawn_blocking(move || {
+        security::logout(&claims, &refresh_token, &revocations, &keys)
+    })
+    .await
+    .unwrap_or_else(|err| Err(io::Error::new(io::ErrorKind::Other, err)));
+    match revoked {
+        Ok(()) => {
+            audit.record(entry);
+            Status::NoContent
+        }
+        Err(err) => {
+            eprintln!("The revoked tokens can't be stored: {}", err);
+            audit.record(AuditEntry {
+                outcome: AuditOutcome::Failure,
+                ..entry
+            });
+            Status::InternalServerError
+        }
     }
 }
@@ -0,0 +1,34 @@
+use super::response;
+use crate::security::Caller;
+use async_stream::stream;
+use rocket::{
+    futures::Stream,
+    get,
+    response::stream::{Event, EventStream},
+    State,
+};
+use worker::JobManager;
+
+/// HTTP handler that streams the lifecycle events of the jobs that the
+/// role of the user can read as Server-Sent Events.  If the client
+/// doesn't keep up with them, a `lagged` event replaces the ones that
+/// have been dropped.
+#[get("/")]
+pub async fn stream(
+    caller: Caller,
+    jobs: State<'_, JobManager>,
+) -> EventStream<impl Stream<Item = Event>> {
+    eprintln!("caller: {}", caller.id);
+    let mut events = jobs.subscribe(caller.principal());
+    EventStream::from(stream! {
+        while let Some(event) = events.next().await {
+            let event = match event {
+                Ok(event) => response::Event::from(event),
+                Err(_) => response::Event::Lagged,
+            };
+            if let Ok(data) = serde_json::to_string(&event) {
+                yield Event::data(data).event(event.name());
+            }
+        }
+    })
+}
@@ -1,102 +1,620 @@
 use super::{request, response};
-use crate::{security::Claims, JobData};
-use rocket::{delete, get, http::Status, post, response::NamedFile, State};
+use crate::{
+    attach::AttachTickets,
+    audit::{redact_command_line, AuditAction, AuditEntry, AuditOutcome},
+    config::Config,
+    limits::{JobLimits, JobStorage},
+    security::Caller,
+    webhooks::{Delivery, Webhooks},
+};
+use rocket::{
+    data::{Data, ToByteUnit},
+    delete, get,
+    http::Status,
+    post, put,
+    response::{status::Custom, NamedFile},
+    State,
+};
 use rocket_contrib::{json::Json, uuid::Uuid};
-use worker::{Job, JobError, JobStatus};
+use std::{convert::TryFrom, fmt::Display, path::PathBuf, sync::Arc, time::Duration};
+use tokio::{io::AsyncReadExt, time};
+use worker::{Grantee, JobError, JobManager, JobOptions};
+
+const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_WAIT_TIMEOUT: Duration = Duration::from_secs(300);
+const STDIN_LIMIT_BYTES: u64 = 64 * 1024 * 1024;
+const STDIN_CHUNK_SIZE: usize = 8 * 1024;
 
 #[post("/", format = "application/json", data = "<new_job>")]
 pub async fn create(
-    claims: Claims,
+    caller: Caller,
+    audit: request::Auditor<'_>,
     new_job: Json<request::Job>,
-    jobs: State<'_, JobData>,
-) -> Status {
-    eprintln!("claim subject: {}", claims.sub);
-    eprintln!("New job: {:?}", new_job);
+    jobs: State<'_, JobManager>,
+    webhooks: State<'_, Webhooks>,
+    config: State<'_, Config>,
+    limits: State<'_, JobLimits>,
+) -> Result<Result<Status, Custom<Json<response::PolicyDenial>>>, response::TooManyRequests> {
+    //! Jobs are refused with `429` if the caller exceeds a rate limit
+    //! or its quota.  A job that is allowed reserves its place in the
+    //! quota, and it is released if the job isn't created.  The output
+    //! and the artifacts of the job are limited to the remaining quota,
+    //! that is reserved until the job finishes.  Every attempt is
+    //! audited with the redacted command line.
+    let new_job = new_job.into_inner();
+    create_job(
+        &caller, &audit, new_job, None, &jobs, &webhooks, &config, &limits,
+    )
+    .await
+}
+
+#[post("/", format = "application/octet-stream", data = "<stdin>")]
+pub async fn create_with_stdin(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    new_job: request::JobHeader,
+    stdin: Data,
+    jobs: State<'_, JobManager>,
+    webhooks: State<'_, Webhooks>,
+    config: State<'_, Config>,
+    limits: State<'_, JobLimits>,
+) -> Result<Result<Status, Custom<Json<response::PolicyDenial>>>, response::TooManyRequests> {
+    //! Jobs are created like with `create`, but described by the
+    //! `X-Job` header and with the body of the request as their stdin,
+    //! so it doesn't have to be encoded in the JSON.  A body longer than
+    //! the limit of the stdin is refused with `413`.
     let new_job = new_job.into_inner();
-    let mut jobs_map = jobs.write().unwrap();
-    if jobs_map.contains_key(&new_job.id.into_inner()) {
-        Status::Conflict
-    } else {
-        match Job::new(new_job.id.into_inner(), &claims.sub, &new_job.command_line) {
-            Ok(job) => {
-                jobs_map.insert(new_job.id.into_inner(), job);
-                Status::Created
+    create_job(
+        &caller,
+        &audit,
+        new_job,
+        Some(stdin),
+        &jobs,
+        &webhooks,
+        &config,
+        &limits,
+    )
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn create_job(
+    caller: &Caller,
+    audit: &request::Auditor<'_>,
+    new_job: request::Job,
+    upload: Option<Data>,
+    jobs: &JobManager,
+    webhooks: &Webhooks,
+    config: &Config,
+    limits: &JobLimits,
+) -> Result<Result<Status, Custom<Json<response::PolicyDenial>>>, response::TooManyRequests> {
+    eprintln!("caller: {}", caller.id);
+    let entry = |outcome| {
+        AuditEntry::new(AuditAction::JobCreate, outcome)
+            .actor(&caller.name, Some(&caller.id))
+            .target(&new_job.id)
+            .detail(redact_command_line(&new_job.command_line))
+    };
+    let job_id = new_job.id.into_inner();
+    let storage = JobStorage {
+        output_limit: config.output_limit(new_job.output_limit.map(worker::OutputLimit::from)),
+        // Jobs without artifacts don't need room for them.
+        max_artifact_bytes: if new_job.artifacts.is_empty() {
+            Some(0)
+        } else {
+            config.max_artifact_bytes
+        },
+    };
+    let storage = match limits
+        .check(jobs, caller.principal(), audit.address(), job_id, storage)
+        .await
+    {
+        Ok(storage) => storage,
+        Err(exceeded) => {
+            eprintln!("Job refused: {}", exceeded.reason);
+            audit.record(entry(AuditOutcome::Limited));
+            return Err(exceeded.into());
+        }
+    };
+    let entry = entry(AuditOutcome::Success);
+    let status = submit(caller, new_job, upload, storage, jobs, webhooks, config).await;
+    let outcome = match status {
+        Ok(status) if status == Status::Created => AuditOutcome::Success,
+        Ok(status) if status == Status::Forbidden => AuditOutcome::Denied,
+        Ok(_) => AuditOutcome::Failure,
+        // Denied by the command policy.
+        Err(_) => AuditOutcome::Denied,
+    };
+    audit.record(AuditEntry { outcome, ..entry });
+    limits
+        .release(&caller.id, job_id, outcome == AuditOutcome::Success)
+        .await;
+    Ok(status)
+}
+
+async fn submit(
+    caller: &Caller,
+    mut new_job: request::Job,
+    upload: Option<Data>,
+    storage: JobStorage,
+    jobs: &JobManager,
+    webhooks: &Webhooks,
+    config: &Config,
+) -> Result<Status, Custom<Json<response::PolicyDenial>>> {
+    // The request isn't logged, since it may contain secrets.
+    eprintln!("New job: {}", new_job.id);
+    let job_id = new_job.id.into_inner();
+    // The jobs run as another user, so they always have a workspace.
+    let working_dir = worker::Workspace::path(job_id);
+    if let Err(rule) = config.command_policy.check(
+        &caller.id,
+        &caller.name,
+        caller.role,
+        &new_job.command_line,
+        &working_dir,
+    ) {
+        eprintln!("Job denied by rule {}", rule);
+        return Err(Custom(
+            Status::Forbidden,
+            Json(response::PolicyDenial {
+                error: "Job denied by the command policy".to_string(),
+                rule,
+            }),
+        ));
+    }
+    if let Some(ref webhook) = new_job.webhook {
+        if !config.webhook_targets.allows_url(&webhook.url) {
+            return Ok(Status::BadRequest);
+        }
+    }
+    // The stdin is either inline or uploaded, but not both.
+    let stdin = match (new_job.stdin.take(), upload) {
+        (Some(encoded), None) => match base64::decode(encoded) {
+            Ok(stdin) => Some(stdin),
+            Err(_) => return Ok(Status::BadRequest),
+        },
+        (None, Some(upload)) => match read_stdin(upload).await {
+            Ok(stdin) => Some(stdin),
+            Err(status) => return Ok(status),
+        },
+        (Some(_), Some(_)) => return Ok(Status::BadRequest),
+        (None, None) => None,
+    };
+    let workspace = match new_job.workspace.take().map(worker::Workspace::try_from) {
+        Some(Ok(workspace)) => Some(workspace),
+        Some(Err(_)) => return Ok(Status::BadRequest),
+        None => None,
+    };
+    // Jobs never run as the user of the server.
+    let credentials = match config.job_user(&caller.id) {
+        Some(credentials) => credentials,
+        None => {
+            eprintln!("No Unix user for the jobs of {}", caller.id);
+            return Ok(Status::Forbidden);
+        }
+    };
+    let hardening = match config.hardening(new_job.hardening.map(worker::HardeningProfile::from)) {
+        Some(hardening) => hardening,
+        None => {
+            eprintln!(
+                "Hardening profile below the minimum: {:?}",
+                new_job.hardening
+            );
+            return Ok(Status::Forbidden);
+        }
+    };
+    let options = JobOptions {
+        stdin,
+        keep_stdin_open: new_job.stdin_open,
+        terminal: new_job.terminal.map(worker::TerminalSize::from),
+        output_limit: storage.output_limit,
+        output_compression: new_job
+            .output_compression
+            .map(worker::Compression::from)
+            .or(config.output_compression),
+        artifacts: new_job.artifacts.clone(),
+        artifacts_max_bytes: storage.max_artifact_bytes,
+        workspace,
+        credentials: Some(credentials),
+        hardening,
+        team: new_job.team.clone(),
+    };
+    if let Err(err) = jobs
+        .submit_with_options(job_id, caller.principal(), &new_job.command_line, options)
+        .await
+    {
+        return Ok(error_status(err));
+    }
+    if let Some(webhook) = new_job.webhook {
+        match jobs.completion(job_id, caller.principal()).await {
+            Ok(completion) => {
+                webhooks
+                    .notify_on_completion(job_id, webhook.into(), completion)
+                    .await
             }
-            Err(err) if matches!(err, JobError::CommandNotFound) => Status::BadRequest,
-            Err(err) if matches!(err, JobError::InvalidCommand(_)) => Status::BadRequest,
-            _ => Status::InternalServerError,
+            Err(err) => return Ok(error_status(err)),
         }
     }
+    Ok(Status::Created)
+}
+
+#[get("/")]
+pub async fn list(caller: Caller, jobs: State<'_, JobManager>) -> Json<Vec<response::Job>> {
+    eprintln!("caller: {}", caller.id);
+    let infos = jobs.list(caller.principal()).await;
+    Json(infos.into_iter().map(response::Job::from).collect())
 }
 
 #[get("/<job_id>")]
 pub async fn get(
-    claims: Claims,
+    caller: Caller,
+    audit: request::Auditor<'_>,
     job_id: Uuid,
-    jobs: State<'_, JobData>,
+    jobs: State<'_, JobManager>,
 ) -> Result<Json<response::JobStatus>, Status> {
-    eprintln!("claim subject: {}", claims.sub);
+    eprintln!("caller: {}", caller.id);
     eprintln!("Job to query: {:?}", job_id);
-    let mut jobs_map = jobs.write().unwrap();
-    if let Some(job) = jobs_map.get_mut(&job_id.into_inner()) {
-        match job.status(&claims.sub) {
-            Ok(status) => match status {
-                JobStatus::Failed | JobStatus::InProgress => Ok(Json(response::JobStatus {
-                    status: status.to_string(),
-                    exit_status: None,
-                })),
-                JobStatus::Done(exit_value) => Ok(Json(response::JobStatus {
-                    status: status.to_string(),
-                    exit_status: exit_value.code(),
-                })),
-            },
-            Err(err) if matches!(err, JobError::Unauthorized) => Err(Status::Forbidden),
-            _ => Err(Status::InternalServerError),
-        }
-    } else {
-        Err(Status::NotFound)
+    let job_id = job_id.into_inner();
+    let info = jobs.get(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &info);
+    match info {
+        Ok(info) => Ok(Json(response::JobStatus::from(info.status))),
+        Err(err) => Err(error_status(err)),
     }
 }
 
+#[get("/<job_id>/wait?<timeout>")]
+pub async fn wait(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    timeout: Option<String>,
+    jobs: State<'_, JobManager>,
+) -> Result<Json<response::JobStatus>, Status> {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to wait for: {:?}", job_id);
+    let timeout = match timeout {
+        Some(timeout) => timeout
+            .parse::<request::Timeout>()
+            .map_err(|_| Status::BadRequest)?
+            .0
+            .min(MAX_WAIT_TIMEOUT),
+        None => DEFAULT_WAIT_TIMEOUT,
+    };
+    let job_id = job_id.into_inner();
+    let completion = jobs.completion(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &completion);
+    let completion = completion.map_err(error_status)?;
+    // On timeout, the current status is returned so the client can
+    // wait again.
+    let status = match time::timeout(timeout, completion.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            jobs.get(job_id, caller.principal())
+                .await
+                .map_err(error_status)?
+                .status
+        }
+    };
+    Ok(Json(response::JobStatus::from(status)))
+}
+
 #[get("/<job_id>/output")]
 pub async fn get_output(
-    claims: Claims,
+    caller: Caller,
+    audit: request::Auditor<'_>,
     job_id: Uuid,
-    jobs: State<'_, JobData>,
-) -> Result<NamedFile, Status> {
-    eprintln!("claim subject: {}", claims.sub);
+    accept_encoding: request::AcceptEncoding,
+    jobs: State<'_, JobManager>,
+) -> Result<response::Output, Status> {
+    eprintln!("caller: {}", caller.id);
     eprintln!("Job to query: {:?}", job_id);
-    let filename: Result<String, Status> = {
-        let mut jobs_map = jobs.write().unwrap();
-        if let Some(job) = jobs_map.get_mut(&job_id.into_inner()) {
-            job.output(&claims.sub).map_err(|err| match err {
-                JobError::Unauthorized => Status::Forbidden,
-                _ => Status::InternalServerError,
-            })
-        } else {
-            Err(Status::NotFound)
+    let job_id = job_id.into_inner();
+    let file = jobs.output(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobOutput, job_id, &file);
+    let file = file.map_err(error_status)?;
+    response::Output::open(file, &accept_encoding)
+        .await
+        .map_err(|_| Status::InternalServerError)
+}
+
+#[delete("/<job_id>/output")]
+pub async fn delete_output(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    jobs: State<'_, JobManager>,
+) -> Status {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to purge: {:?}", job_id);
+    let job_id = job_id.into_inner();
+    let purged = jobs.purge_output(job_id, caller.principal()).await;
+    audit_job(
+        &audit,
+        &caller,
+        AuditAction::JobOutputDelete,
+        job_id,
+        &purged,
+    );
+    match purged {
+        Ok(()) => Status::Ok,
+        Err(err) => error_status(err),
+    }
+}
+
+#[get("/<job_id>/artifacts")]
+pub async fn list_artifacts(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    jobs: State<'_, JobManager>,
+) -> Result<Json<Vec<response::Artifact>>, Status> {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to query: {:?}", job_id);
+    let job_id = job_id.into_inner();
+    let artifacts = jobs.artifacts(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &artifacts);
+    let artifacts = artifacts.map_err(error_status)?;
+    Ok(Json(
+        artifacts
+            .into_iter()
+            .map(response::Artifact::from)
+            .collect(),
+    ))
+}
+
+// The segments guard rejects names that refer to parent directories,
+// but only the names collected by the job are served anyway.
+#[get("/<job_id>/artifacts/<name..>")]
+pub async fn get_artifact(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    name: PathBuf,
+    jobs: State<'_, JobManager>,
+) -> Result<NamedFile, Status> {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Artifact to download: {:?} {:?}", job_id, name);
+    let job_id = job_id.into_inner();
+    let path = jobs
+        .artifact(job_id, caller.principal(), &name.to_string_lossy())
+        .await;
+    audit_job(&audit, &caller, AuditAction::JobArtifact, job_id, &path);
+    let path = path.map_err(error_status)?;
+    NamedFile::open(path).await.map_err(|_| Status::NotFound)
+}
+
+#[post("/<job_id>/stdin?<close>", data = "<input>")]
+pub async fn write_stdin(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    close: Option<bool>,
+    input: Data,
+    jobs: State<'_, JobManager>,
+) -> Status {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to write to: {:?}", job_id);
+    let job_id = job_id.into_inner();
+    // The body is forwarded as it arrives, so the job can consume it
+    // while it is being uploaded.  One byte more than the limit is read
+    // to know if it has been exceeded.
+    let mut stream = input.open((STDIN_LIMIT_BYTES + 1).bytes());
+    let mut buffer = vec![0; STDIN_CHUNK_SIZE];
+    let mut received = 0;
+    let written = loop {
+        let read = match stream.read(&mut buffer).await {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(_) => return Status::BadRequest,
+        };
+        received += read as u64;
+        if received > STDIN_LIMIT_BYTES {
+            return Status::PayloadTooLarge;
+        }
+        let written = jobs
+            .write_stdin(job_id, caller.principal(), &buffer[..read])
+            .await;
+        if written.is_err() {
+            break written;
         }
     };
-    match filename {
-        Ok(filename) => NamedFile::open(&filename)
-            .await
-            .map_err(|_| Status::InternalServerError),
-        _ => Err(Status::NotFound),
+    let written = match written {
+        Ok(()) if close.unwrap_or(false) => jobs.close_stdin(job_id, caller.principal()).await,
+        written => written,
+    };
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &written);
+    match written {
+        Ok(()) => Status::Ok,
+        Err(err) => error_status(err),
     }
 }
 
+// Read the stdin uploaded for a new job.  It is refused with `413` if
+// it is longer than the limit, instead of being truncated.
+async fn read_stdin(upload: Data) -> Result<Vec<u8>, Status> {
+    let mut stdin = Vec::new();
+    upload
+        .open((STDIN_LIMIT_BYTES + 1).bytes())
+        .read_to_end(&mut stdin)
+        .await
+        .map_err(|_| Status::BadRequest)?;
+    if stdin.len() as u64 > STDIN_LIMIT_BYTES {
+        return Err(Status::PayloadTooLarge);
+    }
+    Ok(stdin)
+}
+
+/// HTTP handler that attaches to the terminal of a job.  The output
+/// produced from now on is kept for the WebSocket that redeems the
+/// returned ticket.
+#[post("/<job_id>/attach")]
+pub async fn attach(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    jobs: State<'_, JobManager>,
+    tickets: State<'_, Arc<AttachTickets>>,
+    config: State<'_, Config>,
+) -> Result<Json<response::AttachTicket>, Status> {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to attach to: {:?}", job_id);
+    let job_id = job_id.into_inner();
+    let attachment = jobs.attach(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &attachment);
+    let attachment = attachment.map_err(error_status)?;
+    Ok(Json(response::AttachTicket {
+        ticket: tickets.issue(job_id, caller.principal(), attachment),
+        port: config.attach_address().port(),
+    }))
+}
+
 #[delete("/<job_id>")]
-pub async fn delete(claims: Claims, job_id: Uuid, jobs: State<'_, JobData>) -> Status {
-    eprintln!("claim subject: {}", claims.sub);
+pub async fn delete(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    jobs: State<'_, JobManager>,
+) -> Status {
+    eprintln!("caller: {}", caller.id);
     eprintln!("Job to stop: {:?}", job_id);
-    let mut jobs_map = jobs.write().unwrap();
-    if let Some(job) = jobs_map.get_mut(&job_id.into_inner()) {
-        match job.stop(&claims.sub) {
-            Ok(()) => Status::Ok,
-            Err(err) if matches!(err, JobError::Unauthorized) => Status::Forbidden,
-            _ => Status::InternalServerError,
-        }
-    } else {
-        Status::NotFound
+    let job_id = job_id.into_inner();
+    let stopped = jobs.stop(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobStop, job_id, &stopped);
+    match stopped {
+        Ok(()) => Status::Ok,
+        Err(err) => error_status(err),
+    }
+}
+
+/// HTTP handler that shares a job with a user (`users`) or a team
+/// (`teams`), replacing the access previously granted to it.
+#[put(
+    "/<job_id>/shares/<kind>/<name>",
+    format = "application/json",
+    data = "<share>"
+)]
+pub async fn share(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    kind: String,
+    name: String,
+    share: Json<request::Share>,
+    jobs: State<'_, JobManager>,
+) -> Status {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to share: {:?}", job_id);
+    let grantee = match grantee(&kind, name) {
+        Some(grantee) => grantee,
+        None => return Status::NotFound,
+    };
+    let share = worker::Share {
+        grantee,
+        access: share.into_inner().access.into(),
+    };
+    let job_id = job_id.into_inner();
+    let shared = jobs.share(job_id, caller.principal(), share).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &shared);
+    match shared {
+        Ok(()) => Status::NoContent,
+        Err(err) => error_status(err),
+    }
+}
+
+/// HTTP handler that stops sharing a job with a user or a team.
+#[delete("/<job_id>/shares/<kind>/<name>")]
+pub async fn unshare(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    kind: String,
+    name: String,
+    jobs: State<'_, JobManager>,
+) -> Status {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to unshare: {:?}", job_id);
+    let grantee = match grantee(&kind, name) {
+        Some(grantee) => grantee,
+        None => return Status::NotFound,
+    };
+    let job_id = job_id.into_inner();
+    let unshared = jobs.unshare(job_id, caller.principal(), &grantee).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &unshared);
+    match unshared {
+        Ok(()) => Status::NoContent,
+        Err(err) => error_status(err),
+    }
+}
+
+#[get("/<job_id>/webhook/deliveries")]
+pub async fn get_webhook_deliveries(
+    caller: Caller,
+    audit: request::Auditor<'_>,
+    job_id: Uuid,
+    jobs: State<'_, JobManager>,
+    webhooks: State<'_, Webhooks>,
+) -> Result<Json<Vec<Delivery>>, Status> {
+    eprintln!("caller: {}", caller.id);
+    eprintln!("Job to query: {:?}", job_id);
+    let job_id = job_id.into_inner();
+    // Only users that can access the job can see its deliveries.
+    let info = jobs.get(job_id, caller.principal()).await;
+    audit_job(&audit, &caller, AuditAction::JobAccess, job_id, &info);
+    info.map_err(error_status)?;
+    webhooks
+        .deliveries(job_id)
+        .await
+        .map(Json)
+        .ok_or(Status::NotFound)
+}
+
+// The segment of the URL of a share that names the kind of grantee.
+fn grantee(kind: &str, name: String) -> Option<Grantee> {
+    match kind {
+        "users" => Some(Grantee::User(name)),
+        "teams" => Some(Grantee::Team(name)),
+        _ => None,
+    }
+}
+
+// Record an operation of the caller on a job in the audit log.  The
+// operations audited as `JobAccess` are only recorded when they are
+// denied.
+fn audit_job<T>(
+    audit: &request::Auditor<'_>,
+    caller: &Caller,
+    action: AuditAction,
+    job_id: impl Display,
+    result: &Result<T, JobError>,
+) {
+    let outcome = match result {
+        Ok(_) => AuditOutcome::Success,
+        Err(JobError::Unauthorized) => AuditOutcome::Denied,
+        Err(_) => AuditOutcome::Failure,
+    };
+    if action == AuditAction::JobAccess && outcome != AuditOutcome::Denied {
+        return;
+    }
+    audit.record(
+        AuditEntry::new(action, outcome)
+            .actor(&caller.name, Some(&caller.id))
+            .target(job_id),
+    );
+}
+
+// Translate the errors of the domain into HTTP status codes.
+fn error_status(err: JobError) -> Status {
+    match err {
+        JobError::CommandNotFound
+        | JobError::InvalidCommand(_)
+        | JobError::InvalidArtifactPattern(_)
+        | JobError::ArtifactsWithoutWorkspace
+        | JobError::InvalidWorkspaceArchive => Status::BadRequest,
+        JobError::Unauthorized => Status::Forbidden,
+        JobError::NotFound => Status::NotFound,
+        JobError::AlreadyExists | JobError::StdinClosed | JobError::NoTerminal => Status::Conflict,
+        JobError::IoError(_) | JobError::EventsLagged => Status::InternalServerError,
+        JobError::StdinTimeout => Status::ServiceUnavailable,
     }
 }
@@ -0,0 +1,12 @@
+use crate::security::{JwkSet, SigningKeys};
+use rocket::{get, State};
+use rocket_contrib::json::Json;
+use std::sync::Arc;
+
+/// HTTP handler that publishes the public keys that verify the tokens
+/// of the server.  There are none if the tokens are signed with a
+/// shared secret.
+#[get("/jwks.json")]
+pub async fn jwks(keys: State<'_, Arc<SigningKeys>>) -> Json<JwkSet> {
+    Json(keys.jwks().clone())
+}
@@ -0,0 +1,18 @@
+use super::response;
+use crate::{limits::JobLimits, security::Caller};
+use rocket::{get, State};
+use rocket_contrib::json::Json;
+use worker::JobManager;
+
+/// HTTP handler that returns the resources used by the jobs of the
+/// caller and the limits of its quota.
+#[get("/quota")]
+pub async fn quota(
+    caller: Caller,
+    jobs: State<'_, JobManager>,
+    limits: State<'_, JobLimits>,
+) -> Json<response::Quota> {
+    eprintln!("caller: {}", caller.id);
+    let usage = limits.usage(&jobs, caller.principal()).await;
+    Json(response::Quota::new(usage, limits.quota()))
+}
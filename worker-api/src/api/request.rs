@@ -1,3 +1,33 @@
+mod accept_encoding;
+mod access;
+mod api_key;
+mod auditor;
+mod client_address;
+mod compression;
+mod hardening_profile;
 mod job;
+mod job_header;
+mod output_limit;
+mod output_limit_action;
+mod share;
+mod terminal_size;
+mod timeout;
+mod webhook;
+mod workspace;
 
+pub use accept_encoding::AcceptEncoding;
+pub use access::Access;
+pub use api_key::ApiKey;
+pub use auditor::Auditor;
+pub use client_address::ClientAddress;
+pub use compression::Compression;
+pub use hardening_profile::HardeningProfile;
 pub use job::Job;
+pub use job_header::JobHeader;
+pub use output_limit::OutputLimit;
+pub use output_limit_action::OutputLimitAction;
+pub use share::Share;
+pub use terminal_size::TerminalSize;
+pub use timeout::Timeout;
+pub use webhook::Webhook;
+pub use workspace::Workspace;
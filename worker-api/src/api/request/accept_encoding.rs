@@ -0,0 +1,87 @@
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Content encodings accepted by the client in the `Accept-Encoding`
+/// header.
+#[derive(Debug, Default)]
+pub struct AcceptEncoding {
+    encodings: Vec<String>,
+}
+
+impl AcceptEncoding {
+    /// Parse the value of the header.  The encodings with a quality
+    /// of zero are explicitly rejected, so they are skipped.
+    pub fn parse(header: &str) -> AcceptEncoding {
+        let encodings = header
+            .split(',')
+            .filter_map(|item| {
+                let mut parts = item.split(';').map(str::trim);
+                let encoding = parts.next().filter(|encoding| !encoding.is_empty())?;
+                let rejected = parts.any(|param| {
+                    param
+                        .strip_prefix("q=")
+                        .and_then(|quality| quality.parse::<f32>().ok())
+                        .map_or(false, |quality| quality == 0.0)
+                });
+                if rejected {
+                    None
+                } else {
+                    Some(encoding.to_ascii_lowercase())
+                }
+            })
+            .collect();
+        AcceptEncoding { encodings }
+    }
+
+    /// Check if the client accepts the encoding.
+    pub fn accepts(&self, encoding: &str) -> bool {
+        self.encodings
+            .iter()
+            .any(|accepted| accepted == encoding || accepted == "*")
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AcceptEncoding {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let accept_encoding = request
+            .headers()
+            .get("Accept-Encoding")
+            .map(AcceptEncoding::parse)
+            .fold(AcceptEncoding::default(), |mut all, mut header| {
+                all.encodings.append(&mut header.encodings);
+                all
+            });
+        Outcome::Success(accept_encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listed_encodings_are_accepted() {
+        let accept_encoding = AcceptEncoding::parse("gzip, deflate;q=0.5, ZSTD");
+
+        assert!(accept_encoding.accepts("gzip"));
+        assert!(accept_encoding.accepts("zstd"));
+        assert!(!accept_encoding.accepts("br"));
+    }
+
+    #[test]
+    fn encodings_with_zero_quality_are_rejected() {
+        let accept_encoding = AcceptEncoding::parse("gzip;q=0, zstd;q=0.0");
+
+        assert!(!accept_encoding.accepts("gzip"));
+        assert!(!accept_encoding.accepts("zstd"));
+    }
+
+    #[test]
+    fn wildcard_accepts_any_encoding() {
+        let accept_encoding = AcceptEncoding::parse("*");
+
+        assert!(accept_encoding.accepts("zstd"));
+    }
+}
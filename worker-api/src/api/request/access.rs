@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    Read,
+    Stop,
+}
+
+impl From<Access> for worker::Access {
+    fn from(access: Access) -> Self {
+        match access {
+            Access::Read => worker::Access::Read,
+            Access::Stop => worker::Access::Stop,
+        }
+    }
+}
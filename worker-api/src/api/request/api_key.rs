@@ -0,0 +1,11 @@
+use crate::security::ApiKeyScope;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct ApiKey {
+    /// Description of the key, e.g. the CI system that uses it.
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Days until the key expires.  It is 90 if it isn't set.
+    pub expires_in_days: Option<u32>,
+}
@@ -0,0 +1,46 @@
+use super::ClientAddress;
+use crate::audit::{AuditEntry, AuditLog};
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+    State,
+};
+
+/// Audit log of the server with the address of the client of the
+/// request, that is recorded in its entries.
+pub struct Auditor<'r> {
+    log: &'r AuditLog,
+    address: String,
+}
+
+impl Auditor<'_> {
+    /// Address of the client of the request.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Record an action of the request in the audit log.
+    ///
+    /// * `entry` - Entry of the action, without the address.
+    pub fn record(&self, mut entry: AuditEntry) {
+        entry.address = self.address.clone();
+        self.log.record(&entry);
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Auditor<'r> {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let log = match request.guard::<State<'r, AuditLog>>().await {
+            Outcome::Success(log) => log.inner(),
+            _ => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+        let address = match request.guard::<ClientAddress>().await {
+            Outcome::Success(address) => address.0,
+            _ => return Outcome::Failure((Status::InternalServerError, ())),
+        };
+        Outcome::Success(Auditor { log, address })
+    }
+}
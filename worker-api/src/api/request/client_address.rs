@@ -0,0 +1,34 @@
+use crate::{config::Config, tls::ClientCertificates};
+use rocket::{
+    request::{FromRequest, Outcome, Request},
+    State,
+};
+use std::sync::Arc;
+
+/// Address of the client of a request, that identifies it in the rate
+/// limits and the audit log.  It is the address of the peer, or the one
+/// in `X-Real-IP` if the peer is a trusted proxy, or the one of the
+/// client of the gateway of mutual TLS, or `unknown` if the address
+/// can't be known.
+#[derive(Debug)]
+pub struct ClientAddress(pub String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientAddress {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let certificate = match request.guard::<State<'_, Arc<ClientCertificates>>>().await {
+            Outcome::Success(certificates) => certificates.find(request.remote()),
+            _ => None,
+        };
+        let peer = request.remote().map(|remote| remote.ip());
+        let address = match (certificate, request.guard::<State<'_, Config>>().await) {
+            (Some(certificate), _) => Some(certificate.address),
+            (None, Outcome::Success(config)) => config.client_address(peer, request.real_ip()),
+            (None, _) => peer,
+        };
+        let address = address.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+        Outcome::Success(ClientAddress(address))
+    }
+}
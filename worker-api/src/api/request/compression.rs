@@ -0,0 +1,17 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl From<Compression> for worker::Compression {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Gzip => worker::Compression::Gzip,
+            Compression::Zstd => worker::Compression::Zstd,
+        }
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum HardeningProfile {
+    None,
+    Default,
+    Strict,
+}
+
+impl From<HardeningProfile> for worker::HardeningProfile {
+    fn from(profile: HardeningProfile) -> Self {
+        match profile {
+            HardeningProfile::None => worker::HardeningProfile::None,
+            HardeningProfile::Default => worker::HardeningProfile::Default,
+            HardeningProfile::Strict => worker::HardeningProfile::Strict,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::{Compression, HardeningProfile, OutputLimit, TerminalSize, Webhook, Workspace};
 use rocket_contrib::uuid::Uuid;
 use serde::Deserialize;
 
@@ -5,4 +6,27 @@ use serde::Deserialize;
 pub struct Job {
     pub id: Uuid,
     pub command_line: String,
+    pub webhook: Option<Webhook>,
+    /// Base64 encoded content that is written to the stdin of the job.
+    pub stdin: Option<String>,
+    /// Keep the stdin open to write to it with the stdin endpoint.
+    #[serde(default)]
+    pub stdin_open: bool,
+    /// Run the job in a pseudo terminal of this size.
+    pub terminal: Option<TerminalSize>,
+    /// Maximum size of the stored output and what to do when reached.
+    pub output_limit: Option<OutputLimit>,
+    /// Compress the output once the job has finished.
+    pub output_compression: Option<Compression>,
+    /// Glob patterns of the files collected when the job finishes.
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+    /// Run the job in a new temporary directory, optionally populated
+    /// from a tar archive.
+    pub workspace: Option<Workspace>,
+    /// Resource limits and seccomp filter applied to the job.
+    pub hardening: Option<HardeningProfile>,
+    /// Team that owns the job together with the user.  The user must
+    /// be one of its members.
+    pub team: Option<String>,
 }
@@ -0,0 +1,60 @@
+use super::Job;
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+};
+
+/// Job described by the JSON of the `X-Job` header, for the requests
+/// whose body is the stdin of the job.  The requests without it or with
+/// an invalid one are refused with `400`.
+#[derive(Debug)]
+pub struct JobHeader(Job);
+
+impl JobHeader {
+    /// Parse the value of the header.
+    pub fn parse(header: &str) -> Option<JobHeader> {
+        serde_json::from_str(header).ok().map(JobHeader)
+    }
+
+    /// Job described by the header.
+    pub fn into_inner(self) -> Job {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for JobHeader {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        match request
+            .headers()
+            .get_one("X-Job")
+            .and_then(JobHeader::parse)
+        {
+            Some(job) => Outcome::Success(job),
+            None => Outcome::Failure((Status::BadRequest, ())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_are_parsed_from_json() {
+        let header = r#"{"id":"67e55044-10b1-426f-9247-bb680e5fe0c8","command_line":"wc -l"}"#;
+
+        let job = JobHeader::parse(header).unwrap().into_inner();
+
+        assert_eq!("wc -l", job.command_line);
+        assert!(job.stdin.is_none());
+    }
+
+    #[test]
+    fn invalid_jobs_are_refused() {
+        assert!(JobHeader::parse(r#"{"command_line":"wc -l"}"#).is_none());
+        assert!(JobHeader::parse("wc -l").is_none());
+    }
+}
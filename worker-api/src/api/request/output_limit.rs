@@ -0,0 +1,18 @@
+use super::OutputLimitAction;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct OutputLimit {
+    pub max_bytes: u64,
+    #[serde(default)]
+    pub action: OutputLimitAction,
+}
+
+impl From<OutputLimit> for worker::OutputLimit {
+    fn from(limit: OutputLimit) -> Self {
+        worker::OutputLimit {
+            max_bytes: limit.max_bytes,
+            action: limit.action.into(),
+        }
+    }
+}
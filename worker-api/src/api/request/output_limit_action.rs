@@ -0,0 +1,23 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputLimitAction {
+    TruncateHead,
+    StopJob,
+}
+
+impl Default for OutputLimitAction {
+    fn default() -> Self {
+        OutputLimitAction::TruncateHead
+    }
+}
+
+impl From<OutputLimitAction> for worker::OutputLimitAction {
+    fn from(action: OutputLimitAction) -> Self {
+        match action {
+            OutputLimitAction::TruncateHead => worker::OutputLimitAction::TruncateHead,
+            OutputLimitAction::StopJob => worker::OutputLimitAction::StopJob,
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use super::Access;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct Share {
+    /// Operations that the user or the team can perform on the job.
+    pub access: Access,
+}
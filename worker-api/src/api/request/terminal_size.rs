@@ -0,0 +1,16 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct TerminalSize {
+    pub rows: u16,
+    pub cols: u16,
+}
+
+impl From<TerminalSize> for worker::TerminalSize {
+    fn from(size: TerminalSize) -> Self {
+        worker::TerminalSize {
+            rows: size.rows,
+            cols: size.cols,
+        }
+    }
+}
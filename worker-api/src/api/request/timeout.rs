@@ -0,0 +1,56 @@
+use std::{str::FromStr, time::Duration};
+
+/// Duration expressed as a number followed by an optional unit: `ms`,
+/// `s` (default) or `m`.  For example: `60s`, `500ms` or `2m`.
+#[derive(Debug, PartialEq)]
+pub struct Timeout(pub Duration);
+
+impl FromStr for Timeout {
+    type Err = ();
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let unit_start = value
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(value.len());
+        let (amount, unit) = value.split_at(unit_start);
+        let amount: u64 = amount.parse().map_err(|_| ())?;
+        let duration = match unit {
+            "ms" => Duration::from_millis(amount),
+            "" | "s" => Duration::from_secs(amount),
+            "m" => Duration::from_secs(amount.checked_mul(60).ok_or(())?),
+            _ => return Err(()),
+        };
+        Ok(Timeout(duration))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units_are_parsed() {
+        assert_eq!(Ok(Timeout(Duration::from_millis(500))), "500ms".parse());
+        assert_eq!(Ok(Timeout(Duration::from_secs(60))), "60s".parse());
+        assert_eq!(Ok(Timeout(Duration::from_secs(120))), "2m".parse());
+    }
+
+    #[test]
+    fn seconds_are_the_default_unit() {
+        assert_eq!(Ok(Timeout(Duration::from_secs(30))), "30".parse());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert_eq!(Err(()), "".parse::<Timeout>());
+        assert_eq!(Err(()), "s".parse::<Timeout>());
+        assert_eq!(Err(()), "10h".parse::<Timeout>());
+        assert_eq!(Err(()), "-5s".parse::<Timeout>());
+    }
+
+    #[test]
+    fn minutes_that_overflow_are_rejected() {
+        assert_eq!(Err(()), format!("{}m", u64::MAX).parse::<Timeout>());
+    }
+}
@@ -0,0 +1,27 @@
+use serde::Deserialize;
+use std::fmt::{self, Debug, Formatter};
+
+#[derive(Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    pub secret: String,
+}
+
+// The secret must not be logged.
+impl Debug for Webhook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Webhook")
+            .field("url", &self.url)
+            .field("secret", &"<redacted>")
+            .finish()
+    }
+}
+
+impl From<Webhook> for crate::webhooks::Webhook {
+    fn from(webhook: Webhook) -> Self {
+        crate::webhooks::Webhook {
+            url: webhook.url,
+            secret: webhook.secret,
+        }
+    }
+}
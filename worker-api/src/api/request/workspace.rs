@@ -0,0 +1,31 @@
+use serde::Deserialize;
+use std::{
+    convert::TryFrom,
+    fmt::{self, Debug},
+};
+
+#[derive(Deserialize)]
+pub struct Workspace {
+    /// Base64 encoded tar archive that is extracted in the workspace.
+    pub archive: Option<String>,
+}
+
+// New jobs are logged, so only the size of the archive is shown.
+impl Debug for Workspace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Workspace")
+            .field("archive_len", &self.archive.as_ref().map(String::len))
+            .finish()
+    }
+}
+
+impl TryFrom<Workspace> for worker::Workspace {
+    type Error = base64::DecodeError;
+
+    fn try_from(workspace: Workspace) -> Result<Self, Self::Error> {
+        match workspace.archive {
+            Some(encoded) => Ok(worker::Workspace::Tar(base64::decode(encoded)?)),
+            None => Ok(worker::Workspace::Empty),
+        }
+    }
+}
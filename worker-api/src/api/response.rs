@@ -1,3 +1,27 @@
+mod api_key;
+mod artifact;
+mod attach_ticket;
+mod event;
+mod job;
 mod job_status;
+mod new_api_key;
+mod output;
+mod policy_denial;
+mod quota;
+mod quota_usage;
+mod share;
+mod too_many_requests;
 
+pub use api_key::ApiKey;
+pub use artifact::Artifact;
+pub use attach_ticket::AttachTicket;
+pub use event::Event;
+pub use job::Job;
 pub use job_status::JobStatus;
+pub use new_api_key::NewApiKey;
+pub use output::Output;
+pub use policy_denial::PolicyDenial;
+pub use quota::Quota;
+pub use quota_usage::QuotaUsage;
+pub use share::Share;
+pub use too_many_requests::TooManyRequests;
@@ -0,0 +1,25 @@
+use crate::security::{ApiKeyRecord, ApiKeyScope};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Creation time in seconds since the Unix epoch.
+    pub created: usize,
+    /// Expiration time in seconds since the Unix epoch.
+    pub expires: usize,
+}
+
+impl From<ApiKeyRecord> for ApiKey {
+    fn from(record: ApiKeyRecord) -> Self {
+        ApiKey {
+            id: record.id,
+            name: record.name,
+            scopes: record.scopes,
+            created: record.created,
+            expires: record.expires,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Artifact {
+    pub name: String,
+    pub size: u64,
+}
+
+impl From<worker::Artifact> for Artifact {
+    fn from(artifact: worker::Artifact) -> Self {
+        Artifact {
+            name: artifact.name,
+            size: artifact.size,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use serde::Serialize;
+
+/// Ticket that authorizes a WebSocket to attach to the terminal of a
+/// job.  The WebSocket is served on this port of the same host.
+#[derive(Serialize)]
+pub struct AttachTicket {
+    pub ticket: String,
+    pub port: u16,
+}
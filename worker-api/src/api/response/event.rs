@@ -0,0 +1,99 @@
+use serde::Serialize;
+use uuid::Uuid;
+use worker::{JobEvent, OutputStream};
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Started {
+        id: Uuid,
+    },
+    /// The data is base64 encoded, so any bytes are sent unaltered.
+    Output {
+        id: Uuid,
+        stream: &'static str,
+        data: String,
+    },
+    Stopped {
+        id: Uuid,
+    },
+    Finished {
+        id: Uuid,
+        status: String,
+        exit_status: Option<i32>,
+        signal: Option<i32>,
+    },
+    Removed {
+        id: Uuid,
+    },
+    /// Some events have been dropped because the client didn't keep up
+    /// with them.
+    Lagged,
+}
+
+impl Event {
+    /// Name of the event in the Server-Sent Events stream.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Started { .. } => "started",
+            Event::Output { .. } => "output",
+            Event::Stopped { .. } => "stopped",
+            Event::Finished { .. } => "finished",
+            Event::Removed { .. } => "removed",
+            Event::Lagged => "lagged",
+        }
+    }
+}
+
+impl From<JobEvent> for Event {
+    fn from(event: JobEvent) -> Self {
+        match event {
+            JobEvent::Started { id, .. } => Event::Started { id },
+            JobEvent::Output {
+                id, stream, data, ..
+            } => Event::Output {
+                id,
+                stream: match stream {
+                    OutputStream::Stdout => "stdout",
+                    OutputStream::Stderr => "stderr",
+                },
+                data: base64::encode(&data),
+            },
+            JobEvent::Stopped { id, .. } => Event::Stopped { id },
+            JobEvent::Finished { id, status, .. } => {
+                let status = super::JobStatus::from(status);
+                Event::Finished {
+                    id,
+                    status: status.status,
+                    exit_status: status.exit_status,
+                    signal: status.signal,
+                }
+            }
+            JobEvent::Removed { id, .. } => Event::Removed { id },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_that_is_not_text_is_sent_unaltered() {
+        // The first half of 'é' and an invalid byte.
+        let data = vec![0xc3, 0xff];
+        let event = Event::from(JobEvent::Output {
+            id: Uuid::new_v4(),
+            owner: "owner".to_string(),
+            stream: OutputStream::Stdout,
+            data: data.clone(),
+        });
+
+        match event {
+            Event::Output { data: encoded, .. } => {
+                assert_eq!(data, base64::decode(encoded).unwrap())
+            }
+            _ => panic!("Not an output event"),
+        }
+    }
+}
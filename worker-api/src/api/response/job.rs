@@ -0,0 +1,31 @@
+use serde::Serialize;
+use uuid::Uuid;
+use worker::JobInfo;
+
+#[derive(Serialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub owner: String,
+    pub team: Option<String>,
+    pub shares: Vec<super::Share>,
+    pub command_line: String,
+    pub status: String,
+    pub exit_status: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl From<JobInfo> for Job {
+    fn from(info: JobInfo) -> Self {
+        let status = super::JobStatus::from(info.status);
+        Job {
+            id: info.id,
+            owner: info.owner,
+            team: info.team,
+            shares: info.shares.into_iter().map(super::Share::from).collect(),
+            command_line: info.command_line,
+            status: status.status,
+            exit_status: status.exit_status,
+            signal: status.signal,
+        }
+    }
+}
@@ -1,7 +1,23 @@
 use serde::Serialize;
+use std::os::unix::process::ExitStatusExt;
 
 #[derive(Serialize)]
 pub struct JobStatus {
     pub status: String,
     pub exit_status: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl From<worker::JobStatus> for JobStatus {
+    fn from(status: worker::JobStatus) -> Self {
+        let (exit_status, signal) = match status {
+            worker::JobStatus::Failed | worker::JobStatus::InProgress => (None, None),
+            worker::JobStatus::Done(exit_value) => (exit_value.code(), exit_value.signal()),
+        };
+        JobStatus {
+            status: status.to_string(),
+            exit_status,
+            signal,
+        }
+    }
 }
@@ -0,0 +1,10 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct NewApiKey {
+    #[serde(flatten)]
+    pub api_key: super::ApiKey,
+    /// Key sent as `Authorization: ApiKey <key>`.  It is only returned
+    /// when the key is created.
+    pub key: String,
+}
@@ -0,0 +1,87 @@
+use crate::api::request::AcceptEncoding;
+use rocket::{
+    http::ContentType,
+    request::Request,
+    response::{self, Responder, Response},
+};
+use std::io::{self, Read};
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncWriteExt, DuplexStream},
+    runtime::Handle,
+};
+use worker::{Compression, OutputFile};
+
+const DECOMPRESSION_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Output of a job sent as plain text.  A compressed output is sent as
+/// it is stored, with its `Content-Encoding`, when the client accepts
+/// it and it is decompressed on the fly otherwise.
+pub struct Output {
+    body: Box<dyn AsyncRead + Send + Unpin>,
+    encoding: Option<Compression>,
+}
+
+impl Output {
+    /// Open the file of the output.
+    ///
+    /// * `file` - File that contains the output of the job.
+    /// * `accept_encoding` - Encodings accepted by the client.
+    pub async fn open(file: OutputFile, accept_encoding: &AcceptEncoding) -> io::Result<Output> {
+        let content = File::open(&file.path).await?;
+        Ok(match file.compression {
+            Some(compression) if accept_encoding.accepts(compression.encoding()) => Output {
+                body: Box::new(content),
+                encoding: Some(compression),
+            },
+            Some(compression) => Output {
+                body: Box::new(Self::decompress(content.into_std().await, compression)),
+                encoding: None,
+            },
+            None => Output {
+                body: Box::new(content),
+                encoding: None,
+            },
+        })
+    }
+
+    // Decompress the file in a blocking task that writes to a pipe,
+    // so the whole output is never kept in memory.
+    fn decompress(file: std::fs::File, compression: Compression) -> DuplexStream {
+        let (reader, mut writer) = tokio::io::duplex(DECOMPRESSION_BUFFER_SIZE);
+        let handle = Handle::current();
+        tokio::task::spawn_blocking(move || {
+            let mut decoder = match compression.decoder(file) {
+                Ok(decoder) => decoder,
+                Err(_) => return,
+            };
+            let mut buffer = vec![0; DECOMPRESSION_BUFFER_SIZE];
+            loop {
+                match decoder.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => {
+                        // The client may have closed the connection.
+                        if handle.block_on(writer.write_all(&buffer[..read])).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        reader
+    }
+}
+
+impl<'r> Responder<'r, 'static> for Output {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response
+            .header(ContentType::Plain)
+            .raw_header("Vary", "Accept-Encoding")
+            .streamed_body(self.body);
+        if let Some(compression) = self.encoding {
+            response.raw_header("Content-Encoding", compression.encoding());
+        }
+        response.ok()
+    }
+}
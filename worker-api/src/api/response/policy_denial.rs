@@ -0,0 +1,9 @@
+use serde::Serialize;
+
+/// Body of the response when a job is denied by the command policy.
+#[derive(Serialize)]
+pub struct PolicyDenial {
+    pub error: String,
+    /// Identifier of the rule that denied the job.
+    pub rule: String,
+}
@@ -0,0 +1,25 @@
+use super::QuotaUsage;
+use crate::limits::{Quota as Limits, Usage};
+use serde::Serialize;
+
+/// Body of the response with the usage of the quota of a user.
+#[derive(Serialize)]
+pub struct Quota {
+    pub concurrent_jobs: QuotaUsage,
+    pub jobs_today: QuotaUsage,
+    pub output_bytes: QuotaUsage,
+}
+
+impl Quota {
+    /// Usage of each resource against its limit.
+    ///
+    /// * `usage` - Resources used by the jobs of the user.
+    /// * `limits` - Quota of the user.
+    pub fn new(usage: Usage, limits: &Limits) -> Self {
+        Quota {
+            concurrent_jobs: QuotaUsage::new(usage.concurrent_jobs, limits.max_concurrent_jobs),
+            jobs_today: QuotaUsage::new(usage.jobs_today, limits.max_jobs_per_day),
+            output_bytes: QuotaUsage::new(usage.output_bytes, limits.max_output_bytes),
+        }
+    }
+}
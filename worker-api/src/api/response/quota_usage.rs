@@ -0,0 +1,14 @@
+use serde::Serialize;
+
+/// Usage of a resource and its limit, if any.
+#[derive(Serialize)]
+pub struct QuotaUsage {
+    pub used: u64,
+    pub limit: Option<u64>,
+}
+
+impl QuotaUsage {
+    pub fn new(used: u64, limit: Option<u64>) -> Self {
+        QuotaUsage { used, limit }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Share {
+    /// `user` or `team`.
+    pub kind: String,
+    /// Id of the user or name of the team.
+    pub name: String,
+    /// `read` or `stop`.
+    pub access: String,
+}
+
+impl From<worker::Share> for Share {
+    fn from(share: worker::Share) -> Self {
+        let (kind, name) = match share.grantee {
+            worker::Grantee::User(id) => ("user", id),
+            worker::Grantee::Team(team) => ("team", team),
+        };
+        let access = match share.access {
+            worker::Access::Read => "read",
+            worker::Access::Stop => "stop",
+        };
+        Share {
+            kind: kind.to_string(),
+            name,
+            access: access.to_string(),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use crate::limits::LimitExceeded;
+use rocket::{
+    http::Status,
+    request::Request,
+    response::{self, Responder, Response},
+};
+use rocket_contrib::json::Json;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Response (`429`) to a request refused by a rate limit or a quota.
+/// `Retry-After` tells the client when it can be retried.
+pub struct TooManyRequests {
+    pub error: String,
+    pub retry_after: Duration,
+}
+
+#[derive(Serialize)]
+struct Body {
+    error: String,
+    retry_after_secs: u64,
+}
+
+impl From<LimitExceeded> for TooManyRequests {
+    fn from(exceeded: LimitExceeded) -> Self {
+        TooManyRequests {
+            error: exceeded.reason,
+            retry_after: exceeded.retry_after,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for TooManyRequests {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        // A partial second is rounded up, so it isn't retried too soon.
+        let mut retry_after_secs = self.retry_after.as_secs();
+        if self.retry_after.subsec_nanos() > 0 || retry_after_secs == 0 {
+            retry_after_secs += 1;
+        }
+        let body = Json(Body {
+            error: self.error,
+            retry_after_secs,
+        });
+        Response::build_from(body.respond_to(request)?)
+            .status(Status::TooManyRequests)
+            .raw_header("Retry-After", retry_after_secs.to_string())
+            .ok()
+    }
+}
@@ -0,0 +1,7 @@
+mod attach_server;
+mod attach_ticket;
+mod attach_tickets;
+
+pub use attach_server::AttachServer;
+pub use attach_ticket::AttachTicket;
+pub use attach_tickets::AttachTickets;
@@ -0,0 +1,230 @@
+use super::{AttachTicket, AttachTickets};
+use crate::{
+    api::{request, response},
+    tls,
+};
+use futures_util::{SinkExt, StreamExt};
+use std::{io, net::SocketAddr, path::Path, sync::Arc, time::Duration};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    time,
+};
+use tokio_rustls::{rustls, TlsAcceptor};
+use tokio_tungstenite::{
+    tungstenite::{
+        self,
+        handshake::server::{ErrorResponse, Request, Response},
+        http::StatusCode,
+        protocol::{frame::coding::CloseCode, CloseFrame},
+        Message,
+    },
+    WebSocketStream,
+};
+use worker::{JobError, JobManager, Principal};
+
+/// Time to complete the TLS and WebSocket handshakes.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time that the client has to answer the close of the connection.
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Socket = WebSocketStream<tokio_rustls::server::TlsStream<TcpStream>>;
+
+/// Server of the WebSockets that attach to the terminals of the jobs
+/// (`GET /v1/jobs/<job_uuid>/attach?ticket=<secret>`).  The version of
+/// Rocket that is used can't upgrade its connections, so it listens on
+/// its own address with the same TLS certificate.  The connections are
+/// authorized with the tickets issued by the HTTP API.
+///
+/// The binary messages of the client are written to the terminal and
+/// its text messages are JSON sizes (`{"rows": 50, "cols": 132}`) of
+/// the terminal.  The server sends the output of the terminal in
+/// binary messages and, when the job finishes, its JSON status in a
+/// text message before closing the connection.  If the client doesn't
+/// keep up with the output, the connection is closed with `1011`
+/// instead of dropping part of it.
+pub struct AttachServer {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    jobs: JobManager,
+    tickets: Arc<AttachTickets>,
+}
+
+impl AttachServer {
+    /// Listen on the address with the given certificate and key.
+    ///
+    /// * `address` - Address where the WebSockets are accepted.
+    /// * `certs` - PEM file with the certificate chain of the server.
+    /// * `key` - PEM file with the private key of the server.
+    /// * `jobs` - Jobs whose terminals are attached.
+    /// * `tickets` - Tickets issued by the HTTP API.
+    pub async fn bind(
+        address: SocketAddr,
+        certs: &Path,
+        key: &Path,
+        jobs: JobManager,
+        tickets: Arc<AttachTickets>,
+    ) -> io::Result<AttachServer> {
+        Ok(AttachServer {
+            listener: TcpListener::bind(address).await?,
+            acceptor: Self::acceptor(certs, key)?,
+            jobs,
+            tickets,
+        })
+    }
+
+    /// Accept connections until the server is dropped.  Every
+    /// connection is served in its own task.
+    pub async fn run(self) {
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(_) => continue,
+            };
+            let acceptor = self.acceptor.clone();
+            let jobs = self.jobs.clone();
+            let tickets = Arc::clone(&self.tickets);
+            tokio::spawn(async move {
+                let accepted =
+                    time::timeout(HANDSHAKE_TIMEOUT, Self::accept(stream, acceptor, &tickets))
+                        .await;
+                // Connections without a valid ticket are just closed.
+                if let Ok(Ok((socket, ticket))) = accepted {
+                    let _ = Self::serve(socket, ticket, &jobs).await;
+                }
+            });
+        }
+    }
+
+    fn acceptor(certs: &Path, key: &Path) -> io::Result<TlsAcceptor> {
+        let config = rustls::ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(tls::certificates(certs)?, tls::private_key(key)?)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+
+    // Complete the handshakes, redeeming the ticket of the request
+    // before accepting it.  The type of the refusal is defined by
+    // tungstenite.
+    #[allow(clippy::result_large_err)]
+    async fn accept(
+        stream: TcpStream,
+        acceptor: TlsAcceptor,
+        tickets: &AttachTickets,
+    ) -> Result<(Socket, AttachTicket), tungstenite::Error> {
+        let stream = acceptor.accept(stream).await?;
+        let mut ticket = None;
+        let socket =
+            tokio_tungstenite::accept_hdr_async(stream, |request: &Request, response: Response| {
+                ticket = Self::redeem(tickets, request);
+                if ticket.is_some() {
+                    Ok(response)
+                } else {
+                    let mut refusal = ErrorResponse::new(None);
+                    *refusal.status_mut() = StatusCode::UNAUTHORIZED;
+                    Err(refusal)
+                }
+            })
+            .await?;
+        match ticket {
+            Some(ticket) => Ok((socket, ticket)),
+            None => Err(tungstenite::Error::ConnectionClosed),
+        }
+    }
+
+    fn redeem(tickets: &AttachTickets, request: &Request) -> Option<AttachTicket> {
+        let job_id = request
+            .uri()
+            .path()
+            .strip_prefix("/v1/jobs/")?
+            .strip_suffix("/attach")?
+            .parse()
+            .ok()?;
+        let secret = request
+            .uri()
+            .query()?
+            .split('&')
+            .find_map(|parameter| parameter.strip_prefix("ticket="))?;
+        tickets.redeem(secret, job_id)
+    }
+
+    // Copy the output of the terminal to the socket and the messages
+    // of the socket to the terminal until the terminal is closed or
+    // the client leaves.
+    async fn serve(
+        socket: Socket,
+        ticket: AttachTicket,
+        jobs: &JobManager,
+    ) -> Result<(), tungstenite::Error> {
+        let AttachTicket {
+            job_id,
+            user_id,
+            role,
+            teams,
+            mut attachment,
+            ..
+        } = ticket;
+        let as_user = Principal {
+            id: &user_id,
+            role,
+            teams: &teams,
+        };
+        let (mut sink, mut source) = socket.split();
+        loop {
+            tokio::select! {
+                output = attachment.next() => match output {
+                    Some(data) => sink.send(Message::Binary(data)).await?,
+                    None => break,
+                },
+                message = source.next() => {
+                    // Input for a terminal that is being closed is lost.
+                    let result = match message {
+                        Some(Ok(Message::Binary(data))) => {
+                            jobs.write_stdin(job_id, as_user, &data).await
+                        }
+                        Some(Ok(Message::Text(text))) => {
+                            match serde_json::from_str::<request::TerminalSize>(&text) {
+                                Ok(size) => jobs.resize_terminal(job_id, as_user, size.into()).await,
+                                Err(_) => Ok(()),
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                        Some(Ok(_)) => Ok(()),
+                    };
+                    // The access to the job may have been revoked.
+                    if let Err(JobError::Unauthorized) = result {
+                        sink.send(Self::close(CloseCode::Policy, "Unauthorized operation"))
+                            .await?;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+        let last = if attachment.lagged() {
+            Self::close(CloseCode::Error, "Attachment fell behind the output")
+        } else {
+            let status = match jobs.completion(job_id, as_user).await {
+                Ok(completion) => response::JobStatus::from(completion.wait().await),
+                Err(_) => return Ok(()),
+            };
+            let status = serde_json::to_string(&status).expect("The status can't be serialized");
+            sink.send(Message::Text(status)).await?;
+            Self::close(CloseCode::Normal, "Job finished")
+        };
+        sink.send(last).await?;
+        // Wait for the client to answer the close.
+        let _ = time::timeout(CLOSE_TIMEOUT, async {
+            while let Some(Ok(_)) = source.next().await {}
+        })
+        .await;
+        Ok(())
+    }
+
+    fn close(code: CloseCode, reason: &'static str) -> Message {
+        Message::Close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        }))
+    }
+}
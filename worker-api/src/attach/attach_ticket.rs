@@ -0,0 +1,27 @@
+use std::time::Instant;
+use uuid::Uuid;
+use worker::{JobAttachment, Principal, Role};
+
+/// Attachment to the terminal of a job that has been authorized by the
+/// HTTP API and is waiting for its WebSocket.  The user is kept, so
+/// the input and the resizes are authorized on its behalf.
+pub struct AttachTicket {
+    pub job_id: Uuid,
+    pub user_id: String,
+    pub role: Role,
+    pub teams: Vec<String>,
+    pub attachment: JobAttachment,
+    pub expires_at: Instant,
+}
+
+impl AttachTicket {
+    /// User whose operations on the job are authorized for this
+    /// ticket.
+    pub fn principal(&self) -> Principal<'_> {
+        Principal {
+            id: &self.user_id,
+            role: self.role,
+            teams: &self.teams,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use super::AttachTicket;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+use worker::{JobAttachment, Principal};
+
+/// Time that a ticket can be used to open its WebSocket.
+const TICKET_LIFETIME: Duration = Duration::from_secs(30);
+
+/// Tickets that authorize a WebSocket to attach to the terminal of a
+/// job.  They are issued by the HTTP API, which authenticates and
+/// audits the caller, and they can only be redeemed once, before they
+/// expire.
+#[derive(Default)]
+pub struct AttachTickets {
+    tickets: Mutex<HashMap<String, AttachTicket>>,
+}
+
+impl AttachTickets {
+    /// Create an empty collection of tickets.
+    pub fn new() -> AttachTickets {
+        Self::default()
+    }
+
+    /// Issue a ticket for an attachment and return its secret.  The
+    /// output produced until it is redeemed is kept in the attachment.
+    ///
+    /// * `job_id` - UUID of the job whose terminal is attached.
+    /// * `as_user` - User that has been authorized to attach.
+    /// * `attachment` - Attachment to the terminal of the job.
+    pub fn issue(&self, job_id: Uuid, as_user: Principal<'_>, attachment: JobAttachment) -> String {
+        self.issue_at(job_id, as_user, attachment, Instant::now())
+    }
+
+    /// Take the ticket with the given secret if it is for the job and
+    /// it hasn't expired.
+    ///
+    /// * `secret` - Secret returned when the ticket was issued.
+    /// * `job_id` - UUID of the job whose terminal is attached.
+    pub fn redeem(&self, secret: &str, job_id: Uuid) -> Option<AttachTicket> {
+        self.redeem_at(secret, job_id, Instant::now())
+    }
+
+    fn issue_at(
+        &self,
+        job_id: Uuid,
+        as_user: Principal<'_>,
+        attachment: JobAttachment,
+        now: Instant,
+    ) -> String {
+        let secret = hex::encode(rand::random::<[u8; 32]>());
+        let mut tickets = self.tickets.lock().unwrap();
+        // Dropping the expired tickets ends their attachments.
+        tickets.retain(|_, ticket| ticket.expires_at > now);
+        tickets.insert(
+            secret.clone(),
+            AttachTicket {
+                job_id,
+                user_id: as_user.id.to_string(),
+                role: as_user.role,
+                teams: as_user.teams.to_vec(),
+                attachment,
+                expires_at: now + TICKET_LIFETIME,
+            },
+        );
+        secret
+    }
+
+    fn redeem_at(&self, secret: &str, job_id: Uuid, now: Instant) -> Option<AttachTicket> {
+        let ticket = self.tickets.lock().unwrap().remove(secret)?;
+        if ticket.job_id == job_id && ticket.expires_at > now {
+            Some(ticket)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use worker::{JobManager, JobOptions, TerminalSize};
+
+    const OWNER: Principal = Principal::submitter("owner");
+
+    async fn attachment(jobs: &JobManager) -> (Uuid, JobAttachment) {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            terminal: Some(TerminalSize::default()),
+            ..JobOptions::default()
+        };
+        jobs.submit_with_options(id, OWNER, "sleep 5", options)
+            .await
+            .unwrap();
+        (id, jobs.attach(id, OWNER).await.unwrap())
+    }
+
+    async fn stop(jobs: &JobManager, id: Uuid) {
+        jobs.stop(id, OWNER).await.unwrap();
+        jobs.completion(id, OWNER).await.unwrap().wait().await;
+    }
+
+    #[tokio::test]
+    async fn tickets_are_only_redeemed_once() {
+        let jobs = JobManager::new();
+        let (id, attachment) = attachment(&jobs).await;
+        let tickets = AttachTickets::new();
+        let secret = tickets.issue(id, OWNER, attachment);
+
+        let ticket = tickets.redeem(&secret, id).unwrap();
+
+        assert_eq!("owner", ticket.principal().id);
+        assert!(tickets.redeem(&secret, id).is_none());
+        stop(&jobs, id).await;
+    }
+
+    #[tokio::test]
+    async fn tickets_are_only_valid_for_their_job() {
+        let jobs = JobManager::new();
+        let (id, attachment) = attachment(&jobs).await;
+        let tickets = AttachTickets::new();
+        let secret = tickets.issue(id, OWNER, attachment);
+
+        assert!(tickets.redeem(&secret, Uuid::new_v4()).is_none());
+        assert!(tickets.redeem(&secret, id).is_none());
+        stop(&jobs, id).await;
+    }
+
+    #[tokio::test]
+    async fn expired_tickets_are_refused() {
+        let jobs = JobManager::new();
+        let (id, attachment) = attachment(&jobs).await;
+        let tickets = AttachTickets::new();
+        let now = Instant::now();
+        let secret = tickets.issue_at(id, OWNER, attachment, now);
+
+        assert!(tickets
+            .redeem_at(&secret, id, now + TICKET_LIFETIME)
+            .is_none());
+        stop(&jobs, id).await;
+    }
+}
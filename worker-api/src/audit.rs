@@ -0,0 +1,13 @@
+mod audit_action;
+mod audit_entry;
+mod audit_log;
+mod audit_outcome;
+mod audit_query;
+mod redaction;
+
+pub use audit_action::AuditAction;
+pub use audit_entry::AuditEntry;
+pub use audit_log::AuditLog;
+pub use audit_outcome::AuditOutcome;
+pub use audit_query::AuditQuery;
+pub use redaction::redact_command_line;
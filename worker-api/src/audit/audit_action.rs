@@ -0,0 +1,62 @@
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Security relevant actions that are recorded in the audit log.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    /// Login with the name and the password of a user.
+    Login,
+    /// Exchange of a refresh token for new tokens.
+    Refresh,
+    /// Revocation of the tokens of a user.
+    Logout,
+    /// Authentication of a request with a token or an API key.
+    Authentication,
+    /// Creation of an API key.
+    ApiKeyCreate,
+    /// Revocation of an API key.
+    ApiKeyRevoke,
+    /// Creation of a job.
+    JobCreate,
+    /// Stop of a job.
+    JobStop,
+    /// Download of the output of a job.
+    JobOutput,
+    /// Deletion of the output of a job.
+    JobOutputDelete,
+    /// Download of an artifact of a job.
+    JobArtifact,
+    /// Any other operation on a job.  Only the denied ones are
+    /// recorded.
+    JobAccess,
+    /// Query of the audit log.
+    AuditQuery,
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(action: &str) -> Result<Self, Self::Err> {
+        AuditAction::deserialize(action.into_deserializer())
+            .map_err(|_: serde::de::value::Error| format!("Invalid action '{}'", action))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_are_parsed_by_their_serialized_names() {
+        assert_eq!(
+            Ok(AuditAction::JobOutputDelete),
+            "job_output_delete".parse()
+        );
+        assert_eq!(
+            "\"api_key_create\"",
+            serde_json::to_string(&AuditAction::ApiKeyCreate).unwrap()
+        );
+        assert!("JobCreate".parse::<AuditAction>().is_err());
+    }
+}
@@ -0,0 +1,64 @@
+use super::{AuditAction, AuditOutcome};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+/// Entry of the audit log.  It never contains secrets: passwords and
+/// tokens aren't recorded and the command lines are redacted.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    /// Seconds since the epoch.
+    pub timestamp: i64,
+    /// Name of the user that performs the action, if it is known.
+    pub actor: Option<String>,
+    /// Subject of the user that performs the action, if it is known.
+    pub subject: Option<String>,
+    /// Address of the client.
+    pub address: String,
+    pub action: AuditAction,
+    /// Resource of the action, e.g. the id of a job.
+    pub target: Option<String>,
+    pub outcome: AuditOutcome,
+    /// Additional information, e.g. the redacted command of a job.
+    pub detail: Option<String>,
+}
+
+impl AuditEntry {
+    /// Entry of an action performed now.
+    ///
+    /// * `action` - Action that is audited.
+    /// * `outcome` - Result of the action.
+    pub fn new(action: AuditAction, outcome: AuditOutcome) -> AuditEntry {
+        AuditEntry {
+            timestamp: Utc::now().timestamp(),
+            actor: None,
+            subject: None,
+            address: String::new(),
+            action,
+            target: None,
+            outcome,
+            detail: None,
+        }
+    }
+
+    /// Set the user that performs the action.
+    ///
+    /// * `name` - Name of the user.
+    /// * `subject` - Subject of the user, if it is known.
+    pub fn actor(mut self, name: &str, subject: Option<&str>) -> AuditEntry {
+        self.actor = Some(name.to_string());
+        self.subject = subject.map(str::to_string);
+        self
+    }
+
+    /// Set the resource of the action.
+    pub fn target<T: ToString>(mut self, target: T) -> AuditEntry {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Set additional information.  It must not contain secrets.
+    pub fn detail<T: Into<String>>(mut self, detail: T) -> AuditEntry {
+        self.detail = Some(detail.into());
+        self
+    }
+}
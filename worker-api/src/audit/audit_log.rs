@@ -0,0 +1,154 @@
+use super::{AuditEntry, AuditQuery};
+use std::{
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    os::unix::fs::OpenOptionsExt,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Append-only log of the security relevant actions.  Every entry is a
+/// line of JSON in a file that is only readable by its owner, and the
+/// entries are never modified.  Without a file, the entries are only
+/// written to the standard error.
+#[derive(Default)]
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    file: Mutex<Option<File>>,
+}
+
+impl AuditLog {
+    /// Open the log.  The file is created if it doesn't exist.
+    ///
+    /// * `path` - Path of the file.  The entries are appended to it.
+    pub fn open(path: PathBuf) -> io::Result<AuditLog> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .mode(0o600)
+            .open(&path)?;
+        Ok(AuditLog {
+            path: Some(path),
+            file: Mutex::new(Some(file)),
+        })
+    }
+
+    /// Append an entry to the log.  An entry that can't be written is
+    /// reported in the standard error, since the action has already
+    /// been performed.
+    ///
+    /// * `entry` - Entry of the action.
+    pub fn record(&self, entry: &AuditEntry) {
+        let mut line = match serde_json::to_string(entry) {
+            Ok(line) => line,
+            Err(err) => {
+                eprintln!("The audit entry can't be serialized: {}", err);
+                return;
+            }
+        };
+        line.push('\n');
+        match self.file.lock().unwrap().as_mut() {
+            Some(file) => {
+                if let Err(err) = file.write_all(line.as_bytes()) {
+                    eprintln!("The audit entry can't be written: {}", err);
+                }
+            }
+            None => eprint!("audit: {}", line),
+        }
+    }
+
+    /// Entries that match a query, from the oldest to the most recent.
+    /// The file is read in a blocking task, since it can be large.
+    ///
+    /// * `query` - Filter of the entries.
+    pub async fn query(&self, query: AuditQuery) -> io::Result<Vec<AuditEntry>> {
+        let path = match self.path {
+            Some(ref path) => path.clone(),
+            None => return Ok(Vec::new()),
+        };
+        if query.limit == 0 {
+            return Ok(Vec::new());
+        }
+        tokio::task::spawn_blocking(move || Self::read(&path, &query))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+    }
+
+    fn read(path: &Path, query: &AuditQuery) -> io::Result<Vec<AuditEntry>> {
+        let mut entries = VecDeque::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            // A line that was partially written is skipped.
+            let entry: AuditEntry = match serde_json::from_str(&line?) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if query.matches(&entry) {
+                if entries.len() == query.limit {
+                    entries.pop_front();
+                }
+                entries.push_back(entry);
+            }
+        }
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditAction, AuditOutcome};
+    use std::fs;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn entries_are_appended_and_queried() {
+        let path = std::env::temp_dir().join(format!("{}.jsonl", Uuid::new_v4()));
+        let log = AuditLog::open(path.clone()).unwrap();
+        log.record(
+            &AuditEntry::new(AuditAction::Login, AuditOutcome::Failure).actor("alice", None),
+        );
+        log.record(
+            &AuditEntry::new(AuditAction::Login, AuditOutcome::Success).actor("alice", None),
+        );
+        log.record(
+            &AuditEntry::new(AuditAction::JobStop, AuditOutcome::Denied)
+                .actor("bob", Some("id 2"))
+                .target("job 1"),
+        );
+
+        let reopened = AuditLog::open(path.clone()).unwrap();
+        let logins = AuditQuery {
+            action: Some(AuditAction::Login),
+            limit: 10,
+            ..AuditQuery::default()
+        };
+        let outcomes: Vec<AuditOutcome> = reopened
+            .query(logins)
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.outcome)
+            .collect();
+        assert_eq!(vec![AuditOutcome::Failure, AuditOutcome::Success], outcomes);
+
+        let by_subject = AuditQuery {
+            actor: Some("id 2".to_string()),
+            limit: 10,
+            ..AuditQuery::default()
+        };
+        let entries = reopened.query(by_subject).await.unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!(Some("job 1".to_string()), entries[0].target);
+
+        let latest = AuditQuery {
+            limit: 1,
+            ..AuditQuery::default()
+        };
+        assert_eq!(
+            AuditAction::JobStop,
+            reopened.query(latest).await.unwrap()[0].action
+        );
+        fs::remove_file(path).unwrap();
+    }
+}
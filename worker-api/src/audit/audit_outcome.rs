@@ -0,0 +1,25 @@
+use serde::{de::IntoDeserializer, Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Result of an audited action.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    /// The action was performed.
+    Success,
+    /// The action failed, e.g. wrong credentials or an invalid request.
+    Failure,
+    /// The user isn't authorized to perform the action.
+    Denied,
+    /// The action was refused by a rate limit or a quota.
+    Limited,
+}
+
+impl FromStr for AuditOutcome {
+    type Err = String;
+
+    fn from_str(outcome: &str) -> Result<Self, Self::Err> {
+        AuditOutcome::deserialize(outcome.into_deserializer())
+            .map_err(|_: serde::de::value::Error| format!("Invalid outcome '{}'", outcome))
+    }
+}
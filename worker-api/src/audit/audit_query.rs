@@ -0,0 +1,30 @@
+use super::{AuditAction, AuditEntry, AuditOutcome};
+
+/// Filter of the entries of the audit log.  Every entry matches the
+/// criteria that aren't set.
+#[derive(Debug, Default)]
+pub struct AuditQuery {
+    /// Name or subject of the user.
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub outcome: Option<AuditOutcome>,
+    /// Seconds since the epoch of the oldest entry.
+    pub since: Option<i64>,
+    /// Maximum number of entries, that are the most recent ones.
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    /// Check if an entry matches the filter.
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        let actor_matches = self.actor.as_ref().map_or(true, |actor| {
+            entry.actor.as_ref() == Some(actor) || entry.subject.as_ref() == Some(actor)
+        });
+        actor_matches
+            && self.action.map_or(true, |action| action == entry.action)
+            && self
+                .outcome
+                .map_or(true, |outcome| outcome == entry.outcome)
+            && self.since.map_or(true, |since| entry.timestamp >= since)
+    }
+}
@@ -0,0 +1,656 @@
+use crate::{
+    limits::{Quota, RateLimit},
+    security::{CommandPolicy, OidcConfig, Role},
+    webhooks::TargetPolicy,
+};
+use ipnet::IpNet;
+use std::{
+    collections::HashMap,
+    env,
+    net::{IpAddr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use worker::{
+    Compression, Credentials, HardeningProfile, OutputLimit, OutputLimitAction, RetentionPolicy,
+};
+
+/// Settings of the server that are read from environment variables.
+/// Every setting is optional: there are no limits and no compression
+/// by default, except for the logins.  But jobs are refused unless
+/// their owner is mapped to a Unix user or there is a sandbox user.
+///
+/// - `WORKER_API_MAX_OUTPUT_BYTES`: maximum size of the output of any job.
+/// - `WORKER_API_MAX_ARTIFACT_BYTES`: maximum size of the artifacts of
+///   any job.  The files that would exceed it aren't collected.
+/// - `WORKER_API_RETENTION_MAX_AGE_SECS`: seconds a finished job is kept.
+/// - `WORKER_API_RETENTION_MAX_JOBS`: number of finished jobs that are kept.
+/// - `WORKER_API_OUTPUT_COMPRESSION`: `gzip` or `zstd` compression of
+///   the output of the jobs that don't request any.
+/// - `WORKER_API_JOB_USERS`: Unix users that run the jobs of each
+///   owner, e.g. `alice=1001:1001:27,44;bob=1002:1002`, with the
+///   format `owner=uid:gid[:group,...]`.
+/// - `WORKER_API_SANDBOX_USER`: Unix user, `uid:gid[:group,...]`, that
+///   runs the jobs of the owners that aren't mapped.  Neither these
+///   users nor the job users can be root, be in its group or be the
+///   user of the server.
+/// - `WORKER_API_POLICY_FILE`: JSON file with the `CommandPolicy` that
+///   allows or denies the jobs.  Every job is allowed without it.
+/// - `WORKER_API_HARDENING_PROFILE`: `none`, `default` or `strict`
+///   profile of the jobs that don't request any.  It is `default` if
+///   it isn't set.
+/// - `WORKER_API_MIN_HARDENING_PROFILE`: least restrictive profile that
+///   jobs can request.  There is no minimum if it isn't set.
+/// - `WORKER_API_USERS_FILE`: JSON file of the users that can log in.
+///   It is `users.json` if it isn't set.
+/// - `WORKER_API_REVOCATIONS_FILE`: JSON file of the tokens that have
+///   been revoked.  It is `revoked_tokens.json` if it isn't set.
+/// - `WORKER_API_KEYS_FILE`: JSON file of the API keys of the users.
+///   It is `api_keys.json` if it isn't set.
+/// - `WORKER_API_AUDIT_FILE`: JSON Lines file where the audit log is
+///   appended.  It is `audit.jsonl` if it isn't set.
+/// - `WORKER_API_SIGNING_KEYS`: PEM files of the RSA keys of the tokens
+///   separated by `;`.  The first one signs the tokens and the rest
+///   only verify them, so the keys can be rotated.
+/// - `WORKER_API_INSECURE_DEV_SECRET`: `true` to sign the tokens with a
+///   well known secret if there are neither keys nor
+///   `WORKER_API_SECRET`, or with a `WORKER_API_SECRET` shorter than 32
+///   bytes.  The server doesn't start otherwise.
+/// - `WORKER_API_JOBS_PER_MINUTE`: jobs that each user can create in a
+///   minute.
+/// - `WORKER_API_JOBS_PER_MINUTE_PER_ADDRESS`: jobs that can be created
+///   from each address in a minute.
+/// - `WORKER_API_LOGINS_PER_MINUTE_PER_ADDRESS`: login attempts from
+///   each address in a minute.  It is 10 if it isn't set.
+/// - `WORKER_API_LOGINS_PER_MINUTE_PER_USER`: login attempts for each
+///   user name in a minute.  It is 5 if it isn't set.
+/// - `WORKER_API_MAX_CONCURRENT_JOBS`: jobs of each user that can be in
+///   progress at the same time.
+/// - `WORKER_API_MAX_JOBS_PER_DAY`: jobs that each user can create in a
+///   day (UTC).
+/// - `WORKER_API_MAX_OUTPUT_BYTES_PER_USER`: total size of the stored
+///   output and artifacts of the jobs of each user.
+/// - `WORKER_API_OIDC_ISSUER`: OpenID Connect issuer whose ID tokens
+///   are accepted.  They aren't accepted if it isn't set.
+/// - `WORKER_API_OIDC_AUDIENCE`: client id that must be the audience of
+///   the ID tokens.  It is required with an issuer.
+/// - `WORKER_API_OIDC_GROUPS_CLAIM`: claim of the ID tokens with the
+///   groups of the user.  It is `groups` if it isn't set.
+/// - `WORKER_API_OIDC_ROLES`: roles of the members of the groups, e.g.
+///   `platform=admin;sre=operator`, with the format `group=role`.
+/// - `WORKER_API_OIDC_TEAMS`: groups that are the teams of their
+///   members, e.g. `ops;qa`.  The groups aren't teams otherwise.
+/// - `WORKER_API_ATTACH_ADDRESS`: address of the WebSockets that attach
+///   to the terminals of the jobs.  It is `127.0.0.1:8001` if it isn't
+///   set.
+/// - `WORKER_API_ATTACH_CERTS` and `WORKER_API_ATTACH_KEY`: PEM files of
+///   the certificate chain and the private key of the WebSockets.  They
+///   are the ones in `Rocket.toml` if they aren't set.
+/// - `WORKER_API_MTLS_CA`: PEM file with the certificates of the CAs
+///   that sign the client certificates.  If it is set, the connections
+///   with mutual TLS are accepted on their own address and their
+///   requests on the jobs are authenticated with the certificates.
+///   They use the certificate and the key of the WebSockets, that must
+///   be the ones of the HTTP API.
+/// - `WORKER_API_MTLS_ADDRESS`: address of the connections with mutual
+///   TLS.  It is `127.0.0.1:8002` if it isn't set.
+/// - `WORKER_API_WEBHOOK_ALLOWED_NETWORKS`: loopback, link-local or
+///   private networks that the webhooks can be delivered to, e.g.
+///   `10.1.0.0/16;192.168.1.10`.  Those addresses are refused otherwise.
+/// - `WORKER_API_TRUSTED_PROXIES`: networks of the proxies whose
+///   `X-Real-IP` header is the address of the client, e.g.
+///   `10.0.0.5;192.168.0.0/24`.  The header is ignored otherwise.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    pub max_output_bytes: Option<u64>,
+    pub max_artifact_bytes: Option<u64>,
+    pub retention: RetentionPolicy,
+    pub output_compression: Option<Compression>,
+    pub job_users: HashMap<String, Credentials>,
+    pub sandbox_user: Option<Credentials>,
+    pub command_policy: CommandPolicy,
+    pub hardening: HardeningProfile,
+    pub min_hardening: HardeningProfile,
+    pub users_file: Option<PathBuf>,
+    pub revocations_file: Option<PathBuf>,
+    pub api_keys_file: Option<PathBuf>,
+    pub audit_file: Option<PathBuf>,
+    pub daily_jobs_file: Option<PathBuf>,
+    pub signing_key_files: Vec<PathBuf>,
+    pub insecure_dev_secret: bool,
+    pub job_rate_limit: Option<RateLimit>,
+    pub job_address_rate_limit: Option<RateLimit>,
+    pub login_address_rate_limit: Option<RateLimit>,
+    pub login_user_rate_limit: Option<RateLimit>,
+    pub quota: Quota,
+    pub oidc: Option<OidcConfig>,
+    pub attach_address: Option<SocketAddr>,
+    pub attach_certs: Option<PathBuf>,
+    pub attach_key: Option<PathBuf>,
+    pub mtls_ca: Option<PathBuf>,
+    pub mtls_address: Option<SocketAddr>,
+    pub webhook_targets: TargetPolicy,
+    pub trusted_proxies: Vec<IpNet>,
+}
+
+/// File of the users when `WORKER_API_USERS_FILE` isn't set.
+const DEFAULT_USERS_FILE: &str = "users.json";
+/// File of the revoked tokens when `WORKER_API_REVOCATIONS_FILE` isn't
+/// set.
+const DEFAULT_REVOCATIONS_FILE: &str = "revoked_tokens.json";
+/// File of the API keys when `WORKER_API_KEYS_FILE` isn't set.
+const DEFAULT_API_KEYS_FILE: &str = "api_keys.json";
+/// File of the audit log when `WORKER_API_AUDIT_FILE` isn't set.
+const DEFAULT_AUDIT_FILE: &str = "audit.jsonl";
+/// File of the jobs created by each user today when
+/// `WORKER_API_DAILY_JOBS_FILE` isn't set.
+const DEFAULT_DAILY_JOBS_FILE: &str = "daily_jobs.json";
+/// Login attempts per minute from an address when
+/// `WORKER_API_LOGINS_PER_MINUTE_PER_ADDRESS` isn't set.
+const DEFAULT_LOGINS_PER_ADDRESS: u32 = 10;
+/// Login attempts per minute for a user name when
+/// `WORKER_API_LOGINS_PER_MINUTE_PER_USER` isn't set.
+const DEFAULT_LOGINS_PER_USER: u32 = 5;
+/// Claim with the groups when `WORKER_API_OIDC_GROUPS_CLAIM` isn't set.
+const DEFAULT_GROUPS_CLAIM: &str = "groups";
+/// Address of the WebSockets when `WORKER_API_ATTACH_ADDRESS` isn't
+/// set.
+const DEFAULT_ATTACH_ADDRESS: &str = "127.0.0.1:8001";
+/// Certificate chain of the WebSockets when `WORKER_API_ATTACH_CERTS`
+/// isn't set.
+const DEFAULT_ATTACH_CERTS: &str = "private/rsacert.pem";
+/// Private key of the WebSockets when `WORKER_API_ATTACH_KEY` isn't
+/// set.
+const DEFAULT_ATTACH_KEY: &str = "private/rsakey.pem";
+/// Address of the connections with mutual TLS when
+/// `WORKER_API_MTLS_ADDRESS` isn't set.
+const DEFAULT_MTLS_ADDRESS: &str = "127.0.0.1:8002";
+
+impl Config {
+    /// Read the configuration from the environment.  It panics if a
+    /// value is invalid, so the server doesn't start with a wrong
+    /// configuration.
+    pub fn from_env() -> Config {
+        Config {
+            max_output_bytes: Self::var("WORKER_API_MAX_OUTPUT_BYTES"),
+            max_artifact_bytes: Self::var("WORKER_API_MAX_ARTIFACT_BYTES"),
+            retention: RetentionPolicy {
+                max_age: Self::var("WORKER_API_RETENTION_MAX_AGE_SECS").map(Duration::from_secs),
+                max_finished_jobs: Self::var("WORKER_API_RETENTION_MAX_JOBS"),
+            },
+            output_compression: Self::var::<String>("WORKER_API_OUTPUT_COMPRESSION").map(
+                |compression| match compression.as_str() {
+                    "gzip" => Compression::Gzip,
+                    "zstd" => Compression::Zstd,
+                    _ => panic!(
+                        "Invalid value for WORKER_API_OUTPUT_COMPRESSION: '{}'",
+                        compression
+                    ),
+                },
+            ),
+            job_users: Self::var::<String>("WORKER_API_JOB_USERS")
+                .map(|users| {
+                    Self::job_users(&users).unwrap_or_else(|| {
+                        panic!("Invalid value for WORKER_API_JOB_USERS: '{}'", users)
+                    })
+                })
+                .unwrap_or_default(),
+            sandbox_user: Self::var::<String>("WORKER_API_SANDBOX_USER").map(|user| {
+                Self::credentials(&user).unwrap_or_else(|| {
+                    panic!("Invalid value for WORKER_API_SANDBOX_USER: '{}'", user)
+                })
+            }),
+            command_policy: Self::var::<String>("WORKER_API_POLICY_FILE")
+                .map(|path| {
+                    CommandPolicy::load(Path::new(&path)).unwrap_or_else(|err| {
+                        panic!(
+                            "Invalid policy in WORKER_API_POLICY_FILE '{}': {}",
+                            path, err
+                        )
+                    })
+                })
+                .unwrap_or_default(),
+            hardening: Self::hardening_var("WORKER_API_HARDENING_PROFILE")
+                .unwrap_or(HardeningProfile::Default),
+            min_hardening: Self::hardening_var("WORKER_API_MIN_HARDENING_PROFILE")
+                .unwrap_or(HardeningProfile::None),
+            users_file: Self::var("WORKER_API_USERS_FILE"),
+            revocations_file: Self::var("WORKER_API_REVOCATIONS_FILE"),
+            api_keys_file: Self::var("WORKER_API_KEYS_FILE"),
+            audit_file: Self::var("WORKER_API_AUDIT_FILE"),
+            daily_jobs_file: Self::var("WORKER_API_DAILY_JOBS_FILE"),
+            signing_key_files: Self::var::<String>("WORKER_API_SIGNING_KEYS")
+                .map(|files| {
+                    files
+                        .split(';')
+                        .filter(|file| !file.trim().is_empty())
+                        .map(|file| PathBuf::from(file.trim()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            insecure_dev_secret: Self::var("WORKER_API_INSECURE_DEV_SECRET").unwrap_or(false),
+            job_rate_limit: Self::var("WORKER_API_JOBS_PER_MINUTE").map(RateLimit::per_minute),
+            job_address_rate_limit: Self::var("WORKER_API_JOBS_PER_MINUTE_PER_ADDRESS")
+                .map(RateLimit::per_minute),
+            login_address_rate_limit: Some(RateLimit::per_minute(
+                Self::var("WORKER_API_LOGINS_PER_MINUTE_PER_ADDRESS")
+                    .unwrap_or(DEFAULT_LOGINS_PER_ADDRESS),
+            )),
+            login_user_rate_limit: Some(RateLimit::per_minute(
+                Self::var("WORKER_API_LOGINS_PER_MINUTE_PER_USER")
+                    .unwrap_or(DEFAULT_LOGINS_PER_USER),
+            )),
+            quota: Quota {
+                max_concurrent_jobs: Self::var("WORKER_API_MAX_CONCURRENT_JOBS"),
+                max_jobs_per_day: Self::var("WORKER_API_MAX_JOBS_PER_DAY"),
+                max_output_bytes: Self::var("WORKER_API_MAX_OUTPUT_BYTES_PER_USER"),
+            },
+            oidc: Self::var::<String>("WORKER_API_OIDC_ISSUER").map(|issuer| OidcConfig {
+                issuer,
+                audience: Self::var("WORKER_API_OIDC_AUDIENCE")
+                    .expect("WORKER_API_OIDC_AUDIENCE is required with an OIDC issuer"),
+                groups_claim: Self::var("WORKER_API_OIDC_GROUPS_CLAIM")
+                    .unwrap_or_else(|| DEFAULT_GROUPS_CLAIM.to_string()),
+                role_groups: Self::var::<String>("WORKER_API_OIDC_ROLES")
+                    .map(|roles| {
+                        Self::role_groups(&roles).unwrap_or_else(|| {
+                            panic!("Invalid value for WORKER_API_OIDC_ROLES: '{}'", roles)
+                        })
+                    })
+                    .unwrap_or_default(),
+                team_groups: Self::var::<String>("WORKER_API_OIDC_TEAMS")
+                    .map(|teams| {
+                        teams
+                            .split(';')
+                            .map(str::trim)
+                            .filter(|team| !team.is_empty())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }),
+            attach_address: Self::var("WORKER_API_ATTACH_ADDRESS"),
+            attach_certs: Self::var("WORKER_API_ATTACH_CERTS"),
+            attach_key: Self::var("WORKER_API_ATTACH_KEY"),
+            mtls_ca: Self::var("WORKER_API_MTLS_CA"),
+            mtls_address: Self::var("WORKER_API_MTLS_ADDRESS"),
+            webhook_targets: Self::var::<String>("WORKER_API_WEBHOOK_ALLOWED_NETWORKS")
+                .map(|networks| {
+                    networks.parse().unwrap_or_else(|_| {
+                        panic!(
+                            "Invalid value for WORKER_API_WEBHOOK_ALLOWED_NETWORKS: '{}'",
+                            networks
+                        )
+                    })
+                })
+                .unwrap_or_default(),
+            trusted_proxies: Self::var::<String>("WORKER_API_TRUSTED_PROXIES")
+                .map(|proxies| {
+                    Self::networks(&proxies).unwrap_or_else(|| {
+                        panic!(
+                            "Invalid value for WORKER_API_TRUSTED_PROXIES: '{}'",
+                            proxies
+                        )
+                    })
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    /// File of the users that can log in.
+    pub fn users_file(&self) -> &Path {
+        self.users_file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_USERS_FILE))
+    }
+
+    /// Hardening profile of a job.  It returns `None` if the requested
+    /// profile is less restrictive than the minimum of the server.
+    ///
+    /// * `requested` - Profile requested when the job was created.
+    pub fn hardening(&self, requested: Option<HardeningProfile>) -> Option<HardeningProfile> {
+        let profile = requested.unwrap_or(self.hardening);
+        if profile < self.min_hardening {
+            None
+        } else {
+            Some(profile)
+        }
+    }
+
+    /// File of the tokens that have been revoked.
+    pub fn revocations_file(&self) -> &Path {
+        self.revocations_file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_REVOCATIONS_FILE))
+    }
+
+    /// File of the API keys of the users.
+    pub fn api_keys_file(&self) -> &Path {
+        self.api_keys_file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_API_KEYS_FILE))
+    }
+
+    /// File of the audit log.
+    pub fn audit_file(&self) -> &Path {
+        self.audit_file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_AUDIT_FILE))
+    }
+
+    /// File of the jobs created by each user today.
+    pub fn daily_jobs_file(&self) -> &Path {
+        self.daily_jobs_file
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_DAILY_JOBS_FILE))
+    }
+
+    /// Address of the WebSockets that attach to the terminals.
+    pub fn attach_address(&self) -> SocketAddr {
+        self.attach_address.unwrap_or_else(|| {
+            DEFAULT_ATTACH_ADDRESS
+                .parse()
+                .expect("Invalid default attach address")
+        })
+    }
+
+    /// PEM file of the certificate chain of the WebSockets.
+    pub fn attach_certs(&self) -> &Path {
+        self.attach_certs
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_ATTACH_CERTS))
+    }
+
+    /// PEM file of the private key of the WebSockets.
+    pub fn attach_key(&self) -> &Path {
+        self.attach_key
+            .as_deref()
+            .unwrap_or_else(|| Path::new(DEFAULT_ATTACH_KEY))
+    }
+
+    /// Address of the connections with mutual TLS.
+    pub fn mtls_address(&self) -> SocketAddr {
+        self.mtls_address.unwrap_or_else(|| {
+            DEFAULT_MTLS_ADDRESS
+                .parse()
+                .expect("Invalid default mutual TLS address")
+        })
+    }
+
+    /// Address of the client of a request.  Any client can send
+    /// `X-Real-IP`, so it is only used if the peer is a trusted proxy.
+    ///
+    /// * `peer` - Address of the other end of the connection.
+    /// * `real_ip` - Address in the `X-Real-IP` header of the request.
+    pub fn client_address(&self, peer: Option<IpAddr>, real_ip: Option<IpAddr>) -> Option<IpAddr> {
+        match peer {
+            Some(proxy) if self.trusted_proxies.iter().any(|net| net.contains(&proxy)) => {
+                real_ip.or(peer)
+            }
+            _ => peer,
+        }
+    }
+
+    /// Unix user that runs the jobs of an owner: the one it is mapped
+    /// to or the sandbox user.
+    ///
+    /// * `owner` - Subject of the claims of the user that creates the job.
+    pub fn job_user(&self, owner: &str) -> Option<Credentials> {
+        self.job_users
+            .get(owner)
+            .or(self.sandbox_user.as_ref())
+            .cloned()
+    }
+
+    /// Limit of the output of a job.  The limit requested for the job
+    /// can't exceed the one of the server, that is used when the job
+    /// doesn't request any.
+    ///
+    /// * `requested` - Limit requested when the job was created.
+    pub fn output_limit(&self, requested: Option<OutputLimit>) -> Option<OutputLimit> {
+        match (requested, self.max_output_bytes) {
+            (Some(limit), Some(max_bytes)) => Some(OutputLimit {
+                max_bytes: limit.max_bytes.min(max_bytes),
+                action: limit.action,
+            }),
+            (None, Some(max_bytes)) => Some(OutputLimit {
+                max_bytes,
+                action: OutputLimitAction::TruncateHead,
+            }),
+            (requested, None) => requested,
+        }
+    }
+
+    // Parse `owner=uid:gid[:group,...]` entries separated by `;`.
+    fn job_users(value: &str) -> Option<HashMap<String, Credentials>> {
+        value
+            .split(';')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(2, '=');
+                let owner = parts.next()?.trim();
+                let credentials = Self::credentials(parts.next()?)?;
+                if owner.is_empty() {
+                    return None;
+                }
+                Some((owner.to_string(), credentials))
+            })
+            .collect()
+    }
+
+    // Parse `group=role` entries separated by `;`.
+    fn role_groups(value: &str) -> Option<HashMap<String, Role>> {
+        value
+            .split(';')
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(2, '=');
+                let group = parts.next()?.trim();
+                let role = parts.next()?.trim().parse().ok()?;
+                if group.is_empty() {
+                    return None;
+                }
+                Some((group.to_string(), role))
+            })
+            .collect()
+    }
+
+    // Parse networks separated by `;`.  An address is a network of its
+    // own.
+    fn networks(value: &str) -> Option<Vec<IpNet>> {
+        value
+            .split(';')
+            .map(str::trim)
+            .filter(|net| !net.is_empty())
+            .map(|net| match net.parse::<IpAddr>() {
+                Ok(address) => Some(IpNet::from(address)),
+                Err(_) => net.parse().ok(),
+            })
+            .collect()
+    }
+
+    // Parse `uid:gid[:group,...]`, refusing the privileged users.
+    fn credentials(value: &str) -> Option<Credentials> {
+        let mut parts = value.trim().splitn(3, ':');
+        let uid = parts.next()?.parse().ok()?;
+        let gid = parts.next()?.parse().ok()?;
+        let groups = match parts.next() {
+            Some(groups) => groups
+                .split(',')
+                .map(|group| group.parse().ok())
+                .collect::<Option<Vec<u32>>>()?,
+            None => Vec::new(),
+        };
+        Some(Credentials { uid, gid, groups }).filter(|credentials| !credentials.is_privileged())
+    }
+
+    fn hardening_var(name: &str) -> Option<HardeningProfile> {
+        Self::var::<String>(name).map(|profile| match profile.as_str() {
+            "none" => HardeningProfile::None,
+            "default" => HardeningProfile::Default,
+            "strict" => HardeningProfile::Strict,
+            _ => panic!("Invalid value for {}: '{}'", name, profile),
+        })
+    }
+
+    fn var<T: FromStr>(name: &str) -> Option<T> {
+        let value = env::var(name).ok()?;
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(_) => panic!("Invalid value for {}: '{}'", name, value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STOP_AT_100: OutputLimit = OutputLimit {
+        max_bytes: 100,
+        action: OutputLimitAction::StopJob,
+    };
+
+    #[test]
+    fn requested_limit_is_used_without_server_limit() {
+        let config = Config::default();
+
+        assert_eq!(Some(STOP_AT_100), config.output_limit(Some(STOP_AT_100)));
+        assert_eq!(None, config.output_limit(None));
+    }
+
+    #[test]
+    fn server_limit_is_applied_by_default() {
+        let config = Config {
+            max_output_bytes: Some(50),
+            ..Config::default()
+        };
+
+        let limit = config.output_limit(None).unwrap();
+
+        assert_eq!(50, limit.max_bytes);
+        assert_eq!(OutputLimitAction::TruncateHead, limit.action);
+    }
+
+    #[test]
+    fn requested_limit_cannot_exceed_server_limit() {
+        let config = Config {
+            max_output_bytes: Some(50),
+            ..Config::default()
+        };
+
+        let limit = config.output_limit(Some(STOP_AT_100)).unwrap();
+
+        assert_eq!(50, limit.max_bytes);
+        assert_eq!(OutputLimitAction::StopJob, limit.action);
+    }
+
+    #[test]
+    fn job_users_are_parsed() {
+        let users = Config::job_users("alice=1001:1001:27,44; bob=1002:1003").unwrap();
+
+        assert_eq!(
+            Some(&Credentials {
+                uid: 1001,
+                gid: 1001,
+                groups: vec![27, 44]
+            }),
+            users.get("alice")
+        );
+        assert_eq!(
+            Some(&Credentials {
+                uid: 1002,
+                gid: 1003,
+                groups: Vec::new()
+            }),
+            users.get("bob")
+        );
+        assert!(Config::job_users("alice=1001").is_none());
+        assert!(Config::job_users("=1001:1001").is_none());
+        assert!(Config::job_users("alice=1001:1001:root").is_none());
+    }
+
+    #[test]
+    fn privileged_job_users_are_refused() {
+        let server_uid = unsafe { libc::geteuid() };
+
+        assert!(Config::credentials("0:1001").is_none());
+        assert!(Config::credentials("1001:0").is_none());
+        assert!(Config::credentials("1001:1001:27,0").is_none());
+        assert!(Config::credentials(&format!("{}:1001", server_uid)).is_none());
+    }
+
+    #[test]
+    fn role_groups_are_parsed() {
+        let roles = Config::role_groups("platform=admin; sre=operator").unwrap();
+
+        assert_eq!(Some(&Role::Admin), roles.get("platform"));
+        assert_eq!(Some(&Role::Operator), roles.get("sre"));
+        assert!(Config::role_groups("platform=root").is_none());
+        assert!(Config::role_groups("=admin").is_none());
+    }
+
+    #[test]
+    fn unmapped_owners_use_sandbox_user() {
+        let sandbox = Credentials {
+            uid: 65534,
+            gid: 65534,
+            groups: Vec::new(),
+        };
+        let mut config = Config::default();
+        assert_eq!(None, config.job_user("alice"));
+
+        config.sandbox_user = Some(sandbox.clone());
+        config
+            .job_users
+            .insert("bob".to_string(), Config::credentials("1002:1002").unwrap());
+
+        assert_eq!(Some(sandbox), config.job_user("alice"));
+        assert_eq!(Some(1002), config.job_user("bob").map(|user| user.uid));
+    }
+
+    #[test]
+    fn real_ip_is_only_used_from_trusted_proxies() {
+        let config = Config {
+            trusted_proxies: Config::networks("10.0.0.5; 192.168.0.0/24").unwrap(),
+            ..Config::default()
+        };
+        let client = "203.0.113.7".parse().ok();
+
+        assert_eq!(
+            client,
+            config.client_address("10.0.0.5".parse().ok(), client)
+        );
+        assert_eq!(
+            client,
+            config.client_address("192.168.0.20".parse().ok(), client)
+        );
+        assert_eq!(
+            "10.0.0.6".parse().ok(),
+            config.client_address("10.0.0.6".parse().ok(), client)
+        );
+        assert_eq!(
+            "10.0.0.5".parse().ok(),
+            config.client_address("10.0.0.5".parse().ok(), None)
+        );
+        assert_eq!(None, Config::default().client_address(None, client));
+        assert!(Config::networks("10.0.0.0/33").is_none());
+    }
+
+    #[test]
+    fn hardening_below_minimum_is_refused() {
+        let config = Config {
+            hardening: HardeningProfile::Strict,
+            min_hardening: HardeningProfile::Default,
+            ..Config::default()
+        };
+
+        assert_eq!(Some(HardeningProfile::Strict), config.hardening(None));
+        assert_eq!(
+            Some(HardeningProfile::Default),
+            config.hardening(Some(HardeningProfile::Default))
+        );
+        assert_eq!(None, config.hardening(Some(HardeningProfile::None)));
+    }
+}
@@ -0,0 +1,17 @@
+mod job_limits;
+mod job_storage;
+mod limit_exceeded;
+mod login_limits;
+mod quota;
+mod rate_limit;
+mod rate_limiter;
+mod usage;
+
+pub use job_limits::JobLimits;
+pub use job_storage::JobStorage;
+pub use limit_exceeded::LimitExceeded;
+pub use login_limits::LoginLimits;
+pub use quota::Quota;
+pub use rate_limit::RateLimit;
+pub use rate_limiter::RateLimiter;
+pub use usage::Usage;
@@ -0,0 +1,568 @@
+use super::{JobStorage, LimitExceeded, Quota, RateLimit, RateLimiter, Usage};
+use crate::security::write_private;
+use chrono::Utc;
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
+use uuid::Uuid;
+use worker::{JobManager, JobStatus, OutputLimit, OutputLimitAction, Principal};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+/// Time suggested to retry when the jobs in progress are the maximum.
+const CONCURRENT_JOBS_RETRY_AFTER: Duration = Duration::from_secs(10);
+/// Time suggested to retry when the output is over the quota.  It is
+/// freed when the output is deleted or the jobs are removed.
+const OUTPUT_BYTES_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Rate limits and quotas of the creation of jobs.  The requests are
+/// limited per user and per address, and the quota of each user is
+/// checked against the usage of its jobs.  A job that is allowed
+/// reserves its place in the quota until it is created or it fails,
+/// and its storage in the output quota until it finishes.
+#[derive(Default)]
+pub struct JobLimits {
+    quota: Quota,
+    per_user: RateLimiter,
+    per_address: RateLimiter,
+    // File where the jobs of the day are kept, so they survive a
+    // restart.
+    path: Option<PathBuf>,
+    // Day (since the epoch, UTC), jobs created or reserved in it and
+    // jobs being created by each user.
+    daily: Mutex<HashMap<String, (i64, u64, u64)>>,
+    // Owner, bytes of output and artifacts reserved and whether it has
+    // been created, of the jobs that can still store them, by their id.
+    reserved_output: Mutex<HashMap<Uuid, (String, u64, bool)>>,
+    // Checks of new jobs, that are made one at a time, so they see the
+    // reservations of the previous ones.
+    checking: tokio::sync::Mutex<()>,
+    // Writes of the file, that are made one at a time, so the last one
+    // has the latest jobs of the day.
+    saving: tokio::sync::Mutex<()>,
+}
+
+impl JobLimits {
+    /// Create the limits of the jobs.
+    ///
+    /// * `quota` - Resources of the jobs of each user.
+    /// * `per_user` - Jobs that each user can create.
+    /// * `per_address` - Jobs that can be created from each address.
+    pub fn new(
+        quota: Quota,
+        per_user: Option<RateLimit>,
+        per_address: Option<RateLimit>,
+    ) -> JobLimits {
+        JobLimits {
+            quota,
+            per_user: RateLimiter::new(per_user),
+            per_address: RateLimiter::new(per_address),
+            path: None,
+            daily: Mutex::new(HashMap::new()),
+            reserved_output: Mutex::new(HashMap::new()),
+            checking: tokio::sync::Mutex::new(()),
+            saving: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Create the limits of the jobs like `new`, but keeping the jobs
+    /// created by each user in a JSON file.  A missing file means that
+    /// no jobs have been created.
+    ///
+    /// * `path` - Path of the file.  It is written whenever a job is
+    /// reserved or released.
+    pub fn load(
+        quota: Quota,
+        per_user: Option<RateLimit>,
+        per_address: Option<RateLimit>,
+        path: PathBuf,
+    ) -> io::Result<JobLimits> {
+        let created: HashMap<String, (i64, u64)> = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+        let daily = created
+            .into_iter()
+            .map(|(owner, (day, jobs))| (owner, (day, jobs, 0)))
+            .collect();
+        Ok(JobLimits {
+            path: Some(path),
+            daily: Mutex::new(daily),
+            ..JobLimits::new(quota, per_user, per_address)
+        })
+    }
+
+    /// Quota of each user.
+    pub fn quota(&self) -> &Quota {
+        &self.quota
+    }
+
+    /// Resources used by the jobs that a user owns.  The output and the
+    /// artifacts of a job in progress count as the storage that it has
+    /// reserved, and the unused part of the reservation is released
+    /// once the job finishes.
+    ///
+    /// * `jobs` - Jobs of the server.
+    /// * `owner` - User whose usage is returned.
+    pub async fn usage(&self, jobs: &JobManager, owner: Principal<'_>) -> Usage {
+        let mut usage = Usage {
+            jobs_today: self.jobs_today(owner.id, Utc::now().timestamp()),
+            ..Usage::default()
+        };
+        let mut listed = HashSet::new();
+        for info in jobs.list(owner).await {
+            if info.owner != owner.id {
+                continue;
+            }
+            listed.insert(info.id);
+            let in_progress = matches!(info.status, JobStatus::InProgress);
+            if in_progress {
+                usage.concurrent_jobs += 1;
+            }
+            if let Some(reserved) = self.reserved_output(info.id, in_progress) {
+                usage.output_bytes += reserved;
+                continue;
+            }
+            if let Ok(output) = jobs.output(info.id, owner).await {
+                if let Ok(metadata) = tokio::fs::metadata(&output.path).await {
+                    usage.output_bytes += metadata.len();
+                }
+            }
+            if let Ok(artifacts) = jobs.artifacts(info.id, owner).await {
+                usage.output_bytes += artifacts.iter().map(|artifact| artifact.size).sum::<u64>();
+            }
+        }
+        usage.output_bytes += self.pending_output(owner.id, &listed);
+        usage
+    }
+
+    /// Check if a user can create a job and reserve its place in the
+    /// quota.  The check and the reservation are atomic, so concurrent
+    /// requests can't exceed the quota, and the reservation must be
+    /// released with `release` once the job has been created or it has
+    /// failed.  The storage of the job is lowered to the output that
+    /// remains in the quota, and it is reserved until the job finishes.
+    /// The request is counted by the rate limits even if it is refused.
+    /// It returns the storage of the job.
+    ///
+    /// * `jobs` - Jobs of the server.
+    /// * `owner` - User that creates the job.
+    /// * `address` - Address of the client of the user.
+    /// * `job_id` - UUID of the new job.
+    /// * `storage` - Storage of the job without the quota.
+    pub async fn check(
+        &self,
+        jobs: &JobManager,
+        owner: Principal<'_>,
+        address: &str,
+        job_id: Uuid,
+        storage: JobStorage,
+    ) -> Result<JobStorage, LimitExceeded> {
+        let checked = {
+            let _checking = self.checking.lock().await;
+            let usage = self.usage(jobs, owner).await;
+            self.check_at(owner.id, address, &usage, Utc::now().timestamp())
+                .map(|()| self.reserve_storage(owner.id, job_id, &usage, storage))
+        };
+        self.save().await;
+        checked
+    }
+
+    /// Release the reservation of a job.  A job that hasn't been
+    /// created doesn't count in the jobs of the day nor keeps its
+    /// output reserved, and one that has been created is counted by the
+    /// usage of its owner from now on.
+    ///
+    /// * `owner` - User that has created the job.
+    /// * `job_id` - UUID of the job.
+    /// * `created` - Whether the job has been created.
+    pub async fn release(&self, owner: &str, job_id: Uuid, created: bool) {
+        self.release_at(owner, job_id, created, Utc::now().timestamp());
+        self.save().await;
+    }
+
+    // Storage of a new job of a user, so the stored and the reserved
+    // output and artifacts of its jobs don't exceed its quota.  The
+    // output gets the remaining quota first, and the artifacts the rest.
+    fn storage(&self, usage: &Usage, storage: JobStorage) -> JobStorage {
+        let remaining = match self.quota.max_output_bytes {
+            Some(max_bytes) => max_bytes.saturating_sub(usage.output_bytes),
+            None => return storage,
+        };
+        let output_limit = match storage.output_limit {
+            Some(limit) => OutputLimit {
+                max_bytes: limit.max_bytes.min(remaining),
+                action: limit.action,
+            },
+            None => OutputLimit {
+                max_bytes: remaining,
+                action: OutputLimitAction::TruncateHead,
+            },
+        };
+        let left = remaining - output_limit.max_bytes;
+        JobStorage {
+            output_limit: Some(output_limit),
+            max_artifact_bytes: Some(storage.max_artifact_bytes.map_or(left, |max| max.min(left))),
+        }
+    }
+
+    // Reserve the storage of a new job in the output quota of its owner,
+    // if there is one.
+    fn reserve_storage(
+        &self,
+        owner: &str,
+        job_id: Uuid,
+        usage: &Usage,
+        storage: JobStorage,
+    ) -> JobStorage {
+        let storage = self.storage(usage, storage);
+        if let (Some(limit), Some(max_artifact_bytes)) =
+            (storage.output_limit, storage.max_artifact_bytes)
+        {
+            if self.quota.max_output_bytes.is_some() {
+                let reserved = limit.max_bytes + max_artifact_bytes;
+                self.reserved_output
+                    .lock()
+                    .unwrap()
+                    .insert(job_id, (owner.to_string(), reserved, false));
+            }
+        }
+        storage
+    }
+
+    // Storage reserved by a job, if it can still use it.  The
+    // reservation is released otherwise.
+    fn reserved_output(&self, job_id: Uuid, in_progress: bool) -> Option<u64> {
+        let mut reserved_output = self.reserved_output.lock().unwrap();
+        match reserved_output.get(&job_id) {
+            Some((_, bytes, _)) if in_progress => Some(*bytes),
+            Some(_) => {
+                reserved_output.remove(&job_id);
+                None
+            }
+            None => None,
+        }
+    }
+
+    // Storage reserved by the jobs of a user that are being created.
+    // The reservations of its jobs that have been removed are released.
+    //
+    // * `listed` - Jobs of the user that exist.
+    fn pending_output(&self, owner: &str, listed: &HashSet<Uuid>) -> u64 {
+        let mut reserved_output = self.reserved_output.lock().unwrap();
+        reserved_output.retain(|job_id, (job_owner, _, created)| {
+            job_owner != owner || !*created || listed.contains(job_id)
+        });
+        reserved_output
+            .iter()
+            .filter(|(job_id, (job_owner, _, _))| job_owner == owner && !listed.contains(job_id))
+            .map(|(_, (_, bytes, _))| bytes)
+            .sum()
+    }
+
+    fn check_at(
+        &self,
+        owner: &str,
+        address: &str,
+        usage: &Usage,
+        now: i64,
+    ) -> Result<(), LimitExceeded> {
+        self.per_user.check(owner).map_err(|retry_after| {
+            LimitExceeded::new("Too many jobs created by the user", retry_after)
+        })?;
+        self.per_address.check(address).map_err(|retry_after| {
+            LimitExceeded::new("Too many jobs created from the address", retry_after)
+        })?;
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        let mut daily = self.daily.lock().unwrap();
+        let count = daily.entry(owner.to_string()).or_insert((today, 0, 0));
+        if count.0 != today {
+            *count = (today, 0, count.2);
+        }
+        if exceeds(
+            usage.concurrent_jobs + count.2,
+            self.quota.max_concurrent_jobs,
+        ) {
+            return Err(LimitExceeded::new(
+                "Too many jobs in progress",
+                CONCURRENT_JOBS_RETRY_AFTER,
+            ));
+        }
+        if exceeds(count.1, self.quota.max_jobs_per_day) {
+            let until_tomorrow = SECONDS_PER_DAY - now.rem_euclid(SECONDS_PER_DAY);
+            return Err(LimitExceeded::new(
+                "Too many jobs created today",
+                Duration::from_secs(until_tomorrow as u64),
+            ));
+        }
+        if exceeds(usage.output_bytes, self.quota.max_output_bytes) {
+            return Err(LimitExceeded::new(
+                "Too much output stored",
+                OUTPUT_BYTES_RETRY_AFTER,
+            ));
+        }
+        count.1 += 1;
+        count.2 += 1;
+        daily.retain(|_, (day, _, pending)| *day == today || *pending > 0);
+        Ok(())
+    }
+
+    fn release_at(&self, owner: &str, job_id: Uuid, created: bool, now: i64) {
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        let mut daily = self.daily.lock().unwrap();
+        if let Some(count) = daily.get_mut(owner) {
+            count.2 = count.2.saturating_sub(1);
+            if !created && count.0 == today {
+                count.1 = count.1.saturating_sub(1);
+            }
+        }
+        daily.retain(|_, (day, _, pending)| *day == today || *pending > 0);
+        let mut reserved_output = self.reserved_output.lock().unwrap();
+        if created {
+            if let Some(reservation) = reserved_output.get_mut(&job_id) {
+                reservation.2 = true;
+            }
+        } else {
+            reserved_output.remove(&job_id);
+        }
+    }
+
+    // Write the jobs of the day to the file, if any, out of the threads
+    // of the server.  The counters are kept even if the file can't be
+    // written.
+    async fn save(&self) {
+        let path = match self.path {
+            Some(ref path) => path.clone(),
+            None => return,
+        };
+        let _saving = self.saving.lock().await;
+        let created: HashMap<String, (i64, u64)> = self
+            .daily
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(owner, (day, jobs, _))| (owner.clone(), (*day, *jobs)))
+            .collect();
+        let saved = match serde_json::to_vec(&created) {
+            Ok(content) => tokio::task::spawn_blocking(move || write_private(&path, &content))
+                .await
+                .unwrap_or_else(|err| Err(io::Error::new(io::ErrorKind::Other, err))),
+            Err(err) => Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        };
+        if let Err(err) = saved {
+            eprintln!("The jobs of the day can't be saved: {}", err);
+        }
+    }
+
+    fn jobs_today(&self, owner: &str, now: i64) -> u64 {
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        match self.daily.lock().unwrap().get(owner) {
+            Some((day, count, _)) if *day == today => *count,
+            _ => 0,
+        }
+    }
+}
+
+// A new job exceeds the quota if the usage has reached it.
+fn exceeds(used: u64, max: Option<u64>) -> bool {
+    max.map_or(false, |max| used >= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: Principal = Principal::submitter("alice");
+    const NOON: i64 = 18_000 * SECONDS_PER_DAY + 12 * 60 * 60;
+
+    fn quota() -> Quota {
+        Quota {
+            max_concurrent_jobs: Some(2),
+            max_jobs_per_day: Some(3),
+            max_output_bytes: Some(1024),
+        }
+    }
+
+    #[test]
+    fn jobs_within_the_quota_are_allowed() {
+        let limits = JobLimits::new(quota(), None, None);
+        let usage = Usage {
+            concurrent_jobs: 1,
+            jobs_today: 2,
+            output_bytes: 1023,
+        };
+
+        assert_eq!(Ok(()), limits.check_at("alice", "::1", &usage, NOON));
+    }
+
+    #[test]
+    fn daily_quota_is_retried_tomorrow() {
+        let limits = JobLimits::new(quota(), None, None);
+        for _ in 0..3 {
+            limits
+                .check_at("alice", "::1", &Usage::default(), NOON)
+                .unwrap();
+            limits.release_at("alice", Uuid::new_v4(), true, NOON);
+        }
+
+        let refusal = limits
+            .check_at("alice", "::1", &Usage::default(), NOON)
+            .unwrap_err();
+        assert_eq!(Duration::from_secs(12 * 60 * 60), refusal.retry_after);
+        assert_eq!(0, limits.jobs_today("alice", NOON + SECONDS_PER_DAY));
+        assert_eq!(0, limits.jobs_today("bob", NOON));
+    }
+
+    #[test]
+    fn concurrent_jobs_and_output_are_limited() {
+        let limits = JobLimits::new(quota(), None, None);
+        let running = Usage {
+            concurrent_jobs: 2,
+            ..Usage::default()
+        };
+        let stored = Usage {
+            output_bytes: 1024,
+            ..Usage::default()
+        };
+
+        assert!(limits.check_at("alice", "::1", &running, NOON).is_err());
+        assert!(limits.check_at("alice", "::1", &stored, NOON).is_err());
+    }
+
+    #[test]
+    fn jobs_are_rate_limited_per_address() {
+        let limits = JobLimits::new(Quota::default(), None, Some(RateLimit::per_minute(1)));
+
+        assert_eq!(
+            Ok(()),
+            limits.check_at("alice", "::1", &Usage::default(), NOON)
+        );
+        let refusal = limits
+            .check_at("bob", "::1", &Usage::default(), NOON)
+            .unwrap_err();
+        assert_eq!("Too many jobs created from the address", refusal.reason);
+    }
+
+    #[test]
+    fn reservations_count_until_they_are_released() {
+        let limits = JobLimits::new(quota(), None, None);
+        let usage = Usage {
+            concurrent_jobs: 1,
+            ..Usage::default()
+        };
+
+        assert_eq!(Ok(()), limits.check_at("alice", "::1", &usage, NOON));
+        let refusal = limits.check_at("alice", "::1", &usage, NOON).unwrap_err();
+        assert_eq!("Too many jobs in progress", refusal.reason);
+        limits.release_at("alice", Uuid::new_v4(), false, NOON);
+        assert_eq!(0, limits.jobs_today("alice", NOON));
+        assert_eq!(Ok(()), limits.check_at("alice", "::1", &usage, NOON));
+        assert_eq!(1, limits.jobs_today("alice", NOON));
+    }
+
+    #[tokio::test]
+    async fn jobs_of_the_day_survive_a_reload() {
+        let path = std::env::temp_dir().join(format!("{}.json", Uuid::new_v4()));
+        let limits = JobLimits::load(quota(), None, None, path.clone()).unwrap();
+        limits
+            .check_at("alice", "::1", &Usage::default(), NOON)
+            .unwrap();
+        limits.release_at("alice", Uuid::new_v4(), true, NOON);
+        limits.save().await;
+
+        let reloaded = JobLimits::load(quota(), None, None, path.clone()).unwrap();
+
+        assert_eq!(1, reloaded.jobs_today("alice", NOON));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn output_of_new_jobs_is_limited_to_the_remaining_quota() {
+        let limits = JobLimits::new(quota(), None, None);
+        let usage = Usage {
+            output_bytes: 1000,
+            ..Usage::default()
+        };
+        let requested = JobStorage {
+            output_limit: Some(OutputLimit {
+                max_bytes: 100,
+                action: OutputLimitAction::StopJob,
+            }),
+            max_artifact_bytes: Some(0),
+        };
+
+        assert_eq!(
+            JobStorage {
+                output_limit: Some(OutputLimit {
+                    max_bytes: 24,
+                    action: OutputLimitAction::TruncateHead
+                }),
+                max_artifact_bytes: Some(0),
+            },
+            limits.storage(&usage, JobStorage::default())
+        );
+        assert_eq!(
+            Some(OutputLimit {
+                max_bytes: 24,
+                action: OutputLimitAction::StopJob
+            }),
+            limits.storage(&usage, requested).output_limit
+        );
+        assert_eq!(requested, JobLimits::default().storage(&usage, requested));
+    }
+
+    #[test]
+    fn artifacts_of_new_jobs_get_the_quota_left_by_the_output() {
+        let limits = JobLimits::new(quota(), None, None);
+        let usage = Usage {
+            output_bytes: 1000,
+            ..Usage::default()
+        };
+        let requested = |max_artifact_bytes| JobStorage {
+            output_limit: Some(OutputLimit {
+                max_bytes: 10,
+                action: OutputLimitAction::StopJob,
+            }),
+            max_artifact_bytes,
+        };
+
+        let storage = limits.storage(&usage, requested(None));
+        assert_eq!(Some(14), storage.max_artifact_bytes);
+        let storage = limits.storage(&usage, requested(Some(5)));
+        assert_eq!(Some(5), storage.max_artifact_bytes);
+    }
+
+    #[tokio::test]
+    async fn output_is_reserved_until_the_jobs_finish() {
+        let jobs = JobManager::new();
+        let limits = JobLimits::new(quota(), None, None);
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let requested = JobStorage {
+            output_limit: Some(OutputLimit {
+                max_bytes: 900,
+                action: OutputLimitAction::StopJob,
+            }),
+            max_artifact_bytes: Some(100),
+        };
+
+        let storage = limits.check(&jobs, ALICE, "::1", first, requested);
+        assert_eq!(Ok(requested), storage.await);
+        jobs.submit(first, ALICE, "sleep 5").await.unwrap();
+        limits.release("alice", first, true).await;
+        let storage = limits.check(&jobs, ALICE, "::1", second, requested);
+        let storage = storage.await.unwrap();
+        assert_eq!(24, storage.output_limit.unwrap().max_bytes);
+        assert_eq!(Some(0), storage.max_artifact_bytes);
+        limits.release("alice", second, false).await;
+        jobs.stop(first, ALICE).await.unwrap();
+        jobs.completion(first, ALICE).await.unwrap().wait().await;
+
+        assert_eq!(0, limits.usage(&jobs, ALICE).await.output_bytes);
+    }
+}
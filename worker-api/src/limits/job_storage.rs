@@ -0,0 +1,12 @@
+use worker::OutputLimit;
+
+/// Storage that a new job can use: the limit of its output and the
+/// maximum size of its artifacts.  There is no limit for the ones that
+/// aren't set.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct JobStorage {
+    /// Limit of the output of the job.
+    pub output_limit: Option<OutputLimit>,
+    /// Maximum size of all the artifacts of the job.
+    pub max_artifact_bytes: Option<u64>,
+}
@@ -0,0 +1,22 @@
+use std::time::Duration;
+
+/// Limit or quota that refuses a request, and the time after which it
+/// can be retried.
+#[derive(Debug, PartialEq)]
+pub struct LimitExceeded {
+    pub reason: String,
+    pub retry_after: Duration,
+}
+
+impl LimitExceeded {
+    /// Refusal of a request.
+    ///
+    /// * `reason` - Limit that has been exceeded.
+    /// * `retry_after` - Time until the request may be accepted.
+    pub fn new(reason: &str, retry_after: Duration) -> LimitExceeded {
+        LimitExceeded {
+            reason: reason.to_string(),
+            retry_after,
+        }
+    }
+}
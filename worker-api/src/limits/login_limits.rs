@@ -0,0 +1,37 @@
+use super::{LimitExceeded, RateLimit, RateLimiter};
+
+/// Rate limits of the logins, that slow down the guessing of
+/// passwords.  The attempts are limited per address and per user name,
+/// so guessing the password of a user from many addresses is limited
+/// too.
+#[derive(Default)]
+pub struct LoginLimits {
+    per_address: RateLimiter,
+    per_user: RateLimiter,
+}
+
+impl LoginLimits {
+    /// Create the limits of the logins.
+    ///
+    /// * `per_address` - Attempts from each address.
+    /// * `per_user` - Attempts for each user name.
+    pub fn new(per_address: Option<RateLimit>, per_user: Option<RateLimit>) -> LoginLimits {
+        LoginLimits {
+            per_address: RateLimiter::new(per_address),
+            per_user: RateLimiter::new(per_user),
+        }
+    }
+
+    /// Count a login attempt, whether it succeeds or not.
+    ///
+    /// * `address` - Address of the client.
+    /// * `user_name` - Name of the user that logs in.
+    pub fn check(&self, address: &str, user_name: &str) -> Result<(), LimitExceeded> {
+        self.per_address.check(address).map_err(|retry_after| {
+            LimitExceeded::new("Too many login attempts from the address", retry_after)
+        })?;
+        self.per_user.check(user_name).map_err(|retry_after| {
+            LimitExceeded::new("Too many login attempts for the user", retry_after)
+        })
+    }
+}
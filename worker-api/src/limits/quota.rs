@@ -0,0 +1,12 @@
+/// Resources that the jobs of each user can use.  There is no limit
+/// for the ones that aren't set.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Quota {
+    /// Jobs of the user that can be in progress at the same time.
+    pub max_concurrent_jobs: Option<u64>,
+    /// Jobs that the user can create in a day (UTC).
+    pub max_jobs_per_day: Option<u64>,
+    /// Total size of the stored output and artifacts of the jobs of the
+    /// user.
+    pub max_output_bytes: Option<u64>,
+}
@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+/// Maximum number of requests in a period of time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /// Limit of requests per minute.
+    ///
+    /// * `requests` - Requests allowed in a minute.
+    pub fn per_minute(requests: u32) -> RateLimit {
+        RateLimit {
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use super::RateLimit;
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Number of keys above which the expired windows are forgotten.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Counter of the requests of each key (a user or an address) in fixed
+/// windows of the period of its limit.  Every request is allowed
+/// without a limit.
+#[derive(Default)]
+pub struct RateLimiter {
+    limit: Option<RateLimit>,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter.
+    ///
+    /// * `limit` - Requests allowed for each key, if they are limited.
+    pub fn new(limit: Option<RateLimit>) -> RateLimiter {
+        RateLimiter {
+            limit,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request of a key.  It returns the time until the next
+    /// request is allowed if the key has exceeded its limit.
+    ///
+    /// * `key` - User or address that makes the request.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let limit = match self.limit {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < limit.period);
+        }
+        let window = windows.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(window.0) >= limit.period {
+            *window = (now, 0);
+        }
+        if window.1 >= limit.requests {
+            return Err(limit.period - now.duration_since(window.0));
+        }
+        window.1 += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_over_the_limit_are_refused_until_the_window_ends() {
+        let limiter = RateLimiter::new(Some(RateLimit::per_minute(2)));
+        let start = Instant::now();
+
+        assert_eq!(Ok(()), limiter.check_at("alice", start));
+        assert_eq!(Ok(()), limiter.check_at("alice", start));
+        assert_eq!(
+            Err(Duration::from_secs(50)),
+            limiter.check_at("alice", start + Duration::from_secs(10))
+        );
+        assert_eq!(Ok(()), limiter.check_at("bob", start));
+        assert_eq!(
+            Ok(()),
+            limiter.check_at("alice", start + Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn requests_are_allowed_without_a_limit() {
+        let limiter = RateLimiter::new(None);
+
+        for _ in 0..10 {
+            assert_eq!(Ok(()), limiter.check("alice"));
+        }
+    }
+}
@@ -0,0 +1,12 @@
+/// Resources used by the jobs of a user, that are counted against its
+/// `Quota`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Usage {
+    /// Jobs of the user that are in progress.
+    pub concurrent_jobs: u64,
+    /// Jobs created by the user today (UTC).
+    pub jobs_today: u64,
+    /// Total size of the stored output and artifacts of the jobs of the
+    /// user.
+    pub output_bytes: u64,
+}
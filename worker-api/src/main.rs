@@ -1,22 +1,187 @@
+mod admin;
 mod api;
+mod attach;
+mod audit;
+mod config;
+mod limits;
 mod security;
+mod tls;
+mod webhooks;
 
-use api::{auth, health, jobs};
-use rocket::{launch, routes};
-use std::{collections::HashMap, sync::RwLock};
-use uuid::Uuid;
+use api::{api_keys, audit_log, auth, events, health, jobs, jwks, me};
+use attach::{AttachServer, AttachTickets};
+use audit::AuditLog;
+use config::Config;
+use limits::{JobLimits, LoginLimits};
+use rocket::routes;
+use security::{ApiKeys, FileUserStore, OidcVerifier, RevocationList, SigningKeys, UserStore};
+use std::{
+    env,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    process,
+    sync::Arc,
+};
+use tls::{ClientCertificates, MtlsGateway};
+use webhooks::{DeliveryPolicy, Webhooks};
+use worker::JobManager;
 
-type JobData = RwLock<HashMap<Uuid, worker::Job>>;
+#[rocket::main]
+async fn main() {
+    let config = Config::from_env();
+    let users: Arc<dyn UserStore> = Arc::new(FileUserStore::new(config.users_file().to_path_buf()));
+    // `worker-api user ...` manages the users instead of starting the
+    // server.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("user") {
+        process::exit(admin::run_user_command(users.as_ref(), &args[1..]));
+    }
+    // The server doesn't start with the well known secret, unless it
+    // is explicitly allowed for development.
+    let signing_keys = match SigningKeys::load(
+        &config.signing_key_files,
+        env::var("WORKER_API_SECRET").ok(),
+        config.insecure_dev_secret,
+    ) {
+        Ok(signing_keys) => Arc::new(signing_keys),
+        Err(err) => {
+            eprintln!("ERR: {}", err);
+            process::exit(1);
+        }
+    };
+    let revocations = RevocationList::load(config.revocations_file().to_path_buf())
+        .expect("The revoked tokens can't be loaded");
+    let revocations = Arc::new(revocations);
+    let keys =
+        ApiKeys::load(config.api_keys_file().to_path_buf()).expect("The API keys can't be loaded");
+    let audit =
+        AuditLog::open(config.audit_file().to_path_buf()).expect("The audit log can't be opened");
+    let jobs = JobManager::with_retention(config.retention);
+    let tickets = Arc::new(AttachTickets::new());
+    let attach_server = AttachServer::bind(
+        config.attach_address(),
+        config.attach_certs(),
+        config.attach_key(),
+        jobs.clone(),
+        Arc::clone(&tickets),
+    )
+    .await
+    .expect("The WebSockets of the terminals can't be served");
+    tokio::spawn(attach_server.run());
+    let certificates = Arc::new(ClientCertificates::new());
+    let rocket = rocket(
+        config,
+        users,
+        signing_keys,
+        revocations,
+        keys,
+        audit,
+        jobs,
+        tickets,
+        Arc::clone(&certificates),
+    );
+    let config = rocket
+        .state::<Config>()
+        .expect("The configuration isn't managed");
+    if let Some(clients_ca) = &config.mtls_ca {
+        let gateway = MtlsGateway::bind(
+            config.mtls_address(),
+            clients_ca,
+            config.attach_certs(),
+            config.attach_key(),
+            upstream(rocket.config()),
+            certificates,
+        )
+        .await
+        .expect("The connections with mutual TLS can't be served");
+        tokio::spawn(gateway.run());
+    }
+    if let Err(err) = rocket.launch().await {
+        eprintln!("ERR: {}", err);
+        process::exit(1);
+    }
+}
+
+// Address where the gateway of mutual TLS reaches the HTTP API.
+fn upstream(config: &rocket::Config) -> SocketAddr {
+    let address = match config.address {
+        IpAddr::V4(address) if address.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        IpAddr::V6(address) if address.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        address => address,
+    };
+    SocketAddr::new(address, config.port)
+}
 
-#[launch]
-fn rocket() -> rocket::Rocket {
-    let data: JobData = RwLock::new(HashMap::new());
+#[allow(clippy::too_many_arguments)]
+fn rocket(
+    config: Config,
+    users: Arc<dyn UserStore>,
+    signing_keys: Arc<SigningKeys>,
+    revocations: Arc<RevocationList>,
+    keys: ApiKeys,
+    audit: AuditLog,
+    jobs: JobManager,
+    tickets: Arc<AttachTickets>,
+    certificates: Arc<ClientCertificates>,
+) -> rocket::Rocket {
+    let webhooks = Webhooks::new(DeliveryPolicy::default(), config.webhook_targets.clone())
+        .expect("The HTTP client for the webhooks can't be created");
+    webhooks.forget_removed_jobs(&jobs);
+    let oidc = config.oidc.clone().map(|oidc| {
+        OidcVerifier::new(oidc).expect("The HTTP client for the OIDC issuer can't be created")
+    });
+    let job_limits = JobLimits::load(
+        config.quota.clone(),
+        config.job_rate_limit,
+        config.job_address_rate_limit,
+        config.daily_jobs_file().to_path_buf(),
+    )
+    .expect("The jobs of the day can't be loaded");
+    let login_limits = LoginLimits::new(
+        config.login_address_rate_limit,
+        config.login_user_rate_limit,
+    );
     rocket::ignite()
-        .manage(data)
+        .manage(jobs)
+        .manage(tickets)
+        .manage(certificates)
+        .manage(webhooks)
+        .manage(config)
+        .manage(users)
+        .manage(signing_keys)
+        .manage(revocations)
+        .manage(keys)
+        .manage(oidc)
+        .manage(job_limits)
+        .manage(login_limits)
+        .manage(audit)
         .mount("/health", routes![health::health])
-        .mount("/auth", routes![auth::login])
+        .mount("/.well-known", routes![jwks::jwks])
+        .mount("/auth", routes![auth::login, auth::refresh, auth::logout])
+        .mount("/v1/events", routes![events::stream])
+        .mount("/v1/me", routes![me::quota])
+        .mount("/v1/audit", routes![audit_log::query])
+        .mount(
+            "/v1/api-keys",
+            routes![api_keys::create, api_keys::list, api_keys::revoke],
+        )
         .mount(
             "/v1/jobs",
-            routes![jobs::create, jobs::get, jobs::get_output, jobs::delete],
+            routes![
+                jobs::create,
+                jobs::create_with_stdin,
+                jobs::list,
+                jobs::get,
+                jobs::wait,
+                jobs::get_output,
+                jobs::delete_output,
+                jobs::list_artifacts,
+                jobs::get_artifact,
+                jobs::write_stdin,
+                jobs::attach,
+                jobs::delete,
+                jobs::share,
+                jobs::unshare,
+                jobs::get_webhook_deliveries
+            ],
         )
 }
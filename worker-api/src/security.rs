@@ -1,9 +1,52 @@
+mod api_key_record;
+mod api_key_scope;
+mod api_keys;
+mod caller;
 mod claims;
+mod command_policy;
 mod control;
+mod file_user_store;
+mod id_token_claims;
+mod jwk;
+mod jwk_set;
+mod oidc_config;
+mod oidc_verifier;
+mod password;
+mod policy_effect;
+mod policy_rule;
+mod private_file;
+mod refresh_claims;
+mod refresh_token;
+mod revocation_list;
+mod role;
+mod rsa_pem;
+mod signing_keys;
 mod token;
 mod user;
+mod user_record;
+mod user_store;
 
+pub use api_key_record::ApiKeyRecord;
+pub use api_key_scope::ApiKeyScope;
+pub use api_keys::ApiKeys;
+pub use caller::Caller;
 pub use claims::Claims;
-pub use control::login;
+pub use command_policy::CommandPolicy;
+pub use control::{login, logout, refresh};
+pub use file_user_store::FileUserStore;
+pub use jwk::Jwk;
+pub use jwk_set::JwkSet;
+pub use oidc_config::OidcConfig;
+pub use oidc_verifier::OidcVerifier;
+pub use password::hash_password;
+pub use policy_effect::PolicyEffect;
+pub use policy_rule::PolicyRule;
+pub use private_file::write_private;
+pub use refresh_token::RefreshToken;
+pub use revocation_list::RevocationList;
+pub use role::Role;
+pub use signing_keys::SigningKeys;
 pub use token::Token;
 pub use user::User;
+pub use user_record::UserRecord;
+pub use user_store::UserStore;
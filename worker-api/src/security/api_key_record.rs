@@ -0,0 +1,19 @@
+use super::ApiKeyScope;
+use serde::{Deserialize, Serialize};
+
+/// API key of a user, as it is kept in `ApiKeys`.  Only the hash of
+/// its secret is kept.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ApiKeyRecord {
+    pub id: String,
+    /// Id of the user that created the key.  Its requests act as it.
+    pub user_id: String,
+    pub user_name: String,
+    /// Description of the key, e.g. the CI system that uses it.
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// SHA-256 hash of the secret, hex encoded.
+    pub secret_hash: String,
+    pub created: usize,
+    pub expires: usize,
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+/// Operations on the jobs that an API key allows.  Keys with
+/// `jobs:read` can make `GET` requests and keys with `jobs:write` can
+/// make the rest of them.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum ApiKeyScope {
+    #[serde(rename = "jobs:read")]
+    JobsRead,
+    #[serde(rename = "jobs:write")]
+    JobsWrite,
+}
@@ -0,0 +1,183 @@
+use super::{private_file::write_private, ApiKeyRecord, ApiKeyScope};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use std::{fs, io, path::PathBuf, sync::Mutex};
+use uuid::Uuid;
+
+/// Prefix of the API keys, so they are easy to recognize.
+pub const API_KEY_PREFIX: &str = "wk_";
+
+/// API keys of the users.  They are kept in a JSON file, so they
+/// survive a restart, and the expired ones are forgotten when the keys
+/// change.
+#[derive(Default)]
+pub struct ApiKeys {
+    path: Option<PathBuf>,
+    keys: Mutex<Vec<ApiKeyRecord>>,
+}
+
+impl ApiKeys {
+    /// Load the keys from a file.  A missing file has no keys.
+    ///
+    /// * `path` - Path of the file.  It is written on every change.
+    pub fn load(path: PathBuf) -> io::Result<ApiKeys> {
+        let keys = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(ApiKeys {
+            path: Some(path),
+            keys: Mutex::new(keys),
+        })
+    }
+
+    /// Create a key for a user.  It returns the record of the key and
+    /// the key itself, which can't be recovered later.
+    ///
+    /// * `user_id` - Id of the user.
+    /// * `user_name` - Name of the user.
+    /// * `name` - Description of the key.
+    /// * `scopes` - Operations allowed with the key.
+    /// * `valid_days` - Days until the key expires.
+    pub fn create(
+        &self,
+        user_id: &str,
+        user_name: &str,
+        name: &str,
+        scopes: Vec<ApiKeyScope>,
+        valid_days: i64,
+    ) -> io::Result<(ApiKeyRecord, String)> {
+        let id = Uuid::new_v4().to_simple().to_string();
+        let secret = hex::encode(rand::random::<[u8; 32]>());
+        let now = Utc::now();
+        let record = ApiKeyRecord {
+            id: id.clone(),
+            user_id: user_id.to_string(),
+            user_name: user_name.to_string(),
+            name: name.to_string(),
+            scopes,
+            secret_hash: hash_secret(&secret),
+            created: now.timestamp() as usize,
+            expires: (now + Duration::days(valid_days)).timestamp() as usize,
+        };
+        let mut keys = self.keys.lock().unwrap();
+        keys.push(record.clone());
+        self.store(&mut keys)?;
+        Ok((record, format!("{}{}_{}", API_KEY_PREFIX, id, secret)))
+    }
+
+    /// Keys of a user that haven't expired.
+    ///
+    /// * `user_id` - Id of the user.
+    pub fn list(&self, user_id: &str) -> Vec<ApiKeyRecord> {
+        let now = Utc::now().timestamp() as usize;
+        self.keys
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|key| key.user_id == user_id && key.expires > now)
+            .cloned()
+            .collect()
+    }
+
+    /// Revoke a key of a user.  It returns `false` if the user doesn't
+    /// have a key with that id.
+    ///
+    /// * `user_id` - Id of the user.
+    /// * `id` - Id of the key.
+    pub fn revoke(&self, user_id: &str, id: &str) -> io::Result<bool> {
+        let mut keys = self.keys.lock().unwrap();
+        let count = keys.len();
+        keys.retain(|key| key.user_id != user_id || key.id != id);
+        if keys.len() == count {
+            return Ok(false);
+        }
+        self.store(&mut keys)?;
+        Ok(true)
+    }
+
+    /// Find the record of a key.  It returns `None` if the key doesn't
+    /// exist, it has been revoked or it has expired.
+    ///
+    /// * `key` - API key sent with a request.
+    pub fn authenticate(&self, key: &str) -> Option<ApiKeyRecord> {
+        let mut parts = key.strip_prefix(API_KEY_PREFIX)?.splitn(2, '_');
+        let (id, secret) = (parts.next()?, parts.next()?);
+        let now = Utc::now().timestamp() as usize;
+        let keys = self.keys.lock().unwrap();
+        let record = keys.iter().find(|key| key.id == id && key.expires > now)?;
+        if constant_time_eq(
+            record.secret_hash.as_bytes(),
+            hash_secret(secret).as_bytes(),
+        ) {
+            Some(record.clone())
+        } else {
+            None
+        }
+    }
+
+    // The expired keys are removed before writing the file.
+    fn store(&self, keys: &mut Vec<ApiKeyRecord>) -> io::Result<()> {
+        let now = Utc::now().timestamp() as usize;
+        keys.retain(|key| key.expires > now);
+        match &self.path {
+            Some(path) => {
+                let content = serde_json::to_vec_pretty(&*keys)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                write_private(path, &content)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+// The secrets are random, so a fast hash is enough.
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_authenticated_until_they_are_revoked() {
+        let path = std::env::temp_dir().join(format!("{}.json", Uuid::new_v4()));
+        let keys = ApiKeys::load(path.clone()).unwrap();
+        let (record, key) = keys
+            .create("id 1", "user 1", "ci", vec![ApiKeyScope::JobsRead], 30)
+            .unwrap();
+        assert!(key.starts_with(API_KEY_PREFIX));
+        assert!(!fs::read_to_string(&path)
+            .unwrap()
+            .contains(&key[key.len() - 64..]));
+
+        let reloaded = ApiKeys::load(path.clone()).unwrap();
+        assert_eq!(Some(record.clone()), reloaded.authenticate(&key));
+        assert_eq!(None, reloaded.authenticate(&format!("{}0", key)));
+        assert_eq!(vec![record.clone()], reloaded.list("id 1"));
+        assert!(reloaded.list("id 2").is_empty());
+
+        assert!(!reloaded.revoke("id 2", &record.id).unwrap());
+        assert!(reloaded.revoke("id 1", &record.id).unwrap());
+        assert_eq!(None, reloaded.authenticate(&key));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn expired_keys_are_refused() {
+        let keys = ApiKeys::default();
+        let (_, key) = keys
+            .create("id 1", "user 1", "ci", vec![ApiKeyScope::JobsWrite], -1)
+            .unwrap();
+
+        assert_eq!(None, keys.authenticate(&key));
+        assert!(keys.list("id 1").is_empty());
+    }
+}
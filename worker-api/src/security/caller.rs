@@ -0,0 +1,177 @@
+use super::{ApiKeyRecord, ApiKeyScope, ApiKeys, Claims, Role, UserRecord, UserStore};
+use crate::{
+    api::request::ClientAddress,
+    audit::{AuditAction, AuditEntry, AuditLog, AuditOutcome},
+    tls::{ClientCertificate, ClientCertificates},
+};
+use rocket::{
+    http::{Method, Status},
+    request::{FromRequest, Outcome, Request},
+    State,
+};
+use std::sync::Arc;
+
+/// User that makes a request on the jobs.  It is authenticated with an
+/// access token (`Authorization: Bearer ...`), with an API key
+/// (`Authorization: ApiKey ...`) whose scopes allow the method of the
+/// request, or with the client certificate of a connection with mutual
+/// TLS if the request has no `Authorization` header.
+pub struct Caller {
+    pub id: String,
+    pub name: String,
+    pub role: Role,
+    pub teams: Vec<String>,
+}
+
+impl Caller {
+    /// User whose operations on the jobs are authorized for this
+    /// caller.
+    pub fn principal(&self) -> worker::Principal<'_> {
+        worker::Principal {
+            id: &self.id,
+            role: self.role.into(),
+            teams: &self.teams,
+        }
+    }
+}
+
+impl From<UserRecord> for Caller {
+    fn from(user: UserRecord) -> Self {
+        Caller {
+            id: user.id,
+            name: user.name,
+            role: user.role,
+            teams: user.teams,
+        }
+    }
+}
+
+impl From<Claims> for Caller {
+    fn from(claims: Claims) -> Self {
+        Caller {
+            id: claims.sub,
+            name: claims.user_name,
+            role: claims.role,
+            teams: claims.teams,
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Caller {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        //! The requests that are refused are audited.
+        let api_key = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|header| header.strip_prefix("ApiKey"))
+            .map(|key| key.trim().to_string());
+        let certificate = match request.headers().get_one("Authorization") {
+            Some(_) => None,
+            None => client_certificate(request).await,
+        };
+        let outcome = match (api_key, certificate) {
+            (Some(key), _) => from_api_key(request, &key).await,
+            (None, Some(certificate)) => from_certificate(request, certificate).await,
+            (None, None) => match request.guard::<Claims>().await {
+                Outcome::Success(claims) => Outcome::Success(Caller::from(claims)),
+                _ => Outcome::Failure((Status::Unauthorized, ())),
+            },
+        };
+        // The API keys without the scope are audited with their user.
+        match &outcome {
+            Outcome::Failure((status, _)) if *status == Status::Unauthorized => {
+                audit_refusal(request, *status, None).await
+            }
+            _ => (),
+        }
+        outcome
+    }
+}
+
+// Record a request that isn't authenticated (`401`) or authorized
+// (`403`) in the audit log.
+async fn audit_refusal(request: &Request<'_>, status: Status, key: Option<&ApiKeyRecord>) {
+    let log = match request.guard::<State<'_, AuditLog>>().await {
+        Outcome::Success(log) => log,
+        _ => return,
+    };
+    let outcome = if status == Status::Forbidden {
+        AuditOutcome::Denied
+    } else {
+        AuditOutcome::Failure
+    };
+    let mut entry = AuditEntry::new(AuditAction::Authentication, outcome)
+        .target(request.uri())
+        .detail(request.method().as_str());
+    if let Some(key) = key {
+        entry = entry
+            .actor(&key.user_name, Some(&key.user_id))
+            .detail(format!("{} with API key {}", request.method(), key.id));
+    }
+    if let Outcome::Success(address) = request.guard::<ClientAddress>().await {
+        entry.address = address.0;
+    }
+    log.record(&entry);
+}
+
+async fn from_api_key(request: &Request<'_>, key: &str) -> Outcome<Caller, ()> {
+    let record = match request.guard::<State<'_, ApiKeys>>().await {
+        Outcome::Success(keys) => keys.authenticate(key),
+        _ => None,
+    };
+    let record = match record {
+        Some(record) => record,
+        None => return Outcome::Failure((Status::Unauthorized, ())),
+    };
+    let scope = match request.method() {
+        Method::Get | Method::Head => ApiKeyScope::JobsRead,
+        _ => ApiKeyScope::JobsWrite,
+    };
+    if !record.scopes.contains(&scope) {
+        audit_refusal(request, Status::Forbidden, Some(&record)).await;
+        return Outcome::Failure((Status::Forbidden, ()));
+    }
+    // The role and the teams are the current ones of the user.
+    match find_user(request, &record.user_name).await {
+        Some(user) if user.id == record.user_id => Outcome::Success(Caller::from(user)),
+        _ => Outcome::Failure((Status::Unauthorized, ())),
+    }
+}
+
+// Certificate of the client if the request has been made through the
+// gateway of mutual TLS.
+async fn client_certificate(request: &Request<'_>) -> Option<ClientCertificate> {
+    match request.guard::<State<'_, Arc<ClientCertificates>>>().await {
+        Outcome::Success(certificates) => certificates.find(request.remote()),
+        _ => None,
+    }
+}
+
+// The identity of the certificate is the name of the user.
+async fn from_certificate(
+    request: &Request<'_>,
+    certificate: ClientCertificate,
+) -> Outcome<Caller, ()> {
+    match find_user(request, &certificate.identity).await {
+        Some(user) => Outcome::Success(Caller::from(user)),
+        None => Outcome::Failure((Status::Unauthorized, ())),
+    }
+}
+
+// User with the given name, unless it is disabled.
+async fn find_user(request: &Request<'_>, name: &str) -> Option<UserRecord> {
+    let users = match request.guard::<State<'_, Arc<dyn UserStore>>>().await {
+        Outcome::Success(users) => Arc::clone(&users),
+        _ => return None,
+    };
+    let name = name.to_string();
+    tokio::task::spawn_blocking(move || users.find(&name))
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .flatten()
+        .filter(|user| !user.disabled)
+}
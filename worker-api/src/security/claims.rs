@@ -1,16 +1,25 @@
-use super::control;
+use super::{control, OidcVerifier, RevocationList, Role, SigningKeys};
 use rocket::{
     http::Status,
     request::{FromRequest, Outcome, Request},
+    State,
 };
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Deserialize, Serialize)]
 pub struct Claims {
     pub sub: String,
     pub user_name: String,
+    pub aud: String,
+    /// Id of the token, so it can be revoked.
+    pub jti: String,
     pub iat: usize,
     pub exp: usize,
+    #[serde(default)]
+    pub role: Role,
+    #[serde(default)]
+    pub teams: Vec<String>,
 }
 
 #[rocket::async_trait]
@@ -21,11 +30,39 @@ impl<'r> FromRequest<'r> for Claims {
         if let Some(auth_header) = request.headers().get_one("Authorization") {
             let auth_str = auth_header.to_string();
             if let Some(token) = auth_str.strip_prefix("Bearer").map(|s| s.trim()) {
-                if let Some(claims) = control::decode_token(token) {
-                    return Outcome::Success(claims);
+                let claims = match request.guard::<State<'_, Arc<SigningKeys>>>().await {
+                    Outcome::Success(keys) => control::decode_token(token, &keys),
+                    _ => None,
+                };
+                let claims = match claims {
+                    Some(claims) => Some(claims),
+                    None => from_id_token(request, token).await,
+                };
+                if let Some(claims) = claims {
+                    // Tokens that have been revoked are refused until
+                    // they expire.
+                    if let Outcome::Success(revocations) =
+                        request.guard::<State<'_, Arc<RevocationList>>>().await
+                    {
+                        if !revocations.is_revoked(&claims.jti) {
+                            return Outcome::Success(claims);
+                        }
+                    }
                 }
             }
         }
         Outcome::Failure((Status::Unauthorized, ()))
     }
 }
+
+// ID tokens of the OpenID Connect issuer are accepted too, if there is
+// one.
+async fn from_id_token(request: &Request<'_>, token: &str) -> Option<Claims> {
+    match request.guard::<State<'_, Option<OidcVerifier>>>().await {
+        Outcome::Success(oidc) => match oidc.as_ref() {
+            Some(verifier) => verifier.verify(token).await,
+            None => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,395 @@
+use super::{PolicyEffect, PolicyRule, Role};
+use serde::Deserialize;
+use std::{
+    env, fs, iter,
+    path::{Component, Path, PathBuf},
+};
+
+// Programs that run the command of their arguments, with their options
+// that take a value.  The commands that they run are checked too.
+const WRAPPERS: &[(&str, &[&str])] = &[
+    ("env", &["-u", "--unset", "-C", "--chdir"]),
+    ("nice", &["-n", "--adjustment"]),
+    ("nohup", &[]),
+    ("setsid", &[]),
+    (
+        "stdbuf",
+        &["-i", "--input", "-o", "--output", "-e", "--error"],
+    ),
+    ("timeout", &["-k", "--kill-after", "-s", "--signal"]),
+];
+
+/// Policy that allows or denies the jobs that users can create,
+/// depending on their executable and their arguments.  It is loaded
+/// from the JSON file of `WORKER_API_POLICY_FILE`, e.g.:
+///
+/// ```json
+/// {
+///   "default": "deny",
+///   "rules": [
+///     { "id": "no-clean", "effect": "deny", "executable": "/usr/bin/make", "args": "clean*" },
+///     { "id": "operators", "effect": "allow", "roles": ["operator"], "executable": "/opt/tools/*" },
+///     { "id": "build", "effect": "allow", "executable": "/usr/bin/make" }
+///   ]
+/// }
+/// ```
+///
+/// The first rule that matches a job decides, and the default effect
+/// of a policy file is `deny`.  Without a policy, every job is
+/// allowed.
+///
+/// The commands run by `env`, `nice`, `nohup`, `setsid`, `stdbuf` and
+/// `timeout` must be allowed as well.  Any other program that runs
+/// commands, like a shell, an interpreter, `xargs` or `find`, can run
+/// whatever is denied, so the rules must allow the programs that jobs
+/// need by name instead of whole directories like `/usr/bin/*`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CommandPolicy {
+    #[serde(default)]
+    pub default: PolicyEffect,
+    pub rules: Vec<PolicyRule>,
+}
+
+/// Identifier returned when a job is denied by the default effect.
+pub const DEFAULT_RULE_ID: &str = "default";
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        CommandPolicy {
+            default: PolicyEffect::Allow,
+            rules: Vec::new(),
+        }
+    }
+}
+
+impl CommandPolicy {
+    /// Load the policy from a JSON file and validate its rules.
+    ///
+    /// * `path` - Path of the file.
+    pub fn load(path: &Path) -> Result<CommandPolicy, String> {
+        let content = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&content)
+    }
+
+    fn parse(content: &str) -> Result<CommandPolicy, String> {
+        let policy: CommandPolicy = serde_json::from_str(content).map_err(|err| err.to_string())?;
+        for rule in &policy.rules {
+            rule.validate()?;
+        }
+        Ok(policy)
+    }
+
+    /// Check if a user can create a job with the given command line.
+    /// If it is denied, the error contains the id of the rule.  The
+    /// commands run by wrappers like `env` are checked as well, and the
+    /// first one that is denied denies the job.
+    ///
+    /// * `sub` - Subject of the user that creates the job.
+    /// * `user_name` - Name of the user that creates the job.
+    /// * `role` - Role of the user that creates the job.
+    /// * `command_line` - Command line of the job.
+    /// * `working_dir` - Directory where the job will run.
+    pub fn check(
+        &self,
+        sub: &str,
+        user_name: &str,
+        role: Role,
+        command_line: &str,
+        working_dir: &Path,
+    ) -> Result<(), String> {
+        // The command line is split like the worker does.
+        let mut command = command_line.split_whitespace().collect::<Vec<_>>();
+        loop {
+            let (program, args) = command.split_first().unwrap_or((&"", &[]));
+            let executable = Self::resolve(program, working_dir);
+            let (effect, rule_id) = self
+                .rules
+                .iter()
+                .find(|rule| rule.matches(sub, user_name, role, &executable, &args.join(" ")))
+                .map_or((self.default, DEFAULT_RULE_ID), |rule| {
+                    (rule.effect, rule.id.as_str())
+                });
+            if effect == PolicyEffect::Deny {
+                return Err(rule_id.to_string());
+            }
+            match Self::wrapped_command(&executable, args) {
+                Some(wrapped) => command = wrapped,
+                None => return Ok(()),
+            }
+        }
+    }
+
+    // Command run by a wrapper like `env`, if the executable is one.
+    // `env -S` splits its value into the command, and the worker has
+    // already split it, so the value is the start of the command.
+    fn wrapped_command<'a>(executable: &str, args: &[&'a str]) -> Option<Vec<&'a str>> {
+        let name = Path::new(executable).file_name()?.to_str()?;
+        let (name, with_value) = WRAPPERS.iter().find(|(wrapper, _)| *wrapper == name)?;
+        let mut args = args.iter().copied().peekable();
+        while let Some(arg) = args.peek().copied() {
+            if *name == "env" {
+                let split = arg
+                    .strip_prefix("--split-string=")
+                    .or_else(|| arg.strip_prefix("-S"));
+                if let Some(start) = split {
+                    args.next();
+                    let wrapped: Vec<_> = iter::once(start)
+                        .filter(|start| !start.is_empty())
+                        .chain(args)
+                        .collect();
+                    return Some(wrapped).filter(|wrapped| !wrapped.is_empty());
+                }
+            }
+            if arg == "--" {
+                args.next();
+                break;
+            } else if arg.starts_with('-') && arg != "-" {
+                args.next();
+                if with_value.contains(&arg) {
+                    args.next();
+                }
+            } else if *name == "env" && (arg == "-" || arg.contains('=')) {
+                args.next();
+            } else {
+                break;
+            }
+        }
+        // The duration goes before the command.
+        if *name == "timeout" {
+            args.next();
+        }
+        let wrapped: Vec<_> = args.collect();
+        Some(wrapped).filter(|wrapped| !wrapped.is_empty())
+    }
+
+    // Find the executable that the job will run, so the rules can use
+    // absolute paths: programs without a path are looked up in the
+    // `PATH` and the rest are relative to the working directory.  The
+    // symbolic links are followed, so different paths of the same
+    // program are the same for the rules.  The files that don't exist
+    // yet, like those of a workspace, are only normalized.
+    fn resolve(program: &str, working_dir: &Path) -> String {
+        let path = if program.contains('/') {
+            working_dir.join(program)
+        } else {
+            match env::var_os("PATH").and_then(|paths| {
+                env::split_paths(&paths)
+                    .map(|dir| working_dir.join(dir).join(program))
+                    .find(|path| path.is_file())
+            }) {
+                Some(path) => path,
+                None => return program.to_string(),
+            }
+        };
+        fs::canonicalize(&path)
+            .unwrap_or_else(|_| Self::normalize(&path))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    // Remove the `.` and `..` components of an absolute path without
+    // accessing the file system.
+    fn normalize(path: &Path) -> PathBuf {
+        let mut normalized = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                component => normalized.push(component),
+            }
+        }
+        normalized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use uuid::Uuid;
+
+    const POLICY: &str = r#"{
+        "default": "deny",
+        "rules": [
+            { "id": "no-rm-rf", "effect": "deny", "executable": "ROOT/bin/rm", "args": "-rf *" },
+            { "id": "no-shells", "effect": "deny", "executable": "ROOT/bin/sh" },
+            { "id": "admin", "effect": "allow", "users": ["root"], "executable": "ROOT/*/*" },
+            { "id": "operators", "effect": "allow", "roles": ["operator"], "executable": "ROOT/opt/*" },
+            { "id": "bin", "effect": "allow", "executable": "ROOT/bin/*" }
+        ]
+    }"#;
+
+    // Directory with the programs `bin/rm`, `bin/sh`, `bin/env`,
+    // `bin/timeout` and `opt/tool`, and a link to `bin/sh`, and the
+    // policy of its programs.
+    fn programs() -> (PathBuf, CommandPolicy) {
+        let root = fs::canonicalize(env::temp_dir())
+            .unwrap()
+            .join(Uuid::new_v4().to_string());
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::create_dir_all(root.join("opt")).unwrap();
+        for program in &["bin/rm", "bin/sh", "bin/env", "bin/timeout", "opt/tool"] {
+            fs::write(root.join(program), b"").unwrap();
+        }
+        symlink(root.join("bin/sh"), root.join("link")).unwrap();
+        let policy = CommandPolicy::parse(&POLICY.replace("ROOT", &root.to_string_lossy()));
+        (root, policy.unwrap())
+    }
+
+    fn check(
+        policy: &CommandPolicy,
+        user_name: &str,
+        role: Role,
+        command_line: &str,
+        working_dir: &Path,
+    ) -> Result<(), String> {
+        policy.check("sub", user_name, role, command_line, working_dir)
+    }
+
+    #[test]
+    fn first_matching_rule_decides() {
+        let (root, policy) = programs();
+        let rm = root.join("bin/rm").to_string_lossy().into_owned();
+        let tool = root.join("opt/tool").to_string_lossy().into_owned();
+
+        assert_eq!(
+            Ok(()),
+            check(
+                &policy,
+                "jorge",
+                Role::Submitter,
+                &format!("{} -f file", rm),
+                &root
+            )
+        );
+        assert_eq!(
+            Err("no-rm-rf".to_string()),
+            check(
+                &policy,
+                "jorge",
+                Role::Submitter,
+                &format!("{} -rf /", rm),
+                &root
+            )
+        );
+        assert_eq!(
+            Ok(()),
+            check(&policy, "root", Role::Submitter, &tool, &root)
+        );
+        assert_eq!(
+            Ok(()),
+            check(&policy, "jorge", Role::Operator, &tool, &root)
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn unmatched_jobs_get_default_effect() {
+        let (root, policy) = programs();
+
+        assert_eq!(
+            Err(DEFAULT_RULE_ID.to_string()),
+            check(&policy, "jorge", Role::Submitter, "opt/tool", &root)
+        );
+        assert_eq!(
+            Err(DEFAULT_RULE_ID.to_string()),
+            check(&policy, "jorge", Role::Submitter, "bin/sub/dir", &root)
+        );
+        assert_eq!(
+            Ok(()),
+            check(
+                &CommandPolicy::default(),
+                "jorge",
+                Role::Submitter,
+                "/sbin/reboot",
+                &root
+            )
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn executables_are_resolved_before_matching() {
+        let (root, policy) = programs();
+        let bin = root.join("bin");
+        let absolute = root.join("bin//sh").to_string_lossy().into_owned();
+        let parent = root.join("opt/../bin/sh").to_string_lossy().into_owned();
+
+        for (command_line, working_dir) in &[
+            (absolute.as_str(), &root),
+            (parent.as_str(), &root),
+            ("./link", &root),
+            ("./sh", &bin),
+            ("../bin/sh", &bin),
+        ] {
+            assert_eq!(
+                Err("no-shells".to_string()),
+                check(&policy, "jorge", Role::Submitter, command_line, working_dir),
+                "{}",
+                command_line
+            );
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn commands_of_wrappers_are_checked() {
+        let (root, policy) = programs();
+        let bin = root.join("bin");
+
+        for command_line in &[
+            "./env ./sh -c id",
+            "./env -i HOME=/ -u PATH ./sh",
+            "./env -S./sh -c id",
+            "./env --split-string ./sh",
+            "./env -- ./env ./sh",
+            "./timeout -s KILL 10 ./sh",
+        ] {
+            assert_eq!(
+                Err("no-shells".to_string()),
+                check(&policy, "jorge", Role::Submitter, command_line, &bin),
+                "{}",
+                command_line
+            );
+        }
+        assert_eq!(
+            Err("no-rm-rf".to_string()),
+            check(&policy, "jorge", Role::Submitter, "./env ./rm -rf /", &bin)
+        );
+        assert_eq!(
+            Ok(()),
+            check(
+                &policy,
+                "jorge",
+                Role::Submitter,
+                "./env -u HOME ./rm -f file",
+                &bin
+            )
+        );
+        assert_eq!(
+            Ok(()),
+            check(&policy, "jorge", Role::Submitter, "./env", &bin)
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        let policy = r#"{ "rules": [ { "id": "bad", "effect": "allow", "executable": "[z-a" } ] }"#;
+
+        assert!(CommandPolicy::parse(policy).unwrap_err().contains("bad"));
+    }
+
+    #[test]
+    fn programs_are_found_in_path() {
+        let executable = CommandPolicy::resolve("sh", Path::new("/"));
+
+        assert!(executable.starts_with('/') && Path::new(&executable).is_file());
+        assert_eq!(
+            "/tmp/workspace/script",
+            CommandPolicy::resolve("./script", Path::new("/tmp/workspace/bin/.."))
+        );
+    }
+}
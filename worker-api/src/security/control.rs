@@ -1,55 +1,214 @@
-use super::{claims::Claims, user::User};
+use super::{
+    claims::Claims, password, refresh_claims::RefreshClaims, user::User, RevocationList,
+    SigningKeys, Token, UserRecord, UserStore,
+};
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use std::env;
-
-pub fn login(user: User) -> Option<String> {
-    let uid;
-    // FIXME: The authentication is hardcoded for demonstration
-    // purposes. DON'T use in a production system.
-    match user.name.as_ref() {
-        "jorge" => {
-            uid = String::from("f14f83b7-c626-4255-9da4-cec1ac22b4a1");
-        }
-        "joel" => {
-            uid = String::from("c0e38e26-8364-4bac-aed0-f0463945557b");
-        }
-        _ => {
-            return None;
+use std::io;
+use uuid::Uuid;
+
+const ACCESS_TOKEN_MINUTES: i64 = 15;
+const REFRESH_TOKEN_DAYS: i64 = 30;
+const ACCESS_AUDIENCE: &str = "worker-api";
+const REFRESH_AUDIENCE: &str = "worker-api/refresh";
+
+/// Authenticate a user and return new tokens for it.  It returns
+/// `None` if the user doesn't exist, it is disabled or the password is
+/// wrong.
+///
+/// * `user` - Name and password used to log in.
+/// * `users` - Store of the users.
+/// * `keys` - Keys that sign the tokens.
+pub fn login(user: User, users: &dyn UserStore, keys: &SigningKeys) -> Option<Token> {
+    let record = users.find(&user.name).unwrap_or_else(|err| {
+        eprintln!("The users can't be read: {}", err);
+        None
+    });
+    // A hash is always verified, so every refused login takes the same
+    // time.
+    let hash = match &record {
+        Some(record) if !record.disabled => record.password_hash.as_str(),
+        _ => password::UNUSABLE_HASH,
+    };
+    if !password::verify_password(hash, &user.password) {
+        return None;
+    }
+    issue(record?, keys)
+}
+
+/// Exchange a refresh token for new tokens.  The refresh token is
+/// revoked, so it can only be used once even by concurrent requests,
+/// and the role and the teams of the user are read again.
+///
+/// * `refresh_token` - Refresh token returned with the previous tokens.
+/// * `users` - Store of the users.
+/// * `revocations` - Tokens that have been revoked.
+/// * `keys` - Keys that sign the tokens.
+pub fn refresh(
+    refresh_token: &str,
+    users: &dyn UserStore,
+    revocations: &RevocationList,
+    keys: &SigningKeys,
+) -> Option<Token> {
+    let claims: RefreshClaims = keys.decode(refresh_token, REFRESH_AUDIENCE)?;
+    if revocations.is_revoked(&claims.jti) {
+        return None;
+    }
+    let record = users.find(&claims.user_name).ok().flatten()?;
+    if record.disabled || record.id != claims.sub {
+        return None;
+    }
+    // Another request may have used the token since it was checked.
+    if !revocations.revoke_once(&claims.jti, claims.exp).ok()? {
+        return None;
+    }
+    issue(record, keys)
+}
+
+/// Revoke the access token of a user and its refresh token.
+///
+/// * `claims` - Claims of the access token.
+/// * `refresh_token` - Refresh token returned with the access token.
+/// It is ignored if it isn't valid or it belongs to another user.
+/// * `revocations` - Tokens that have been revoked.
+/// * `keys` - Keys that sign the tokens.
+pub fn logout(
+    claims: &Claims,
+    refresh_token: &str,
+    revocations: &RevocationList,
+    keys: &SigningKeys,
+) -> io::Result<()> {
+    revocations.revoke(&claims.jti, claims.exp)?;
+    let refresh_claims: Option<RefreshClaims> = keys.decode(refresh_token, REFRESH_AUDIENCE);
+    match refresh_claims {
+        Some(refresh_claims) if refresh_claims.sub == claims.sub => {
+            revocations.revoke(&refresh_claims.jti, refresh_claims.exp)
         }
+        _ => Ok(()),
     }
+}
 
-    let now = Utc::now();
-    let expiration = now + Duration::days(1);
+/// Decode an access token.  It returns `None` if it isn't valid or it
+/// has expired, but revocation is checked by the caller.
+///
+/// * `token` - Access token.
+/// * `keys` - Keys that sign the tokens.
+pub fn decode_token(token: &str, keys: &SigningKeys) -> Option<Claims> {
+    keys.decode(token, ACCESS_AUDIENCE)
+}
 
+// Every token has a different id, so it can be revoked.
+fn issue(record: UserRecord, keys: &SigningKeys) -> Option<Token> {
+    let now = Utc::now();
+    let refresh_claims = RefreshClaims {
+        sub: record.id.clone(),
+        user_name: record.name.clone(),
+        aud: REFRESH_AUDIENCE.to_string(),
+        jti: Uuid::new_v4().to_string(),
+        iat: now.timestamp() as usize,
+        exp: (now + Duration::days(REFRESH_TOKEN_DAYS)).timestamp() as usize,
+    };
     let claims = Claims {
-        sub: uid,
-        user_name: user.name,
+        sub: record.id,
+        user_name: record.name,
+        aud: ACCESS_AUDIENCE.to_string(),
+        jti: Uuid::new_v4().to_string(),
         iat: now.timestamp() as usize,
-        exp: expiration.timestamp() as usize,
+        exp: (now + Duration::minutes(ACCESS_TOKEN_MINUTES)).timestamp() as usize,
+        role: record.role,
+        teams: record.teams,
     };
-    let header = Header::default();
-    let secret = get_secret();
-    encode(&header, &claims, &EncodingKey::from_secret(secret.as_ref())).ok()
+    Some(Token {
+        token: keys.encode(&claims)?,
+        refresh_token: keys.encode(&refresh_claims)?,
+    })
 }
 
-pub fn decode_token(token: &str) -> Option<Claims> {
-    let secret = get_secret();
-    decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::default(),
-    )
-    .map(|token_data| token_data.claims)
-    .ok()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::Role;
+    use std::{io, sync::Mutex};
+
+    const SECRET: &str = "s3cr3t";
+
+    struct MemoryUserStore(Mutex<Vec<UserRecord>>);
 
-fn get_secret() -> String {
-    const JWT_SECRET: &str = "secret";
-    let secret = env::var("WORKER_API_SECRET").unwrap_or_else(|_| JWT_SECRET.to_string());
-    if secret == JWT_SECRET {
-        eprintln!("USING DEFAULT SECRET: THIS INSECURE BEHAVIOR IS ONLY MEANT FOR TESTING");
+    impl UserStore for MemoryUserStore {
+        fn find(&self, name: &str) -> io::Result<Option<UserRecord>> {
+            let users = self.0.lock().unwrap();
+            Ok(users.iter().find(|user| user.name == name).cloned())
+        }
+
+        fn save(&self, user: UserRecord) -> io::Result<()> {
+            self.0.lock().unwrap().push(user);
+            Ok(())
+        }
     }
 
-    secret
+    fn login_as(users: &MemoryUserStore, name: &str, password: &str) -> Option<Claims> {
+        let user = User {
+            name: name.to_string(),
+            password: password.to_string(),
+        };
+        let keys = SigningKeys::from_secret(SECRET);
+        login(user, users, &keys).and_then(|token| decode_token(&token.token, &keys))
+    }
+
+    #[test]
+    fn only_enabled_users_with_their_password_log_in() {
+        let users = MemoryUserStore(Mutex::new(Vec::new()));
+        let mut alice = UserRecord {
+            id: "f14f83b7-c626-4255-9da4-cec1ac22b4a1".to_string(),
+            name: "alice".to_string(),
+            password_hash: password::hash_password("secret").unwrap(),
+            role: Role::Operator,
+            teams: vec!["ops".to_string()],
+            disabled: false,
+        };
+        users.save(alice.clone()).unwrap();
+
+        let claims = login_as(&users, "alice", "secret").unwrap();
+        assert_eq!(alice.id, claims.sub);
+        assert_eq!("alice", claims.user_name);
+        assert_eq!(Role::Operator, claims.role);
+        assert_eq!(vec!["ops".to_string()], claims.teams);
+        assert!(login_as(&users, "alice", "wrong").is_none());
+        assert!(login_as(&users, "bob", "secret").is_none());
+
+        alice.disabled = true;
+        *users.0.lock().unwrap() = vec![alice];
+        assert!(login_as(&users, "alice", "secret").is_none());
+    }
+
+    #[test]
+    fn refresh_tokens_are_rotated_and_revoked_on_logout() {
+        let users = MemoryUserStore(Mutex::new(Vec::new()));
+        users
+            .save(UserRecord {
+                id: "f14f83b7-c626-4255-9da4-cec1ac22b4a1".to_string(),
+                name: "alice".to_string(),
+                password_hash: password::hash_password("secret").unwrap(),
+                role: Role::Submitter,
+                teams: Vec::new(),
+                disabled: false,
+            })
+            .unwrap();
+        let revocations = RevocationList::default();
+        let keys = SigningKeys::from_secret(SECRET);
+        let user = User {
+            name: "alice".to_string(),
+            password: "secret".to_string(),
+        };
+        let first = login(user, &users, &keys).unwrap();
+        assert!(decode_token(&first.refresh_token, &keys).is_none());
+        assert!(refresh(&first.token, &users, &revocations, &keys).is_none());
+
+        let second = refresh(&first.refresh_token, &users, &revocations, &keys).unwrap();
+
+        assert!(refresh(&first.refresh_token, &users, &revocations, &keys).is_none());
+        let claims = decode_token(&second.token, &keys).unwrap();
+        logout(&claims, &second.refresh_token, &revocations, &keys).unwrap();
+        assert!(revocations.is_revoked(&claims.jti));
+        assert!(refresh(&second.refresh_token, &users, &revocations, &keys).is_none());
+    }
 }
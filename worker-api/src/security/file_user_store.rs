@@ -0,0 +1,87 @@
+use super::{private_file::write_private, UserRecord, UserStore};
+use std::{fs, io, path::PathBuf};
+
+/// `UserStore` kept in a JSON file with an array of `UserRecord`s.
+/// The file is read on every lookup, so the changes made by `worker-api
+/// user` are used without restarting the server.  A missing file is an
+/// empty store.
+pub struct FileUserStore {
+    path: PathBuf,
+}
+
+impl FileUserStore {
+    /// Create a store for a file.  The file is created when the first
+    /// user is saved.
+    ///
+    /// * `path` - Path of the file.
+    pub fn new(path: PathBuf) -> FileUserStore {
+        FileUserStore { path }
+    }
+
+    fn load(&self) -> io::Result<Vec<UserRecord>> {
+        match fs::read(&self.path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err),
+        }
+    }
+
+    // The file contains the password hashes.
+    fn store(&self, users: &[UserRecord]) -> io::Result<()> {
+        let content = serde_json::to_vec_pretty(users)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        write_private(&self.path, &content)
+    }
+}
+
+impl UserStore for FileUserStore {
+    fn find(&self, name: &str) -> io::Result<Option<UserRecord>> {
+        Ok(self.load()?.into_iter().find(|user| user.name == name))
+    }
+
+    fn save(&self, user: UserRecord) -> io::Result<()> {
+        let mut users = self.load()?;
+        match users.iter_mut().find(|stored| stored.name == user.name) {
+            Some(stored) => *stored = user,
+            None => users.push(user),
+        }
+        self.store(&users)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::Role;
+    use std::os::unix::fs::PermissionsExt;
+    use uuid::Uuid;
+
+    fn user(name: &str, password_hash: &str) -> UserRecord {
+        UserRecord {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            password_hash: password_hash.to_string(),
+            role: Role::default(),
+            teams: Vec::new(),
+            disabled: false,
+        }
+    }
+
+    #[test]
+    fn saved_users_are_found() {
+        let path = std::env::temp_dir().join(format!("{}.json", Uuid::new_v4()));
+        let store = FileUserStore::new(path.clone());
+        assert_eq!(None, store.find("alice").unwrap());
+
+        store.save(user("alice", "first")).unwrap();
+        store.save(user("bob", "second")).unwrap();
+        store.save(user("alice", "third")).unwrap();
+
+        assert_eq!("third", store.find("alice").unwrap().unwrap().password_hash);
+        assert_eq!("second", store.find("bob").unwrap().unwrap().password_hash);
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Claims of an OpenID Connect ID token that are used.  The issuer,
+/// the audience and the expiration are validated when it is decoded.
+#[derive(Deserialize, Debug)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    pub preferred_username: Option<String>,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub jti: String,
+    pub iat: usize,
+    pub exp: usize,
+    /// The rest of the claims, which include the groups.
+    #[serde(flatten)]
+    pub other: HashMap<String, Value>,
+}
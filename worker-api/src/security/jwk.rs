@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// Public key of a JSON Web Key Set (RFC 7517).  Only RSA keys are
+/// used.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    /// `sig` for the keys that sign tokens.
+    #[serde(rename = "use", skip_serializing_if = "Option::is_none")]
+    pub key_use: Option<String>,
+    /// Modulus of an RSA key, base64url encoded.
+    #[serde(default)]
+    pub n: String,
+    /// Exponent of an RSA key, base64url encoded.
+    #[serde(default)]
+    pub e: String,
+}
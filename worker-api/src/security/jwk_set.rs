@@ -0,0 +1,9 @@
+use super::Jwk;
+use serde::{Deserialize, Serialize};
+
+/// JSON Web Key Set, as published by an OpenID Connect issuer or by
+/// `worker-api` itself.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
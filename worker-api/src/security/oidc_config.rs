@@ -0,0 +1,115 @@
+use super::Role;
+use std::collections::HashMap;
+
+/// Settings of the OpenID Connect issuer whose ID tokens are accepted.
+#[derive(Clone, Debug)]
+pub struct OidcConfig {
+    /// Issuer (`iss`) of the ID tokens.  Its keys are discovered from
+    /// `<issuer>/.well-known/openid-configuration`.
+    pub issuer: String,
+    /// Audience (`aud`) of the ID tokens, i.e. the client id of
+    /// `worker-cli` at the issuer.
+    pub audience: String,
+    /// Claim of the ID tokens with the groups of the user.
+    pub groups_claim: String,
+    /// Role of the members of each group.
+    pub role_groups: HashMap<String, Role>,
+    /// Groups that are the teams of their members.  The other groups
+    /// only decide the role.
+    pub team_groups: Vec<String>,
+}
+
+impl OidcConfig {
+    /// Prefix of the subjects and the names of the users of the
+    /// issuer, so they can't be mistaken for the local users.
+    pub const PREFIX: &'static str = "oidc:";
+
+    /// Subject of a user of the issuer: `oidc:<issuer>:<sub>`.
+    ///
+    /// * `sub` - Subject of the ID token.
+    pub fn subject(&self, sub: &str) -> String {
+        format!("{}{}:{}", Self::PREFIX, self.issuer, sub)
+    }
+
+    /// Name of a user of the issuer: `oidc:<name>`.
+    ///
+    /// * `name` - Name of the user in the ID token.
+    pub fn user_name(&self, name: &str) -> String {
+        format!("{}{}", Self::PREFIX, name)
+    }
+
+    /// Teams of a user: its groups that are configured as teams.
+    ///
+    /// * `groups` - Groups of the user in its ID token.
+    pub fn teams(&self, groups: &[String]) -> Vec<String> {
+        groups
+            .iter()
+            .filter(|group| self.team_groups.contains(group))
+            .cloned()
+            .collect()
+    }
+
+    /// Role of a user: the most privileged one of its groups, or
+    /// submitter if none of them has a role.
+    ///
+    /// * `groups` - Groups of the user in its ID token.
+    pub fn role(&self, groups: &[String]) -> Role {
+        groups
+            .iter()
+            .filter_map(|group| self.role_groups.get(group).copied())
+            .max_by_key(|role| match role {
+                Role::Viewer => 0,
+                Role::Submitter => 1,
+                Role::Operator => 2,
+                Role::Admin => 3,
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_privileged_role_of_the_groups_is_used() {
+        let config = OidcConfig {
+            issuer: "https://issuer.example".to_string(),
+            audience: "worker-cli".to_string(),
+            groups_claim: "groups".to_string(),
+            role_groups: vec![
+                ("sre".to_string(), Role::Operator),
+                ("auditors".to_string(), Role::Viewer),
+            ]
+            .into_iter()
+            .collect(),
+            team_groups: Vec::new(),
+        };
+        let groups = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(Role::Operator, config.role(&groups(&["auditors", "sre"])));
+        assert_eq!(Role::Viewer, config.role(&groups(&["auditors", "qa"])));
+        assert_eq!(Role::Submitter, config.role(&groups(&["qa"])));
+    }
+
+    #[test]
+    fn only_configured_groups_are_teams() {
+        let config = OidcConfig {
+            issuer: "https://issuer.example".to_string(),
+            audience: "worker-cli".to_string(),
+            groups_claim: "groups".to_string(),
+            role_groups: HashMap::new(),
+            team_groups: vec!["ops".to_string()],
+        };
+        let groups = vec!["ops".to_string(), "everyone".to_string()];
+
+        assert_eq!(vec!["ops".to_string()], config.teams(&groups));
+        assert_eq!("oidc:https://issuer.example:42", config.subject("42"));
+        assert_eq!("oidc:root", config.user_name("root"));
+    }
+}
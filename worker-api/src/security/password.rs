@@ -0,0 +1,58 @@
+use argon2::{Config, ThreadMode, Variant, Version};
+
+// Argon2id with the minimum parameters recommended by OWASP.
+const MEMORY_COST_KIB: u32 = 19456;
+const TIME_COST: u32 = 2;
+
+/// Hash of a password that never matches.  It is verified when a user
+/// doesn't exist or it is disabled, so the time to refuse a login
+/// doesn't reveal which users exist.
+pub const UNUSABLE_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$z9z6cyo8pmTO/ZPZAUifNA$IKwCczHZY59pb8oJ8sUjkJZ0Toj3j9QMwDuufRwH+Uo";
+
+fn config() -> Config<'static> {
+    Config {
+        variant: Variant::Argon2id,
+        version: Version::Version13,
+        mem_cost: MEMORY_COST_KIB,
+        time_cost: TIME_COST,
+        lanes: 1,
+        thread_mode: ThreadMode::Sequential,
+        secret: &[],
+        ad: &[],
+        hash_length: 32,
+    }
+}
+
+/// Hash a password with Argon2id and a random salt.  It returns the
+/// hash in PHC string format.
+///
+/// * `password` - Password in plain text.
+pub fn hash_password(password: &str) -> Result<String, argon2::Error> {
+    let salt: [u8; 16] = rand::random();
+    argon2::hash_encoded(password.as_bytes(), &salt, &config())
+}
+
+/// Check a password against its hash.  The hashes are compared in
+/// constant time.
+///
+/// * `hash` - Hash in PHC string format.
+/// * `password` - Password in plain text.
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    argon2::verify_encoded(hash, password.as_bytes()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_hashed_password_is_verified() {
+        let hash = hash_password("correct horse").unwrap();
+
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password(&hash, "correct horse"));
+        assert!(!verify_password(&hash, "battery staple"));
+        assert!(!verify_password(UNUSABLE_HASH, ""));
+    }
+}
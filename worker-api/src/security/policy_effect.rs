@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+/// Whether a rule of the command policy allows or denies a job.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+    Allow,
+    Deny,
+}
+
+impl Default for PolicyEffect {
+    fn default() -> Self {
+        PolicyEffect::Deny
+    }
+}
@@ -0,0 +1,77 @@
+use super::{PolicyEffect, Role};
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+
+/// Rule of the command policy.  It applies to the jobs of the given
+/// users and roles whose executable, and optionally arguments, match
+/// the patterns.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PolicyRule {
+    /// Identifier of the rule that is returned when it denies a job.
+    pub id: String,
+    pub effect: PolicyEffect,
+    /// Subjects or names of the users the rule applies to.  It
+    /// applies to every user if it is missing.
+    pub users: Option<Vec<String>>,
+    /// Roles of the users the rule applies to.  It applies to every
+    /// role if it is missing.
+    pub roles: Option<Vec<Role>>,
+    /// Glob pattern of the absolute path of the executable.
+    pub executable: String,
+    /// Glob pattern of the arguments, separated by single spaces.
+    pub args: Option<String>,
+}
+
+impl PolicyRule {
+    /// Check that the patterns of the rule are valid globs.
+    pub fn validate(&self) -> Result<(), String> {
+        let patterns = std::iter::once(&self.executable).chain(self.args.iter());
+        for pattern in patterns {
+            Pattern::new(pattern).map_err(|err| {
+                format!("Rule {}: invalid pattern '{}': {}", self.id, pattern, err)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Whether the rule applies to a job.  A `*` in the pattern of the
+    /// executable doesn't match a `/`.
+    ///
+    /// * `sub` - Subject of the user that creates the job.
+    /// * `user_name` - Name of the user that creates the job.
+    /// * `role` - Role of the user that creates the job.
+    /// * `executable` - Path of the executable of the job.
+    /// * `args` - Arguments of the job separated by single spaces.
+    pub fn matches(
+        &self,
+        sub: &str,
+        user_name: &str,
+        role: Role,
+        executable: &str,
+        args: &str,
+    ) -> bool {
+        let applies_to_user = self.users.as_ref().map_or(true, |users| {
+            users.iter().any(|user| user == sub || user == user_name)
+        }) && self
+            .roles
+            .as_ref()
+            .map_or(true, |roles| roles.contains(&role));
+        let path_options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        applies_to_user
+            && Self::matches_pattern(&self.executable, executable, path_options)
+            && self.args.as_ref().map_or(true, |pattern| {
+                Self::matches_pattern(pattern, args, MatchOptions::new())
+            })
+    }
+
+    // Invalid patterns are rejected when the policy is loaded, but
+    // they never match anyway.
+    fn matches_pattern(pattern: &str, value: &str, options: MatchOptions) -> bool {
+        Pattern::new(pattern)
+            .map(|pattern| pattern.matches_with(value, options))
+            .unwrap_or(false)
+    }
+}
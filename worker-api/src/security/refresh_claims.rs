@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+
+/// Claims of a refresh token.  Its audience is different from the one
+/// of the access tokens, so they can't be used instead of each other.
+#[derive(Deserialize, Serialize)]
+pub struct RefreshClaims {
+    pub sub: String,
+    pub user_name: String,
+    pub aud: String,
+    pub jti: String,
+    pub iat: usize,
+    pub exp: usize,
+}
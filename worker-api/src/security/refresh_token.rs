@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+/// Body of the requests that refresh the tokens or log out.
+#[derive(Deserialize)]
+pub struct RefreshToken {
+    pub refresh_token: String,
+}
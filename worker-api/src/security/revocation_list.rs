@@ -0,0 +1,104 @@
+use super::private_file::write_private;
+use chrono::Utc;
+use std::{collections::HashMap, fs, io, path::PathBuf, sync::Mutex};
+
+/// Ids (`jti`) of the tokens that have been revoked before they
+/// expire.  It is kept in a JSON file, so tokens stay revoked after a
+/// restart, and the tokens are forgotten once they expire.
+#[derive(Default)]
+pub struct RevocationList {
+    path: Option<PathBuf>,
+    revoked: Mutex<HashMap<String, usize>>,
+}
+
+impl RevocationList {
+    /// Load the list from a file.  A missing file is an empty list.
+    ///
+    /// * `path` - Path of the file.  It is written on every revocation.
+    pub fn load(path: PathBuf) -> io::Result<RevocationList> {
+        let revoked = match fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(RevocationList {
+            path: Some(path),
+            revoked: Mutex::new(revoked),
+        })
+    }
+
+    /// Check if a token has been revoked.
+    ///
+    /// * `jti` - Id of the token.
+    pub fn is_revoked(&self, jti: &str) -> bool {
+        self.revoked.lock().unwrap().contains_key(jti)
+    }
+
+    /// Revoke a token until it expires.  The token is revoked even if
+    /// the file can't be written.
+    ///
+    /// * `jti` - Id of the token.
+    /// * `exp` - Expiration time of the token.
+    pub fn revoke(&self, jti: &str, exp: usize) -> io::Result<()> {
+        self.revoke_once(jti, exp).map(|_| ())
+    }
+
+    /// Revoke a token like `revoke`, but return whether it wasn't
+    /// revoked yet.  The check and the revocation are atomic, so only
+    /// one of the concurrent revocations of a token gets `true`.
+    ///
+    /// * `jti` - Id of the token.
+    /// * `exp` - Expiration time of the token.
+    pub fn revoke_once(&self, jti: &str, exp: usize) -> io::Result<bool> {
+        let now = Utc::now().timestamp() as usize;
+        let mut revoked = self.revoked.lock().unwrap();
+        revoked.retain(|_, expiration| *expiration > now);
+        if revoked.insert(jti.to_string(), exp).is_some() {
+            return Ok(false);
+        }
+        match &self.path {
+            Some(path) => {
+                let content = serde_json::to_vec(&*revoked)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                write_private(path, &content)?;
+                Ok(true)
+            }
+            None => Ok(true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    #[test]
+    fn revoked_tokens_survive_a_reload() {
+        let path = std::env::temp_dir().join(format!("{}.json", Uuid::new_v4()));
+        let now = Utc::now().timestamp() as usize;
+        let revocations = RevocationList::load(path.clone()).unwrap();
+        assert!(!revocations.is_revoked("first"));
+
+        revocations.revoke("expired", now - 1).unwrap();
+        revocations.revoke("first", now + 60).unwrap();
+        revocations.revoke("second", now + 60).unwrap();
+
+        let reloaded = RevocationList::load(path.clone()).unwrap();
+        assert!(reloaded.is_revoked("first"));
+        assert!(reloaded.is_revoked("second"));
+        assert!(!reloaded.is_revoked("expired"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn tokens_are_only_revoked_once() {
+        let exp = Utc::now().timestamp() as usize + 60;
+        let revocations = RevocationList::default();
+
+        assert!(revocations.revoke_once("token", exp).unwrap());
+        assert!(!revocations.revoke_once("token", exp).unwrap());
+        assert!(revocations.is_revoked("token"));
+    }
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+/// Role of a user, as it is stored and included in its tokens.  The
+/// users without one are submitters.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Operator,
+    Submitter,
+    Viewer,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Role::Submitter
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(role: &str) -> Result<Self, Self::Err> {
+        match role {
+            "admin" => Ok(Role::Admin),
+            "operator" => Ok(Role::Operator),
+            "submitter" => Ok(Role::Submitter),
+            "viewer" => Ok(Role::Viewer),
+            _ => Err(format!("Invalid role '{}'", role)),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Role::Admin => "admin",
+            Role::Operator => "operator",
+            Role::Submitter => "submitter",
+            Role::Viewer => "viewer",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl From<Role> for worker::Role {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Admin => worker::Role::Admin,
+            Role::Operator => worker::Role::Operator,
+            Role::Submitter => worker::Role::Submitter,
+            Role::Viewer => worker::Role::Viewer,
+        }
+    }
+}
@@ -0,0 +1,101 @@
+// Minimal DER reader for the public components of the RSA keys in PEM
+// files, which are published in the JWKS.
+
+const SEQUENCE: u8 = 0x30;
+const INTEGER: u8 = 0x02;
+const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+
+/// Modulus and public exponent, as unsigned big-endian bytes, of an
+/// RSA key in PEM format.  The key can be private (PKCS#1 or PKCS#8) or
+/// public (PKCS#1 or SubjectPublicKeyInfo).  It returns `None` if it
+/// isn't an RSA key.
+///
+/// * `pem` - Content of the PEM file.
+pub fn rsa_public_components(pem: &str) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (label, der) = decode_pem(pem)?;
+    match label.as_str() {
+        "RSA PRIVATE KEY" => pkcs1_private(&der),
+        "RSA PUBLIC KEY" => pkcs1_public(&der),
+        "PRIVATE KEY" => {
+            // PrivateKeyInfo: version, algorithm and the PKCS#1 key.
+            let (info, _) = read(SEQUENCE, &der)?;
+            let (_, rest) = read(INTEGER, info)?;
+            let (_, rest) = read(SEQUENCE, rest)?;
+            let (key, _) = read(OCTET_STRING, rest)?;
+            pkcs1_private(key)
+        }
+        "PUBLIC KEY" => {
+            // SubjectPublicKeyInfo: algorithm and the PKCS#1 key.
+            let (info, _) = read(SEQUENCE, &der)?;
+            let (_, rest) = read(SEQUENCE, info)?;
+            let (bits, _) = read(BIT_STRING, rest)?;
+            pkcs1_public(bits.get(1..)?)
+        }
+        _ => None,
+    }
+}
+
+fn decode_pem(pem: &str) -> Option<(String, Vec<u8>)> {
+    let mut lines = pem.lines().map(str::trim);
+    let label = lines
+        .find_map(|line| line.strip_prefix("-----BEGIN "))?
+        .strip_suffix("-----")?
+        .to_string();
+    let body: String = lines
+        .take_while(|line| !line.starts_with("-----END "))
+        .collect();
+    Some((label, base64::decode(body).ok()?))
+}
+
+// RSAPrivateKey: version, modulus, public exponent...
+fn pkcs1_private(der: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (key, _) = read(SEQUENCE, der)?;
+    let (_, rest) = read(INTEGER, key)?;
+    components(rest)
+}
+
+// RSAPublicKey: modulus, public exponent.
+fn pkcs1_public(der: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (key, _) = read(SEQUENCE, der)?;
+    components(key)
+}
+
+fn components(der: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (modulus, rest) = read(INTEGER, der)?;
+    let (exponent, _) = read(INTEGER, rest)?;
+    Some((unsigned(modulus), unsigned(exponent)))
+}
+
+// DER integers have a leading zero when their first bit is set.
+fn unsigned(integer: &[u8]) -> Vec<u8> {
+    let start = integer
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(integer.len());
+    integer[start..].to_vec()
+}
+
+// Read an element with the expected tag.  It returns its content and
+// the elements after it.
+fn read(tag: u8, der: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (&actual, rest) = der.split_first()?;
+    let (&first, mut rest) = rest.split_first()?;
+    let length = if first < 0x80 {
+        usize::from(first)
+    } else {
+        let count = usize::from(first & 0x7f);
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let (bytes, tail) = rest.split_at(count);
+        rest = tail;
+        bytes
+            .iter()
+            .fold(0, |length, byte| (length << 8) | usize::from(*byte))
+    };
+    if actual != tag || rest.len() < length {
+        return None;
+    }
+    Some(rest.split_at(length))
+}
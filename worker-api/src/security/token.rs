@@ -2,5 +2,8 @@ use serde::Serialize;
 
 #[derive(Serialize)]
 pub struct Token {
+    /// Short-lived access token.
     pub token: String,
+    /// Token that is exchanged for new tokens at `/auth/refresh`.
+    pub refresh_token: String,
 }
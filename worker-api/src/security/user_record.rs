@@ -0,0 +1,19 @@
+use super::Role;
+use serde::{Deserialize, Serialize};
+
+/// User that can log in, as it is kept in a `UserStore`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct UserRecord {
+    /// Subject of the claims of the user.  Jobs are owned by it.
+    pub id: String,
+    pub name: String,
+    /// Argon2id hash of the password in PHC string format.
+    pub password_hash: String,
+    #[serde(default)]
+    pub role: Role,
+    /// Names of the teams of the user.
+    #[serde(default)]
+    pub teams: Vec<String>,
+    #[serde(default)]
+    pub disabled: bool,
+}
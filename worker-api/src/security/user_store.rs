@@ -0,0 +1,15 @@
+use super::UserRecord;
+use std::io;
+
+/// Storage of the users that can log in.
+pub trait UserStore: Send + Sync {
+    /// Find a user by name.
+    ///
+    /// * `name` - Name used to log in.
+    fn find(&self, name: &str) -> io::Result<Option<UserRecord>>;
+
+    /// Add a user or replace the one with the same name.
+    ///
+    /// * `user` - User to save.
+    fn save(&self, user: UserRecord) -> io::Result<()>;
+}
@@ -0,0 +1,11 @@
+mod client_certificate;
+mod client_certificates;
+mod mtls_gateway;
+mod pem_files;
+mod pinned_certificate;
+
+pub use client_certificate::ClientCertificate;
+pub use client_certificates::ClientCertificates;
+pub use mtls_gateway::MtlsGateway;
+pub use pem_files::{certificates, private_key};
+pub use pinned_certificate::PinnedCertificate;
@@ -0,0 +1,184 @@
+use std::{net::IpAddr, str};
+
+/// Tag of a DER sequence.
+const SEQUENCE: u8 = 0x30;
+/// Tag of a DER set.
+const SET: u8 = 0x31;
+/// Tag of a DER object identifier.
+const OID: u8 = 0x06;
+/// Tag of a DER octet string.
+const OCTET_STRING: u8 = 0x04;
+/// Tag of the version of a certificate (`[0]`).
+const VERSION: u8 = 0xa0;
+/// Tag of the extensions of a certificate (`[3]`).
+const EXTENSIONS: u8 = 0xa3;
+/// Tag of a DNS name in the subject alternative names (`[2]`).
+const DNS_NAME: u8 = 0x82;
+/// Tag of a URI in the subject alternative names (`[6]`).
+const URI: u8 = 0x86;
+/// Object identifier of the subject alternative names (2.5.29.17).
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+/// Object identifier of the common name (2.5.4.3).
+const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Client certificate of a connection of the `MtlsGateway`, that has
+/// been verified with the CA of the clients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientCertificate {
+    /// Name of the user in the certificate.
+    pub identity: String,
+    /// Address of the client.
+    pub address: IpAddr,
+}
+
+impl ClientCertificate {
+    /// Name of the user in a certificate.  It is its first URI subject
+    /// alternative name, or its first DNS one, or the common name of
+    /// its subject.  It returns `None` if the certificate has none of
+    /// them or it can't be parsed.
+    ///
+    /// * `der` - DER of the certificate.
+    pub fn identity(der: &[u8]) -> Option<String> {
+        let (tag, certificate, _) = value(der)?;
+        if tag != SEQUENCE {
+            return None;
+        }
+        let (_, tbs_certificate, _) = value(certificate)?;
+        let mut fields: Vec<_> = Values(tbs_certificate).collect();
+        if fields.first()?.0 == VERSION {
+            fields.remove(0);
+        }
+        // Serial number, signature, issuer, validity and subject.
+        let subject = fields.get(4)?.1;
+        let names = fields
+            .iter()
+            .find(|(tag, _)| *tag == EXTENSIONS)
+            .and_then(|(_, extensions)| alternative_names(extensions))
+            .unwrap_or_default();
+        let alternative_name = |kind| {
+            names
+                .iter()
+                .find(|(tag, _)| *tag == kind)
+                .map(|(_, name)| *name)
+        };
+        let identity = alternative_name(URI)
+            .or_else(|| alternative_name(DNS_NAME))
+            .or_else(|| common_name(subject))?;
+        str::from_utf8(identity)
+            .ok()
+            .filter(|identity| !identity.is_empty())
+            .map(String::from)
+    }
+}
+
+// Tag and contents of the DER value at the start of the data, and the
+// data after it.
+fn value(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, data) = data.split_first()?;
+    let (&first, data) = data.split_first()?;
+    let (length, data) = if first < 0x80 {
+        (first as usize, data)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || data.len() < count {
+            return None;
+        }
+        let (length, data) = data.split_at(count);
+        let length = length
+            .iter()
+            .fold(0, |length, &byte| length << 8 | byte as usize);
+        (length, data)
+    };
+    if data.len() < length {
+        return None;
+    }
+    let (contents, rest) = data.split_at(length);
+    Some((tag, contents, rest))
+}
+
+// Tags and contents of the DER values in the contents of a sequence or
+// a set.
+struct Values<'a>(&'a [u8]);
+
+impl<'a> Iterator for Values<'a> {
+    type Item = (u8, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (tag, contents, rest) = value(self.0)?;
+        self.0 = rest;
+        Some((tag, contents))
+    }
+}
+
+// Subject alternative names in the extensions of a certificate.
+fn alternative_names(extensions: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let (_, extensions, _) = value(extensions)?;
+    Values(extensions).find_map(|(_, extension)| {
+        let mut parts = Values(extension);
+        match parts.next()? {
+            (OID, SUBJECT_ALT_NAME) => {
+                // The critical flag is optional.
+                let (_, names) = parts.find(|(tag, _)| *tag == OCTET_STRING)?;
+                let (_, names, _) = value(names)?;
+                Some(Values(names).collect())
+            }
+            _ => None,
+        }
+    })
+}
+
+// Common name in the subject of a certificate.
+fn common_name(subject: &[u8]) -> Option<&[u8]> {
+    Values(subject)
+        .filter(|(tag, _)| *tag == SET)
+        .flat_map(|(_, attributes)| Values(attributes))
+        .find_map(|(_, attribute)| {
+            let mut parts = Values(attribute);
+            match parts.next()? {
+                (OID, COMMON_NAME) => parts.next().map(|(_, name)| name),
+                _ => None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tls;
+    use std::path::Path;
+
+    fn identity(file: &str) -> Option<String> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/certs")
+            .join(file);
+        let certs = tls::certificates(&path).unwrap();
+        ClientCertificate::identity(&certs[0].0)
+    }
+
+    #[test]
+    fn the_identity_is_the_common_name_without_alternative_names() {
+        assert_eq!(identity("client.pem"), Some("alice".to_string()));
+    }
+
+    #[test]
+    fn dns_names_are_preferred_to_the_common_name() {
+        assert_eq!(
+            identity("client-dns.pem"),
+            Some("ci.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn uris_are_preferred_to_dns_names() {
+        assert_eq!(
+            identity("client-uri.pem"),
+            Some("spiffe://example.com/ci".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_certificates_have_no_identity() {
+        assert_eq!(ClientCertificate::identity(b""), None);
+        assert_eq!(ClientCertificate::identity(&[SEQUENCE, 0x82, 0xff]), None);
+    }
+}
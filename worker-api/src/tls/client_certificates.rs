@@ -0,0 +1,70 @@
+use super::ClientCertificate;
+use std::{collections::HashMap, net::SocketAddr, sync::Mutex};
+
+/// Client certificates of the connections that the `MtlsGateway` has
+/// open with the HTTP API, by the local address of each connection.
+/// The HTTP API sees that address as the address of the peer, so the
+/// requests of those connections can be authenticated with the
+/// certificates.  The connections are registered before the first
+/// request and removed before they are closed, so an address can't be
+/// reused by another connection while it is registered.
+#[derive(Default)]
+pub struct ClientCertificates {
+    connections: Mutex<HashMap<SocketAddr, ClientCertificate>>,
+}
+
+impl ClientCertificates {
+    /// Create an empty collection of certificates.
+    pub fn new() -> ClientCertificates {
+        Self::default()
+    }
+
+    /// Register a connection of the gateway with the HTTP API.
+    ///
+    /// * `local` - Local address of the connection.
+    /// * `certificate` - Certificate of the client of the connection.
+    pub fn register(&self, local: SocketAddr, certificate: ClientCertificate) {
+        self.connections.lock().unwrap().insert(local, certificate);
+    }
+
+    /// Remove a connection of the gateway with the HTTP API.
+    ///
+    /// * `local` - Local address of the connection.
+    pub fn remove(&self, local: SocketAddr) {
+        self.connections.lock().unwrap().remove(&local);
+    }
+
+    /// Certificate of the client of a request, if it has been made
+    /// through the gateway.
+    ///
+    /// * `peer` - Address of the peer of the request.
+    pub fn find(&self, peer: Option<SocketAddr>) -> Option<ClientCertificate> {
+        let connections = self.connections.lock().unwrap();
+        connections.get(&peer?).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_registered_connections_have_certificates() {
+        let certificates = ClientCertificates::new();
+        let local = "127.0.0.1:40000".parse().unwrap();
+        let certificate = ClientCertificate {
+            identity: "alice".to_string(),
+            address: "192.168.1.10".parse().unwrap(),
+        };
+        certificates.register(local, certificate.clone());
+
+        assert_eq!(certificates.find(Some(local)), Some(certificate));
+        assert_eq!(
+            certificates.find(Some("127.0.0.1:40001".parse().unwrap())),
+            None
+        );
+        assert_eq!(certificates.find(None), None);
+        certificates.remove(local);
+        assert_eq!(certificates.find(Some(local)), None);
+    }
+}
@@ -0,0 +1,234 @@
+use super::{certificates, private_key, ClientCertificate, ClientCertificates, PinnedCertificate};
+use rustls::{
+    server::AllowAnyAuthenticatedClient, ClientConfig, RootCertStore, ServerConfig, ServerName,
+};
+use std::{convert::TryFrom, io, net::SocketAddr, path::Path, sync::Arc, time::Duration};
+use tokio::{
+    net::{TcpListener, TcpStream},
+    time,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// Time to complete the TLS handshake with the client.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Listener of the connections with mutual TLS.  The version of Rocket
+/// that is used can't request client certificates, so the gateway
+/// accepts the connections on its own address, requiring certificates
+/// signed by the CA of the clients, and forwards them to the HTTP API.
+/// The connections with the HTTP API use TLS too, and they are only
+/// made with the certificate of the gateway.  Each one is registered
+/// with the certificate of its client, so its requests are
+/// authenticated with the identity of the certificate.
+pub struct MtlsGateway {
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    connector: TlsConnector,
+    upstream: SocketAddr,
+    connections: Arc<ClientCertificates>,
+}
+
+impl MtlsGateway {
+    /// Listen on the address with the given certificate and key.
+    ///
+    /// * `address` - Address where the connections are accepted.
+    /// * `clients_ca` - PEM file with the certificates of the CAs that
+    ///   sign the client certificates.
+    /// * `certs` - PEM file with the certificate chain of the HTTP API.
+    /// * `key` - PEM file with the private key of the HTTP API.
+    /// * `upstream` - Address of the HTTP API.
+    /// * `connections` - Connections with the HTTP API.
+    pub async fn bind(
+        address: SocketAddr,
+        clients_ca: &Path,
+        certs: &Path,
+        key: &Path,
+        upstream: SocketAddr,
+        connections: Arc<ClientCertificates>,
+    ) -> io::Result<MtlsGateway> {
+        let invalid = |err| io::Error::new(io::ErrorKind::InvalidData, err);
+        let mut roots = RootCertStore::empty();
+        for ca in certificates(clients_ca)? {
+            roots.add(&ca).map_err(invalid)?;
+        }
+        let certs = certificates(certs)?;
+        let pinned = certs
+            .first()
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No server certificate"))?;
+        let server = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            .with_single_cert(certs, private_key(key)?)
+            .map_err(invalid)?;
+        let client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(PinnedCertificate(pinned)))
+            .with_no_client_auth();
+        Ok(MtlsGateway {
+            listener: TcpListener::bind(address).await?,
+            acceptor: TlsAcceptor::from(Arc::new(server)),
+            connector: TlsConnector::from(Arc::new(client)),
+            upstream,
+            connections,
+        })
+    }
+
+    /// Address where the connections are accepted.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept connections until the gateway is dropped.  Every
+    /// connection is forwarded in its own task.
+    pub async fn run(self) {
+        let gateway = Arc::new(self);
+        loop {
+            let (stream, peer) = match gateway.listener.accept().await {
+                Ok(accepted) => accepted,
+                Err(_) => continue,
+            };
+            let gateway = Arc::clone(&gateway);
+            tokio::spawn(async move {
+                // Connections without a valid certificate are just
+                // closed.
+                let _ = gateway.forward(stream, peer).await;
+            });
+        }
+    }
+
+    async fn forward(&self, stream: TcpStream, peer: SocketAddr) -> io::Result<()> {
+        let mut client = time::timeout(HANDSHAKE_TIMEOUT, self.acceptor.accept(stream)).await??;
+        let identity = client
+            .get_ref()
+            .1
+            .peer_certificates()
+            .and_then(|certs| certs.first())
+            .and_then(|cert| ClientCertificate::identity(&cert.0))
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "No identity in the certificate")
+            })?;
+        let name = ServerName::try_from("localhost").expect("Invalid server name");
+        let upstream = TcpStream::connect(self.upstream).await?;
+        let mut server = self.connector.connect(name, upstream).await?;
+        // The HTTP API can't receive a request before the connection is
+        // registered, and the connection is still open when it is
+        // removed.
+        let local = server.get_ref().0.local_addr()?;
+        self.connections.register(
+            local,
+            ClientCertificate {
+                identity,
+                address: peer.ip(),
+            },
+        );
+        let forwarded = tokio::io::copy_bidirectional(&mut client, &mut server).await;
+        self.connections.remove(local);
+        forwarded.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn cert_file(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/certs")
+            .join(name)
+    }
+
+    // HTTP API that answers the first message of each connection with
+    // the identity of its client.
+    async fn upstream(connections: Arc<ClientCertificates>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(
+                certificates(&cert_file("server.pem")).unwrap(),
+                private_key(&cert_file("server-key.pem")).unwrap(),
+            )
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = listener.accept().await.unwrap();
+                let mut stream = acceptor.accept(stream).await.unwrap();
+                let mut request = [0; 4];
+                stream.read_exact(&mut request).await.unwrap();
+                let identity = connections
+                    .find(Some(peer))
+                    .map(|certificate| certificate.identity)
+                    .unwrap_or_default();
+                stream.write_all(identity.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+        address
+    }
+
+    async fn gateway(connections: Arc<ClientCertificates>) -> SocketAddr {
+        let upstream = upstream(Arc::clone(&connections)).await;
+        let gateway = MtlsGateway::bind(
+            "127.0.0.1:0".parse().unwrap(),
+            &cert_file("ca.pem"),
+            &cert_file("server.pem"),
+            &cert_file("server-key.pem"),
+            upstream,
+            connections,
+        )
+        .await
+        .unwrap();
+        let address = gateway.local_addr().unwrap();
+        tokio::spawn(gateway.run());
+        address
+    }
+
+    // Send a message to the gateway with a client certificate and
+    // return the answer.
+    async fn request(gateway: SocketAddr, cert: &str) -> io::Result<String> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(&certificates(&cert_file("ca.pem")).unwrap()[0])
+            .unwrap();
+        let config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_client_auth_cert(
+                certificates(&cert_file(cert)).unwrap(),
+                private_key(&cert_file("client-key.pem")).unwrap(),
+            )
+            .unwrap();
+        let name = ServerName::try_from("localhost").unwrap();
+        let stream = TcpStream::connect(gateway).await?;
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(name, stream)
+            .await?;
+        stream.write_all(b"ping").await?;
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).await?;
+        Ok(answer)
+    }
+
+    #[tokio::test]
+    async fn requests_are_forwarded_with_the_identity_of_the_client() {
+        let gateway = gateway(Arc::new(ClientCertificates::new())).await;
+
+        let answer = request(gateway, "client.pem").await.unwrap();
+
+        assert_eq!(answer, "alice");
+    }
+
+    #[tokio::test]
+    async fn certificates_of_other_cas_are_refused() {
+        let gateway = gateway(Arc::new(ClientCertificates::new())).await;
+
+        let answer = request(gateway, "stranger.pem").await;
+
+        assert!(answer.map_or(true, |answer| answer.is_empty()));
+    }
+}
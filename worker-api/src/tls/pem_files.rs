@@ -0,0 +1,31 @@
+use rustls::{Certificate, PrivateKey};
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    path::Path,
+};
+
+/// Certificates in a PEM file, e.g. a certificate chain or the
+/// certificates of the trusted CAs.
+///
+/// * `path` - Path of the PEM file.
+pub fn certificates(path: &Path) -> io::Result<Vec<Certificate>> {
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(path)?))?;
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// First private key in a PEM file.  RSA, PKCS8 and EC keys are
+/// supported.
+///
+/// * `path` - Path of the PEM file.
+pub fn private_key(path: &Path) -> io::Result<PrivateKey> {
+    rustls_pemfile::read_all(&mut BufReader::new(File::open(path)?))?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "No private key"))
+}
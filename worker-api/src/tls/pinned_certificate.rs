@@ -0,0 +1,30 @@
+use rustls::{
+    client::{ServerCertVerified, ServerCertVerifier},
+    Certificate, Error, ServerName,
+};
+use std::time::SystemTime;
+
+/// Verifier of the server of a TLS connection that only accepts one
+/// certificate, whatever its name or issuer.  The signatures of the
+/// handshake are still verified with its key.  It is used to connect
+/// to the HTTP API of this server, whose certificate may be self-signed
+/// and may not have the name of the address used to reach it.
+pub struct PinnedCertificate(pub Certificate);
+
+impl ServerCertVerifier for PinnedCertificate {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, Error> {
+        if *end_entity == self.0 {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(Error::General("Unexpected server certificate".to_string()))
+        }
+    }
+}
@@ -0,0 +1,10 @@
+mod delivery;
+mod notifier;
+mod signature;
+mod target_policy;
+mod webhook;
+
+pub use delivery::{Delivery, DeliveryPolicy};
+pub use notifier::Webhooks;
+pub use target_policy::TargetPolicy;
+pub use webhook::Webhook;
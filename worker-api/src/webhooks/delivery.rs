@@ -0,0 +1,39 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// Record of an attempt to deliver a webhook notification.
+#[derive(Serialize, Clone)]
+pub struct Delivery {
+    /// Number of the attempt, starting at 1.
+    pub attempt: u32,
+    /// Unix timestamp of the attempt.
+    pub timestamp: i64,
+    /// HTTP status code returned by the receiver, if any.
+    pub status_code: Option<u16>,
+    /// Description of the error if the request couldn't be sent.
+    pub error: Option<String>,
+    /// Whether the receiver has accepted the notification.
+    pub delivered: bool,
+}
+
+/// Parameters that control the retries of the deliveries.
+#[derive(Clone, Copy)]
+pub struct DeliveryPolicy {
+    /// Maximum number of attempts for each notification.
+    pub max_attempts: u32,
+    /// Time to wait before the first retry.  It is doubled for every
+    /// subsequent retry.
+    pub initial_backoff: Duration,
+    /// Maximum time to wait for each request.
+    pub timeout: Duration,
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> Self {
+        DeliveryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use super::{signature, Delivery, DeliveryPolicy, TargetPolicy, Webhook};
+use crate::api::response;
+use chrono::Utc;
+use reqwest::{header::CONTENT_TYPE, redirect, Client, Response};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::{sync::RwLock, time};
+use uuid::Uuid;
+use worker::{JobCompletion, JobEvent, JobManager, Principal, Role};
+
+const SIGNATURE_HEADER: &str = "X-Worker-Signature";
+const TIMESTAMP_HEADER: &str = "X-Worker-Timestamp";
+const JOB_HEADER: &str = "X-Worker-Job";
+// Jobs whose deliveries are logged.  The oldest logs are dropped.
+const MAX_LOGGED_JOBS: usize = 1000;
+
+/// Type that notifies the webhooks of the jobs when they finish and
+/// keeps a log of the deliveries of the most recent ones.  Clones share
+/// the same log.
+#[derive(Clone)]
+pub struct Webhooks {
+    client: Client,
+    policy: DeliveryPolicy,
+    deliveries: Arc<RwLock<DeliveryLog>>,
+}
+
+// Log of the deliveries of each job, in the order they were logged.
+#[derive(Default)]
+struct DeliveryLog {
+    deliveries: HashMap<Uuid, Vec<Delivery>>,
+    jobs: VecDeque<Uuid>,
+}
+
+#[derive(Serialize)]
+struct Payload {
+    id: Uuid,
+    #[serde(flatten)]
+    status: response::JobStatus,
+}
+
+impl Webhooks {
+    /// Create a new `Webhooks` that delivers the notifications using
+    /// the given policies.  Redirects aren't followed and proxies aren't
+    /// used, since they could lead to a refused address.
+    ///
+    /// * `policy` - Retries and timeout of the deliveries.
+    /// * `targets` - Addresses that the notifications can be sent to.
+    pub fn new(policy: DeliveryPolicy, targets: TargetPolicy) -> Result<Webhooks, reqwest::Error> {
+        let client = Client::builder()
+            .timeout(policy.timeout)
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .dns_resolver(Arc::new(targets))
+            .build()?;
+        Ok(Webhooks {
+            client,
+            policy,
+            deliveries: Arc::new(RwLock::new(DeliveryLog::default())),
+        })
+    }
+
+    /// Wait in a different task for the job to finish and then notify
+    /// the webhook with its final status.
+    ///
+    /// * `job_id` - UUID of the job.
+    /// * `webhook` - Webhook that will be notified.
+    /// * `completion` - Handle to wait for the job to finish.
+    pub async fn notify_on_completion(
+        &self,
+        job_id: Uuid,
+        webhook: Webhook,
+        completion: JobCompletion,
+    ) {
+        self.deliveries.write().await.insert(job_id);
+        let webhooks = self.clone();
+        tokio::spawn(async move {
+            let status = completion.wait().await;
+            let payload = Payload {
+                id: job_id,
+                status: response::JobStatus::from(status),
+            };
+            match serde_json::to_vec(&payload) {
+                Ok(body) => webhooks.deliver(job_id, &webhook, body).await,
+                Err(err) => eprintln!("Webhook payload of job {} failed: {}", job_id, err),
+            }
+        });
+    }
+
+    /// Drop in a different task the log of the deliveries of the jobs
+    /// that are removed from the manager.
+    ///
+    /// * `jobs` - Manager whose removals are followed.
+    pub fn forget_removed_jobs(&self, jobs: &JobManager) {
+        // Only an administrator receives the events of every job.
+        let mut events = jobs.subscribe(Principal {
+            id: "",
+            role: Role::Admin,
+            teams: &[],
+        });
+        let deliveries = Arc::clone(&self.deliveries);
+        tokio::spawn(async move {
+            // The logs of the removals that are lost stay until the
+            // oldest ones are dropped.
+            while let Some(event) = events.next().await {
+                if let Ok(JobEvent::Removed { id, .. }) = event {
+                    deliveries.write().await.remove(id);
+                }
+            }
+        });
+    }
+
+    /// Return the log of the delivery attempts of the webhook of a
+    /// job, or `None` if the job doesn't have a webhook or its log has
+    /// been dropped.
+    ///
+    /// * `job_id` - UUID of the job.
+    pub async fn deliveries(&self, job_id: Uuid) -> Option<Vec<Delivery>> {
+        self.deliveries
+            .read()
+            .await
+            .deliveries
+            .get(&job_id)
+            .cloned()
+    }
+
+    // Send the signed payload to the webhook, retrying with
+    // exponential backoff until it is accepted or the maximum number
+    // of attempts is reached.  The timestamp of each attempt is signed
+    // with the payload (`<timestamp>.<payload>`), so the receivers can
+    // refuse the notifications that are replayed later.
+    async fn deliver(&self, job_id: Uuid, webhook: &Webhook, body: Vec<u8>) {
+        let mut backoff = self.policy.initial_backoff;
+        for attempt in 1..=self.policy.max_attempts {
+            let timestamp = Utc::now().timestamp().to_string();
+            let mut signed = format!("{}.", timestamp).into_bytes();
+            signed.extend_from_slice(&body);
+            let signature = format!("sha256={}", signature::sign(&webhook.secret, &signed));
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header(CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, signature)
+                .header(TIMESTAMP_HEADER, timestamp)
+                .header(JOB_HEADER, job_id.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+            let delivery = Self::delivery(attempt, result);
+            let delivered = delivery.delivered;
+            self.deliveries.write().await.push(job_id, delivery);
+            if delivered {
+                return;
+            }
+            if attempt < self.policy.max_attempts {
+                time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+    }
+
+    fn delivery(attempt: u32, result: Result<Response, reqwest::Error>) -> Delivery {
+        let (status_code, error) = match result {
+            Ok(response) => (Some(response.status().as_u16()), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        Delivery {
+            attempt,
+            timestamp: Utc::now().timestamp(),
+            status_code,
+            error,
+            delivered: matches!(status_code, Some(code) if (200..300).contains(&code)),
+        }
+    }
+}
+
+impl DeliveryLog {
+    // Start the log of a job, dropping the oldest one if it is full.
+    fn insert(&mut self, job_id: Uuid) {
+        if self.deliveries.insert(job_id, Vec::new()).is_none() {
+            self.jobs.push_back(job_id);
+        }
+        if self.jobs.len() > MAX_LOGGED_JOBS {
+            if let Some(oldest) = self.jobs.pop_front() {
+                self.deliveries.remove(&oldest);
+            }
+        }
+    }
+
+    fn push(&mut self, job_id: Uuid, delivery: Delivery) {
+        if !self.deliveries.contains_key(&job_id) {
+            self.insert(job_id);
+        }
+        if let Some(deliveries) = self.deliveries.get_mut(&job_id) {
+            deliveries.push(delivery);
+        }
+    }
+
+    fn remove(&mut self, job_id: Uuid) {
+        if self.deliveries.remove(&job_id).is_some() {
+            self.jobs.retain(|id| *id != job_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        task::JoinHandle,
+    };
+    use worker::RetentionPolicy;
+
+    const SECRET: &str = "s3cr3t";
+    const OWNER: Principal = Principal::submitter("owner");
+
+    // The stub servers listen on the loopback address.
+    fn test_webhooks(max_attempts: u32) -> Webhooks {
+        let targets = "127.0.0.1".parse().unwrap();
+        Webhooks::new(test_policy(max_attempts), targets).unwrap()
+    }
+
+    fn test_policy(max_attempts: u32) -> DeliveryPolicy {
+        DeliveryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(10),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    // Local HTTP stub that answers each request with the next status
+    // code and returns the requests that it has received.
+    async fn stub_server(status_codes: Vec<u16>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for status_code in status_codes {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                requests.push(read_request(&mut socket).await);
+                let response = format!(
+                    "HTTP/1.1 {} Stub\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                    status_code
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, server)
+    }
+
+    async fn read_request(socket: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 1024];
+        loop {
+            let read = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).to_lowercase();
+            if let Some(headers_end) = text.find("\r\n\r\n") {
+                let content_length = text
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:"))
+                    .and_then(|value| value.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if read == 0 || request.len() >= headers_end + 4 + content_length {
+                    return String::from_utf8_lossy(&request).into_owned();
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_deliveries_are_retried_and_logged() {
+        let (url, server) = stub_server(vec![500, 200]).await;
+        let webhooks = test_webhooks(3);
+        let job_id = Uuid::new_v4();
+        let webhook = Webhook {
+            url,
+            secret: SECRET.to_string(),
+        };
+
+        webhooks.deliver(job_id, &webhook, b"{}".to_vec()).await;
+
+        let requests = server.await.unwrap();
+        let deliveries = webhooks.deliveries(job_id).await.unwrap();
+        assert_eq!(2, deliveries.len());
+        assert_eq!(Some(500), deliveries[0].status_code);
+        assert!(!deliveries[0].delivered);
+        assert_eq!(Some(200), deliveries[1].status_code);
+        assert!(deliveries[1].delivered);
+        let request = requests[1].to_lowercase();
+        let timestamp = request
+            .lines()
+            .find_map(|line| line.strip_prefix("x-worker-timestamp: "))
+            .unwrap();
+        let signature_header = format!(
+            "x-worker-signature: sha256={}",
+            signature::sign(SECRET, format!("{}.{{}}", timestamp).as_bytes())
+        );
+        assert!(request.contains(&signature_header));
+    }
+
+    #[tokio::test]
+    async fn delivery_gives_up_after_max_attempts() {
+        let (url, server) = stub_server(vec![503, 503]).await;
+        let webhooks = test_webhooks(2);
+        let job_id = Uuid::new_v4();
+        let webhook = Webhook {
+            url,
+            secret: SECRET.to_string(),
+        };
+
+        webhooks.deliver(job_id, &webhook, b"{}".to_vec()).await;
+
+        server.await.unwrap();
+        let deliveries = webhooks.deliveries(job_id).await.unwrap();
+        assert_eq!(2, deliveries.len());
+        assert!(deliveries.iter().all(|delivery| !delivery.delivered));
+    }
+
+    #[tokio::test]
+    async fn finished_job_is_notified_with_its_final_status() {
+        let (url, server) = stub_server(vec![200]).await;
+        let webhooks = test_webhooks(1);
+        let jobs = JobManager::new();
+        let job_id = Uuid::new_v4();
+        jobs.submit(job_id, OWNER, "true").await.unwrap();
+        let completion = jobs.completion(job_id, OWNER).await.unwrap();
+        let webhook = Webhook {
+            url,
+            secret: SECRET.to_string(),
+        };
+
+        webhooks
+            .notify_on_completion(job_id, webhook, completion)
+            .await;
+
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains(&format!("\"id\":\"{}\"", job_id)));
+        assert!(requests[0].contains("\"exit_status\":0"));
+    }
+
+    #[tokio::test]
+    async fn deliveries_of_removed_jobs_are_forgotten() {
+        let (url, server) = stub_server(vec![200]).await;
+        let webhooks = test_webhooks(1);
+        let jobs = JobManager::with_retention(RetentionPolicy {
+            max_age: None,
+            max_finished_jobs: Some(0),
+        });
+        webhooks.forget_removed_jobs(&jobs);
+        let job_id = Uuid::new_v4();
+        jobs.submit(job_id, OWNER, "true").await.unwrap();
+        let completion = jobs.completion(job_id, OWNER).await.unwrap();
+        let webhook = Webhook {
+            url,
+            secret: SECRET.to_string(),
+        };
+        webhooks
+            .notify_on_completion(job_id, webhook, completion)
+            .await;
+        server.await.unwrap();
+        while webhooks.deliveries(job_id).await.unwrap().is_empty() {
+            time::sleep(Duration::from_millis(10)).await;
+        }
+
+        jobs.collect_garbage().await;
+
+        for _ in 0..100 {
+            if webhooks.deliveries(job_id).await.is_none() {
+                return;
+            }
+            time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("The deliveries of the removed job are kept");
+    }
+
+    #[tokio::test]
+    async fn webhooks_of_refused_addresses_are_not_delivered() {
+        let (url, _) = stub_server(vec![200]).await;
+        let webhooks = Webhooks::new(test_policy(1), TargetPolicy::default()).unwrap();
+        let job_id = Uuid::new_v4();
+        let webhook = Webhook {
+            url: url.replace("127.0.0.1", "localhost"),
+            secret: SECRET.to_string(),
+        };
+
+        webhooks.deliver(job_id, &webhook, b"{}".to_vec()).await;
+
+        let deliveries = webhooks.deliveries(job_id).await.unwrap();
+        assert_eq!(None, deliveries[0].status_code);
+        assert!(!deliveries[0].delivered);
+    }
+
+    #[test]
+    fn only_the_most_recent_logs_are_kept() {
+        let mut log = DeliveryLog::default();
+        let ids: Vec<Uuid> = (0..=MAX_LOGGED_JOBS).map(|_| Uuid::new_v4()).collect();
+
+        for id in &ids {
+            log.insert(*id);
+        }
+        log.remove(ids[1]);
+
+        assert_eq!(MAX_LOGGED_JOBS - 1, log.deliveries.len());
+        assert_eq!(MAX_LOGGED_JOBS - 1, log.jobs.len());
+        assert!(!log.deliveries.contains_key(&ids[0]));
+        assert!(log.deliveries.contains_key(&ids[MAX_LOGGED_JOBS]));
+    }
+}
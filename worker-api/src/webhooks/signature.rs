@@ -0,0 +1,26 @@
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+
+/// Sign the payload of a notification with the secret of the webhook
+/// using HMAC-SHA256.  It returns the hex encoded signature.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC can take keys of any size");
+    mac.update(payload);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_matches_rfc_4231_test_vector() {
+        let signature = sign("Jefe", b"what do ya want for nothing?");
+
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+            signature
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use hyper::client::connect::dns::Name;
+use ipnet::IpNet;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    Url,
+};
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+};
+
+/// Addresses that the webhooks can be delivered to.  The loopback,
+/// link-local, private (RFC 1918 and unique local), shared (RFC 6598)
+/// and multicast addresses are refused, so the jobs can't make the server send requests to its
+/// internal network, unless they are in one of the allowed networks.
+///
+/// It is also the DNS resolver of the deliveries, so a host name is
+/// checked every time it is resolved and can't point to an internal
+/// address after the webhook has been accepted.
+#[derive(Clone, Debug, Default)]
+pub struct TargetPolicy {
+    allowed: Vec<IpNet>,
+}
+
+impl TargetPolicy {
+    /// Check if the notifications can be sent to an address.
+    ///
+    /// * `address` - IP address of the receiver.
+    pub fn allows(&self, address: IpAddr) -> bool {
+        !Self::is_internal(address) || self.allowed.iter().any(|net| net.contains(&address))
+    }
+
+    /// Check if a URL can be a webhook: an absolute HTTP(S) URL whose
+    /// host isn't a refused address.  The addresses of the host names
+    /// are checked when they are resolved.
+    ///
+    /// * `url` - URL of the webhook.
+    pub fn allows_url(&self, url: &str) -> bool {
+        let url = match Url::parse(url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => url,
+            _ => return false,
+        };
+        // IPv6 addresses are enclosed in brackets.
+        let host = match url.host_str() {
+            Some(host) => host.trim_start_matches('[').trim_end_matches(']'),
+            None => return false,
+        };
+        match host.parse() {
+            Ok(address) => self.allows(address),
+            Err(_) => true,
+        }
+    }
+
+    fn is_internal(address: IpAddr) -> bool {
+        match address {
+            IpAddr::V4(address) => {
+                address.is_loopback()
+                    || address.is_link_local()
+                    || address.is_private()
+                    || address.is_unspecified()
+                    || address.is_broadcast()
+                    || address.is_multicast()
+                    || Self::is_shared(address)
+            }
+            IpAddr::V6(address) => match address.to_ipv4_mapped() {
+                Some(mapped) => Self::is_internal(mapped.into()),
+                None => {
+                    address.is_loopback()
+                        || address.is_unspecified()
+                        || address.is_multicast()
+                        || Self::in_prefix(address, 0xfe80, 10)
+                        || Self::in_prefix(address, 0xfc00, 7)
+                }
+            },
+        }
+    }
+
+    // Whether the address is in the shared address space of the carrier
+    // grade NATs, 100.64.0.0/10, that is internal to the provider.
+    fn is_shared(address: Ipv4Addr) -> bool {
+        let octets = address.octets();
+        octets[0] == 100 && octets[1] & 0xc0 == 64
+    }
+
+    // Whether the first segment of the address has the given prefix.
+    fn in_prefix(address: Ipv6Addr, prefix: u16, length: u32) -> bool {
+        let mask = !(u16::MAX >> length);
+        address.segments()[0] & mask == prefix
+    }
+}
+
+impl FromStr for TargetPolicy {
+    type Err = ipnet::AddrParseError;
+
+    /// Parse the allowed networks separated by `;`, e.g.
+    /// `10.1.0.0/16;192.168.1.10`.  An address is a network of its own.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let allowed = value
+            .split(';')
+            .map(str::trim)
+            .filter(|net| !net.is_empty())
+            .map(|net| match net.parse::<IpAddr>() {
+                Ok(address) => Ok(IpNet::from(address)),
+                Err(_) => net.parse(),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(TargetPolicy { allowed })
+    }
+}
+
+impl Resolve for TargetPolicy {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.clone();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| policy.allows(address.ip()))
+                .collect();
+            if addresses.is_empty() {
+                let refused = format!("No allowed address for the webhook host {}", name.as_str());
+                return Err(io::Error::new(io::ErrorKind::PermissionDenied, refused).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn internal_addresses_are_refused() {
+        let policy = TargetPolicy::default();
+
+        assert!(policy.allows_url("https://93.184.216.34/hook"));
+        assert!(policy.allows_url("https://ci.example.com/hook"));
+        assert!(!policy.allows_url("http://127.0.0.1:8000/v1/jobs"));
+        assert!(!policy.allows_url("http://169.254.169.254/latest/meta-data"));
+        assert!(!policy.allows_url("http://10.0.0.1/hook"));
+        assert!(!policy.allows_url("http://172.16.5.4/hook"));
+        assert!(!policy.allows_url("http://192.168.1.1/hook"));
+        assert!(!policy.allows_url("http://0.0.0.0/hook"));
+        assert!(!policy.allows_url("http://100.64.0.1/hook"));
+        assert!(!policy.allows_url("http://100.127.255.254/hook"));
+        assert!(policy.allows_url("http://100.128.0.1/hook"));
+        assert!(!policy.allows_url("http://224.0.0.251/hook"));
+        assert!(!policy.allows_url("http://[ff02::1]/hook"));
+        assert!(!policy.allows_url("http://[::1]/hook"));
+        assert!(!policy.allows_url("http://[fe80::1]/hook"));
+        assert!(!policy.allows_url("http://[fd00::1]/hook"));
+        assert!(!policy.allows_url("http://[::ffff:127.0.0.1]/hook"));
+        assert!(!policy.allows_url("ftp://ci.example.com/hook"));
+        assert!(!policy.allows_url("/hook"));
+    }
+
+    #[test]
+    fn allowed_networks_can_be_internal() {
+        let policy: TargetPolicy = "10.1.0.0/16; 127.0.0.1".parse().unwrap();
+
+        assert!(policy.allows_url("http://10.1.2.3/hook"));
+        assert!(policy.allows_url("http://127.0.0.1:9000/hook"));
+        assert!(!policy.allows_url("http://10.2.0.1/hook"));
+        assert!(!policy.allows_url("http://127.0.0.2/hook"));
+        assert!("10.1.0.0/33".parse::<TargetPolicy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn host_names_of_internal_addresses_are_not_resolved() {
+        let policy = TargetPolicy::default();
+
+        assert!(policy.resolve("localhost".parse().unwrap()).await.is_err());
+    }
+}
@@ -0,0 +1,8 @@
+/// Callback that is notified when a job finishes.
+#[derive(Clone)]
+pub struct Webhook {
+    /// URL that will receive the notification using a POST request.
+    pub url: String,
+    /// Secret shared with the receiver that is used to sign the payload.
+    pub secret: String,
+}
@@ -1,7 +1,16 @@
 mod error;
+mod event_reader;
+mod oidc_client;
 pub mod request;
 pub mod response;
+mod session;
+mod terminal_attachment;
+mod terminal_message;
 mod worker_client;
 
 pub use error::Error;
+pub use oidc_client::OidcClient;
+pub use session::Session;
+pub use terminal_attachment::TerminalAttachment;
+pub use terminal_message::TerminalMessage;
 pub use worker_client::WorkerClient;
@@ -9,6 +9,14 @@ pub enum Error {
     ApiError(reqwest::StatusCode),
     /// Errors related to reading files (certificate).
     FileError(std::io::Error),
+    /// Responses from the API server that cannot be parsed.
+    ParseError(serde_json::Error),
+    /// Problems of the TLS connection of a WebSocket.
+    TlsError(rustls::Error),
+    /// Problems found by the WebSocket client.
+    WebSocketError(Box<tungstenite::Error>),
+    /// Logins refused or expired at the OpenID Connect issuer.
+    AuthorizationError(String),
     /// Errors on internal work of the `WorkerClient`.
     InternalError,
 }
@@ -25,6 +33,10 @@ impl Display for Error {
                 status.canonical_reason().unwrap_or("unknown")
             ),
             Error::FileError(err) => write!(f, "File error: {}", err),
+            Error::ParseError(err) => write!(f, "Parse error: {}", err),
+            Error::TlsError(err) => write!(f, "TLS error: {}", err),
+            Error::WebSocketError(err) => write!(f, "WebSocket error: {}", err),
+            Error::AuthorizationError(err) => write!(f, "Authorization error: {}", err),
             Error::InternalError => write!(f, "Internal error"),
         }
     }
@@ -41,3 +53,30 @@ impl From<std::io::Error> for Error {
         Error::FileError(error)
     }
 }
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::ParseError(error)
+    }
+}
+
+impl From<rustls::Error> for Error {
+    fn from(error: rustls::Error) -> Self {
+        Error::TlsError(error)
+    }
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(error: tungstenite::Error) -> Self {
+        match error {
+            // Refused handshakes carry the status of the API server.
+            tungstenite::Error::Http(response) => {
+                match reqwest::StatusCode::from_u16(response.status().as_u16()) {
+                    Ok(status) => Error::ApiError(status),
+                    Err(_) => Error::InternalError,
+                }
+            }
+            error => Error::WebSocketError(Box::new(error)),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use super::{error::Error, response};
+use reqwest::blocking::Response;
+use std::io::{BufRead, BufReader};
+
+/// Iterator over the events received from the Server-Sent Events
+/// stream of worker-api.  It blocks until the next event arrives.
+pub struct EventReader {
+    reader: BufReader<Response>,
+}
+
+impl EventReader {
+    pub(super) fn new(response: Response) -> EventReader {
+        EventReader {
+            reader: BufReader::new(response),
+        }
+    }
+
+    // Read the lines of the next event and return the content of its
+    // data field.  Other fields and comments (heartbeats) are ignored.
+    fn read_event_data(&mut self) -> Result<Option<String>, Error> {
+        let mut data = String::new();
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+            if line.is_empty() {
+                if !data.is_empty() {
+                    return Ok(Some(data));
+                }
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !data.is_empty() {
+                    data.push('\n');
+                }
+                data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            }
+        }
+    }
+}
+
+impl Iterator for EventReader {
+    type Item = Result<response::Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_event_data() {
+            Ok(Some(data)) => Some(serde_json::from_str(&data).map_err(Error::from)),
+            Ok(None) => None,
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
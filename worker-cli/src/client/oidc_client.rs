@@ -0,0 +1,97 @@
+use super::{error::Error, request, response};
+use reqwest::blocking::Client;
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+const DEVICE_CODE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:device_code";
+const SCOPES: &str = "openid profile email groups";
+const SLOW_DOWN_INCREMENT: Duration = Duration::from_secs(5);
+
+/// Type that obtains ID tokens from an OpenID Connect issuer with the
+/// device authorization grant (RFC 8628), so users log in with their
+/// browser.
+pub struct OidcClient {
+    issuer: String,
+    client_id: String,
+}
+
+impl OidcClient {
+    /// Create a new instance for an issuer.
+    ///
+    /// * `issuer` - URL of the issuer, as configured in worker-api.
+    /// * `client_id` - Id of worker-cli at the issuer.
+    pub fn new(issuer: &str, client_id: &str) -> OidcClient {
+        OidcClient {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+        }
+    }
+
+    /// Log in with the device flow and return the ID token.  It blocks
+    /// until the user has approved the login in its browser, or the
+    /// codes have expired.
+    ///
+    /// * `show_code` - Shows the user code and the URI where it is entered.
+    pub fn device_login(
+        &self,
+        show_code: impl FnOnce(&response::DeviceAuthorization),
+    ) -> Result<String, Error> {
+        let client = Client::builder()
+            .https_only(true)
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        let discovery_url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let discovery = client
+            .get(&discovery_url)
+            .send()?
+            .error_for_status()?
+            .json::<response::OidcDiscovery>()?;
+        let device_endpoint = discovery.device_authorization_endpoint.ok_or_else(|| {
+            Error::AuthorizationError("The issuer doesn't support the device flow".to_string())
+        })?;
+
+        let authorization = client
+            .post(&device_endpoint)
+            .form(&request::DeviceAuthorization {
+                client_id: self.client_id.clone(),
+                scope: SCOPES.to_string(),
+            })
+            .send()?
+            .error_for_status()?
+            .json::<response::DeviceAuthorization>()?;
+        show_code(&authorization);
+
+        let token_request = request::DeviceToken {
+            grant_type: DEVICE_CODE_GRANT_TYPE.to_string(),
+            device_code: authorization.device_code.clone(),
+            client_id: self.client_id.clone(),
+        };
+        let expiration = Instant::now() + Duration::from_secs(authorization.expires_in);
+        let mut interval = Duration::from_secs(authorization.interval);
+        while Instant::now() < expiration {
+            thread::sleep(interval);
+            let response = client
+                .post(&discovery.token_endpoint)
+                .form(&token_request)
+                .send()?;
+            if response.status().is_success() {
+                return Ok(response.json::<response::DeviceToken>()?.id_token);
+            }
+            let error = response.json::<response::OAuthError>()?;
+            match error.error.as_str() {
+                "authorization_pending" => {}
+                "slow_down" => interval += SLOW_DOWN_INCREMENT,
+                _ => {
+                    return Err(Error::AuthorizationError(
+                        error.error_description.unwrap_or(error.error),
+                    ))
+                }
+            }
+        }
+        Err(Error::AuthorizationError(
+            "The device code has expired".to_string(),
+        ))
+    }
+}
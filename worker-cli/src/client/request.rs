@@ -1,5 +1,17 @@
+mod device_authorization;
+mod device_token;
 mod login;
 mod new_job;
+mod refresh;
+mod share;
+mod terminal_size;
+mod workspace;
 
+pub use device_authorization::DeviceAuthorization;
+pub use device_token::DeviceToken;
 pub use login::Login;
 pub use new_job::NewJob;
+pub use refresh::Refresh;
+pub use share::Share;
+pub use terminal_size::TerminalSize;
+pub use workspace::Workspace;
@@ -0,0 +1,8 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct DeviceAuthorization {
+    pub client_id: String,
+    /// Space separated scopes that are requested.
+    pub scope: String,
+}
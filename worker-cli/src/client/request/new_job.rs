@@ -1,3 +1,4 @@
+use super::{TerminalSize, Workspace};
 use serde::Serialize;
 use uuid::Uuid;
 
@@ -5,4 +6,38 @@ use uuid::Uuid;
 pub struct NewJob {
     pub id: Uuid,
     pub command_line: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    pub stdin_open: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<TerminalSize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace: Option<Workspace>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hardening: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team: Option<String>,
+}
+
+impl NewJob {
+    /// Job without input that runs in the working directory of the
+    /// server.
+    pub fn new(id: Uuid, command_line: &str) -> NewJob {
+        NewJob {
+            id,
+            command_line: command_line.to_string(),
+            stdin: None,
+            stdin_open: false,
+            terminal: None,
+            workspace: None,
+            hardening: None,
+            team: None,
+        }
+    }
+
+    /// Content that is written to the stdin of the job.
+    pub fn with_stdin(mut self, stdin: &[u8]) -> NewJob {
+        self.stdin = Some(base64::encode(stdin));
+        self
+    }
 }
@@ -0,0 +1,7 @@
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Share {
+    /// `read` or `stop`.
+    pub access: String,
+}
@@ -0,0 +1,7 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+pub struct TerminalSize {
+    pub rows: u16,
+    pub cols: u16,
+}
@@ -0,0 +1,23 @@
+use serde::Serialize;
+use std::{io, path::Path};
+
+#[derive(Serialize)]
+pub struct Workspace {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive: Option<String>,
+}
+
+impl Workspace {
+    /// Workspace populated with the content of a local directory.  It
+    /// is sent as a tar archive with the paths relative to the
+    /// directory.
+    pub fn from_directory(path: &Path) -> io::Result<Workspace> {
+        let mut builder = tar::Builder::new(Vec::new());
+        builder.follow_symlinks(false);
+        builder.append_dir_all(".", path)?;
+        let archive = builder.into_inner()?;
+        Ok(Workspace {
+            archive: Some(base64::encode(archive)),
+        })
+    }
+}
@@ -1,5 +1,19 @@
+mod artifact;
+mod attach_ticket;
+mod device_authorization;
+mod device_token;
+mod event;
 mod login;
+mod oauth_error;
+mod oidc_discovery;
 mod status;
 
+pub use artifact::Artifact;
+pub use attach_ticket::AttachTicket;
+pub use device_authorization::DeviceAuthorization;
+pub use device_token::DeviceToken;
+pub use event::Event;
 pub use login::Login;
+pub use oauth_error::OAuthError;
+pub use oidc_discovery::OidcDiscovery;
 pub use status::Status;
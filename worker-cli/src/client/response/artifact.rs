@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct Artifact {
+    pub name: String,
+    pub size: u64,
+}
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct AttachTicket {
+    pub ticket: String,
+    pub port: u16,
+}
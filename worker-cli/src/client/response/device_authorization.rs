@@ -0,0 +1,21 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    /// Code that the user enters at the verification URI.
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Verification URI that includes the user code.
+    pub verification_uri_complete: Option<String>,
+    /// Seconds until the codes expire.
+    pub expires_in: u64,
+    /// Seconds between the requests for the token.
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+// RFC 8628 default.
+fn default_interval() -> u64 {
+    5
+}
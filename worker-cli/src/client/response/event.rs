@@ -0,0 +1,28 @@
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Started {
+        id: Uuid,
+    },
+    Output {
+        id: Uuid,
+        stream: String,
+        data: String,
+    },
+    Stopped {
+        id: Uuid,
+    },
+    Finished {
+        id: Uuid,
+        status: String,
+        exit_status: Option<i32>,
+        signal: Option<i32>,
+    },
+    Removed {
+        id: Uuid,
+    },
+    Lagged,
+}
@@ -3,4 +3,5 @@ use serde::Deserialize;
 #[derive(Deserialize)]
 pub struct Login {
     pub token: String,
+    pub refresh_token: String,
 }
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct OAuthError {
+    /// Error code, e.g. `authorization_pending` or `access_denied`.
+    pub error: String,
+    pub error_description: Option<String>,
+}
@@ -0,0 +1,8 @@
+use serde::Deserialize;
+
+/// Endpoints of an OpenID Connect issuer that are used.
+#[derive(Deserialize)]
+pub struct OidcDiscovery {
+    pub device_authorization_endpoint: Option<String>,
+    pub token_endpoint: String,
+}
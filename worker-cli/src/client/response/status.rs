@@ -4,4 +4,23 @@ use serde::Deserialize;
 pub struct Status {
     pub status: String,
     pub exit_status: Option<i32>,
+    pub signal: Option<i32>,
+}
+
+impl Status {
+    /// Whether the job has reached its final status.
+    pub fn is_finished(&self) -> bool {
+        self.status != "IN_PROGRESS"
+    }
+
+    /// Exit code that a shell would report for the job: its exit
+    /// status, 128 plus the number of the signal that killed it or 1
+    /// if it failed.
+    pub fn exit_code(&self) -> i32 {
+        match (self.exit_status, self.signal) {
+            (Some(exit_status), _) => exit_status,
+            (None, Some(signal)) => 128 + signal,
+            (None, None) => 1,
+        }
+    }
 }
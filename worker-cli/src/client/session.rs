@@ -0,0 +1,203 @@
+use super::{error::Error, response, WorkerClient};
+use reqwest::StatusCode;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Tokens of a command that may outlive its access token, like `wait`,
+/// `run` or `attach`.  If a request is refused with `401`, the access
+/// token is renewed with the refresh token and the request is retried
+/// once.  It can be shared by the threads of the command.
+#[derive(Clone)]
+pub struct Session {
+    client: WorkerClient,
+    tokens: Arc<Mutex<Tokens>>,
+}
+
+struct Tokens {
+    access: String,
+    refresh: Option<String>,
+    renewed: bool,
+}
+
+impl Session {
+    /// Create a session with the tokens of the user.
+    ///
+    /// * `client` - Client of worker-api.
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `refresh_token` - token that is obtained with the access token.
+    ///   Without it, the access token isn't renewed.
+    pub fn new(client: WorkerClient, token: &str, refresh_token: Option<&str>) -> Session {
+        Session {
+            client,
+            tokens: Arc::new(Mutex::new(Tokens {
+                access: token.to_string(),
+                refresh: refresh_token.map(String::from),
+                renewed: false,
+            })),
+        }
+    }
+
+    /// Make a request with the current access token, renewing it if
+    /// it has expired.
+    ///
+    /// * `request` - Request made with the client and a token.
+    pub fn call<T>(
+        &self,
+        request: impl Fn(&WorkerClient, &str) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let token = self.tokens.lock().unwrap().access.clone();
+        match request(&self.client, &token) {
+            Err(Error::ApiError(StatusCode::UNAUTHORIZED)) => match self.renew(&token) {
+                Some(token) => request(&self.client, &token),
+                None => Err(Error::ApiError(StatusCode::UNAUTHORIZED)),
+            },
+            result => result,
+        }
+    }
+
+    /// Wait for a job to finish and return its final status.  Every
+    /// long poll is a request of its own, so the wait can outlive the
+    /// tokens.
+    ///
+    /// * `id` - valid UUID of the job to wait for.
+    pub fn wait(&self, id: Uuid) -> Result<response::Status, Error> {
+        loop {
+            let status = self.call(|client, token| client.poll(token, id))?;
+            if status.is_finished() {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Tokens of the session if they have been renewed.  The refresh
+    /// token that was given is no longer valid then.
+    pub fn renewed_tokens(&self) -> Option<response::Login> {
+        let tokens = self.tokens.lock().unwrap();
+        match (&tokens.refresh, tokens.renewed) {
+            (Some(refresh), true) => Some(response::Login {
+                token: tokens.access.clone(),
+                refresh_token: refresh.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    // Exchange the refresh token for new tokens, unless another thread
+    // has already done it.  Refresh tokens can only be used once.
+    fn renew(&self, expired: &str) -> Option<String> {
+        let mut tokens = self.tokens.lock().unwrap();
+        if tokens.access != expired {
+            return Some(tokens.access.clone());
+        }
+        let renewed = self.client.refresh(tokens.refresh.as_ref()?).ok()?;
+        tokens.access = renewed.token;
+        tokens.refresh = Some(renewed.refresh_token);
+        tokens.renewed = true;
+        Some(tokens.access.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::{ServerConfig, ServerConnection, StreamOwned};
+    use std::{
+        fs::File,
+        io::{BufReader, Read, Write},
+        net::TcpListener,
+        path::{Path, PathBuf},
+        thread::{self, JoinHandle},
+    };
+
+    fn cert_file(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/certs")
+            .join(name)
+    }
+
+    // Server that answers the given responses, in order, to one request
+    // per connection, and returns the requests that it has received.
+    fn server(responses: &[&str]) -> (WorkerClient, JoinHandle<Vec<String>>) {
+        let mut certs = BufReader::new(File::open(cert_file("server.pem")).unwrap());
+        let certs = rustls_pemfile::certs(&mut certs).unwrap();
+        let mut key = BufReader::new(File::open(cert_file("server-key.pem")).unwrap());
+        let key = rustls_pemfile::pkcs8_private_keys(&mut key)
+            .unwrap()
+            .remove(0);
+        let config = Arc::new(
+            ServerConfig::builder()
+                .with_safe_defaults()
+                .with_no_client_auth()
+                .with_single_cert(
+                    certs.into_iter().map(rustls::Certificate).collect(),
+                    rustls::PrivateKey(key),
+                )
+                .unwrap(),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let responses: Vec<String> = responses.iter().map(|r| r.to_string()).collect();
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let connection = ServerConnection::new(Arc::clone(&config)).unwrap();
+                let mut stream = StreamOwned::new(connection, stream);
+                let mut request = Vec::new();
+                let mut byte = [0; 1];
+                while !request.ends_with(b"\r\n\r\n") && stream.read(&mut byte).unwrap() == 1 {
+                    request.push(byte[0]);
+                }
+                requests.push(String::from_utf8_lossy(&request).to_lowercase());
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    if response.is_empty() { "401 Unauthorized" } else { "200 OK" },
+                    response.len(),
+                    response
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+                stream.flush().unwrap();
+            }
+            requests
+        });
+        let client = WorkerClient::new()
+            .with_base_url(&format!("https://localhost:{}", port))
+            .with_root_certificate(&cert_file("ca.pem"));
+        (client, handle)
+    }
+
+    const FINISHED: &str = r#"{"status":"EXITED","exit_status":0,"signal":null}"#;
+    const TOKENS: &str = r#"{"token":"new","refresh_token":"next"}"#;
+
+    #[test]
+    fn expired_tokens_are_renewed_and_the_request_is_retried() {
+        let (client, server) = server(&["", TOKENS, FINISHED]);
+        let session = Session::new(client, "old", Some("refresh"));
+
+        let status = session.wait(Uuid::new_v4()).unwrap();
+
+        assert!(status.is_finished());
+        let requests = server.join().unwrap();
+        assert!(requests[0].contains("authorization: bearer old"));
+        assert!(requests[1].starts_with("post /auth/refresh"));
+        assert!(requests[2].contains("authorization: bearer new"));
+        let tokens = session.renewed_tokens().unwrap();
+        assert_eq!(tokens.token, "new");
+        assert_eq!(tokens.refresh_token, "next");
+    }
+
+    #[test]
+    fn tokens_are_not_renewed_without_a_refresh_token() {
+        let (client, server) = server(&[""]);
+        let session = Session::new(client, "old", None);
+
+        let status = session.wait(Uuid::new_v4());
+
+        assert!(matches!(
+            status,
+            Err(Error::ApiError(StatusCode::UNAUTHORIZED))
+        ));
+        assert_eq!(server.join().unwrap().len(), 1);
+        assert!(session.renewed_tokens().is_none());
+    }
+}
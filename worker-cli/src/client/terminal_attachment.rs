@@ -0,0 +1,129 @@
+use super::{error::Error, request, TerminalMessage};
+use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerName, StreamOwned};
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufReader},
+    net::TcpStream,
+    sync::Arc,
+    time::Duration,
+};
+use tungstenite::{
+    handshake::HandshakeError,
+    protocol::{frame::coding::CloseCode, Message},
+    WebSocket,
+};
+
+type Stream = StreamOwned<ClientConnection, TcpStream>;
+
+/// WebSocket attached to the terminal of a job.  The input and the
+/// size of the terminal are sent as they change, while its output is
+/// read.
+pub struct TerminalAttachment {
+    socket: WebSocket<Stream>,
+}
+
+impl TerminalAttachment {
+    // Open the WebSocket over TLS, trusting the certificate of the
+    // server.  Reads wait at most the poll interval, so input can be
+    // sent while no output arrives.
+    pub(super) fn connect(
+        host: &str,
+        port: u16,
+        path: &str,
+        identity: Option<&[u8]>,
+        poll_interval: Duration,
+    ) -> Result<TerminalAttachment, Error> {
+        let mut roots = RootCertStore::empty();
+        let certs = rustls_pemfile::certs(&mut BufReader::new(File::open("private/rsacert.pem")?))?;
+        roots.add_parsable_certificates(&certs);
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+        let config = match identity {
+            Some(identity) => {
+                let (certs, key) = Self::identity(identity)?;
+                builder.with_client_auth_cert(certs, key)?
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let server_name = ServerName::try_from(host).map_err(|_| Error::InternalError)?;
+        let connection = ClientConnection::new(Arc::new(config), server_name)?;
+        let tcp = TcpStream::connect((host, port))?;
+        let url = format!("wss://{}:{}{}", host, port, path);
+        let socket = match tungstenite::client(url, StreamOwned::new(connection, tcp)) {
+            Ok((socket, _)) => socket,
+            Err(HandshakeError::Failure(err)) => return Err(err.into()),
+            Err(HandshakeError::Interrupted(_)) => return Err(Error::InternalError),
+        };
+        socket
+            .get_ref()
+            .sock
+            .set_read_timeout(Some(poll_interval))?;
+        Ok(TerminalAttachment { socket })
+    }
+
+    // Split the PEM of the client certificate, followed by its private
+    // key.
+    fn identity(identity: &[u8]) -> Result<(Vec<rustls::Certificate>, rustls::PrivateKey), Error> {
+        let mut certs = Vec::new();
+        let mut key = None;
+        for item in rustls_pemfile::read_all(&mut &identity[..])? {
+            match item {
+                rustls_pemfile::Item::X509Certificate(cert) => {
+                    certs.push(rustls::Certificate(cert))
+                }
+                rustls_pemfile::Item::RSAKey(der)
+                | rustls_pemfile::Item::PKCS8Key(der)
+                | rustls_pemfile::Item::ECKey(der) => key = Some(rustls::PrivateKey(der)),
+                _ => {}
+            }
+        }
+        Ok((certs, key.ok_or(Error::InternalError)?))
+    }
+
+    /// Write data to the terminal.
+    ///
+    /// * `data` - Bytes that are written, like the keys pressed.
+    pub fn write_input(&mut self, data: &[u8]) -> Result<(), Error> {
+        Ok(self.socket.send(Message::Binary(data.to_vec()))?)
+    }
+
+    /// Change the size of the terminal.
+    ///
+    /// * `size` - New size of the terminal.
+    pub fn resize(&mut self, size: request::TerminalSize) -> Result<(), Error> {
+        let size = serde_json::to_string(&size)?;
+        Ok(self.socket.send(Message::Text(size))?)
+    }
+
+    /// Wait for the next message of the terminal.  It returns `None` if
+    /// nothing arrives during the poll interval.
+    pub fn read(&mut self) -> Result<Option<TerminalMessage>, Error> {
+        match self.socket.read() {
+            Ok(Message::Binary(data)) => Ok(Some(TerminalMessage::Output(data))),
+            Ok(Message::Text(status)) => Ok(Some(TerminalMessage::Finished(serde_json::from_str(
+                &status,
+            )?))),
+            Ok(Message::Close(frame)) => {
+                // Send the reply to the close.
+                let _ = self.socket.flush();
+                let reason = frame
+                    .filter(|frame| frame.code != CloseCode::Normal)
+                    .map(|frame| frame.reason.into_owned());
+                Ok(Some(TerminalMessage::Closed(reason)))
+            }
+            Ok(_) => Ok(None),
+            Err(tungstenite::Error::Io(err))
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(tungstenite::Error::ConnectionClosed) => Ok(Some(TerminalMessage::Closed(None))),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
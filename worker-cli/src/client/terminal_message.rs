@@ -0,0 +1,12 @@
+use super::response;
+
+/// Messages received from the terminal of a job.
+pub enum TerminalMessage {
+    /// Output of the terminal.
+    Output(Vec<u8>),
+    /// Final status of the job.  The connection is closed afterwards.
+    Finished(response::Status),
+    /// The connection has been closed, with its reason if it hasn't
+    /// been closed normally.
+    Closed(Option<String>),
+}
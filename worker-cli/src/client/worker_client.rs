@@ -1,13 +1,30 @@
-use super::{error::Error, request, response};
-use reqwest::{blocking::Client, Certificate};
+use super::{error::Error, event_reader::EventReader, request, response, TerminalAttachment};
+use reqwest::{
+    blocking::{Client, Response},
+    header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, AUTHORIZATION, CONTENT_ENCODING},
+    Certificate, Identity, Url,
+};
 use std::{array::IntoIter, collections::HashMap, time::Duration};
-use std::{fs::File, io::Read};
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 use uuid::Uuid;
 
+const WAIT_POLL_TIMEOUT: Duration = Duration::from_secs(60);
+const ATTACH_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const API_KEY_PREFIX: &str = "wk_";
+
 /// Type that defines the parameters for operating with `worker-api`
+#[derive(Clone)]
 pub struct WorkerClient {
     base_url: String,
     endpoints: HashMap<String, String>,
+    // PEM of the client certificate followed by its private key.
+    identity: Option<Vec<u8>>,
+    // PEM of the certificate that the server is verified with.
+    root_certificate: PathBuf,
 }
 
 impl WorkerClient {
@@ -17,38 +34,106 @@ impl WorkerClient {
             base_url: String::from("https://localhost:8000"),
             endpoints: IntoIter::new([
                 ("login".to_string(), "/auth/login".to_string()),
+                ("refresh".to_string(), "/auth/refresh".to_string()),
+                ("logout".to_string(), "/auth/logout".to_string()),
                 ("jobs".to_string(), "/v1/jobs".to_string()),
+                ("events".to_string(), "/v1/events".to_string()),
             ])
             .collect(),
+            identity: None,
+            root_certificate: PathBuf::from("private/rsacert.pem"),
         }
     }
 
+    /// Use another base URL, e.g. the address of the connections with
+    /// mutual TLS of `worker-api`.
+    ///
+    /// * `base_url` - Scheme, host and port of `worker-api`.
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Authenticate the TLS connections with a client certificate, for
+    /// servers that require mutual TLS.
+    ///
+    /// * `cert` - PEM file with the certificate, optionally followed by
+    ///   its chain.
+    /// * `key` - PEM file with the private key of the certificate.
+    pub fn with_client_certificate(mut self, cert: &Path, key: &Path) -> Result<Self, Error> {
+        let mut identity = fs::read(cert)?;
+        identity.push(b'\n');
+        identity.extend(fs::read(key)?);
+        // Fail early if they can't be used.
+        Identity::from_pem(&identity)?;
+        self.identity = Some(identity);
+        Ok(self)
+    }
+
+    // The tests verify their servers with the certificates of a test
+    // CA.
+    #[cfg(test)]
+    pub(crate) fn with_root_certificate(mut self, root_certificate: &Path) -> Self {
+        self.root_certificate = root_certificate.to_path_buf();
+        self
+    }
+
     fn endpoint(&self, name: &str) -> Option<String> {
         let path = self.endpoints.get(name)?;
         Some(format!("{}{}", self.base_url, path))
     }
 
-    fn customized_client() -> Result<Client, Error> {
+    // API keys are sent with their own scheme, so they can be used
+    // wherever a token is expected.  Requests without a token are
+    // authenticated with the client certificate, if any.
+    fn authorization(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = if token.is_empty() {
+            return headers;
+        } else if token.starts_with(API_KEY_PREFIX) {
+            format!("ApiKey {}", token)
+        } else {
+            format!("Bearer {}", token)
+        };
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            headers.insert(AUTHORIZATION, value);
+        }
+        headers
+    }
+
+    fn customized_client(&self) -> Result<Client, Error> {
+        self.customized_client_with_timeout(Some(Duration::from_secs(5)))
+    }
+
+    // Streamed responses can't have a timeout, because it applies
+    // until the whole body has been read.
+    fn customized_client_with_timeout(&self, timeout: Option<Duration>) -> Result<Client, Error> {
         let mut buf = Vec::new();
-        File::open("private/rsacert.pem")?.read_to_end(&mut buf)?;
+        File::open(&self.root_certificate)?.read_to_end(&mut buf)?;
         let cert = Certificate::from_pem(&buf)?;
-        let client = Client::builder()
+        let mut builder = Client::builder()
             .add_root_certificate(cert)
             .https_only(true)
             // .danger_accept_invalid_certs(true) // TLS: Required for macOS
-            .timeout(Some(Duration::from_secs(5)))
-            .build()?;
+            .timeout(timeout);
+        if let Some(identity) = &self.identity {
+            // Only rustls can use the identities read from PEM.
+            builder = builder
+                .use_rustls_tls()
+                .identity(Identity::from_pem(identity)?);
+        }
+        let client = builder.build()?;
 
         Ok(client)
     }
 
-    /// Login for worker-api and return token.
+    /// Login for worker-api and return the access and refresh tokens.
     ///
     /// * `user` - User name.
     /// * `password` - User password.
-    pub fn login(&self, user: &str, password: &str) -> Result<String, Error> {
+    pub fn login(&self, user: &str, password: &str) -> Result<response::Login, Error> {
         let endpoint = self.endpoint("login").ok_or(Error::InternalError)?;
-        let client = Self::customized_client()?;
+        let client = self.customized_client()?;
         let login_request = request::Login {
             name: user.to_string(),
             password: password.to_string(),
@@ -56,8 +141,48 @@ impl WorkerClient {
         let response = client.post(&endpoint).json(&login_request).send()?;
 
         if response.status().is_success() {
-            let token_data = response.json::<response::Login>()?;
-            Ok(token_data.token)
+            Ok(response.json::<response::Login>()?)
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Exchange a refresh token for new access and refresh tokens.
+    ///
+    /// * `refresh_token` - token that is obtained with the last access token.
+    pub fn refresh(&self, refresh_token: &str) -> Result<response::Login, Error> {
+        let endpoint = self.endpoint("refresh").ok_or(Error::InternalError)?;
+        let client = self.customized_client()?;
+        let refresh_request = request::Refresh {
+            refresh_token: refresh_token.to_string(),
+        };
+        let response = client.post(&endpoint).json(&refresh_request).send()?;
+
+        if response.status().is_success() {
+            Ok(response.json::<response::Login>()?)
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Logout from worker-api, revoking the access and refresh tokens.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `refresh_token` - token that is obtained with the access token.
+    pub fn logout(&self, token: &str, refresh_token: &str) -> Result<(), Error> {
+        let endpoint = self.endpoint("logout").ok_or(Error::InternalError)?;
+        let client = self.customized_client()?;
+        let logout_request = request::Refresh {
+            refresh_token: refresh_token.to_string(),
+        };
+        let response = client
+            .post(&endpoint)
+            .headers(Self::authorization(token))
+            .json(&logout_request)
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(())
         } else {
             Err(Error::ApiError(response.status()))
         }
@@ -66,19 +191,20 @@ impl WorkerClient {
     /// Start a job in worker-api.
     ///
     /// * `token` - authenticated JWT that is obtained from the login command.
-    /// * `id` - valid UUID that will be used for the created job.
-    /// * `command_line` - the command that will be executed in the job.
-    pub fn start(&self, token: &str, id: Uuid, command_line: &str) -> Result<(), Error> {
+    /// * `new_job` - id, command line and settings of the job.  The
+    /// id must be a valid UUID that will be used for the created job.
+    pub fn start(&self, token: &str, new_job: &request::NewJob) -> Result<(), Error> {
         let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
-        let client = Self::customized_client()?;
-        let new_job_request = request::NewJob {
-            id,
-            command_line: command_line.to_string(),
+        // Uploading a workspace can take longer than the usual requests.
+        let client = if new_job.workspace.is_some() {
+            self.customized_client_with_timeout(None)?
+        } else {
+            self.customized_client()?
         };
         let response = client
             .post(&endpoint)
-            .bearer_auth(token)
-            .json(&new_job_request)
+            .headers(Self::authorization(token))
+            .json(new_job)
             .send()?;
 
         if response.status().is_success() {
@@ -95,8 +221,11 @@ impl WorkerClient {
     pub fn status(&self, token: &str, id: Uuid) -> Result<String, Error> {
         let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
         let endpoint_with_id = format!("{}/{}", endpoint, id);
-        let client = Self::customized_client()?;
-        let response = client.get(&endpoint_with_id).bearer_auth(token).send()?;
+        let client = self.customized_client()?;
+        let response = client
+            .get(&endpoint_with_id)
+            .headers(Self::authorization(token))
+            .send()?;
 
         if response.status().is_success() {
             let status_data = response.json::<response::Status>()?;
@@ -113,24 +242,190 @@ impl WorkerClient {
         }
     }
 
-    /// Output of a job in worker-api.
+    /// Wait for a job in worker-api to finish, up to the timeout of a
+    /// long poll, and return its status.  `Session::wait` polls until
+    /// the job finishes.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the job to wait for.
+    pub fn poll(&self, token: &str, id: Uuid) -> Result<response::Status, Error> {
+        let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
+        let endpoint_with_id = format!("{}/{}/wait", endpoint, id);
+        let client =
+            self.customized_client_with_timeout(Some(WAIT_POLL_TIMEOUT + Duration::from_secs(10)))?;
+        let response = client
+            .get(&endpoint_with_id)
+            .query(&[("timeout", format!("{}s", WAIT_POLL_TIMEOUT.as_secs()))])
+            .headers(Self::authorization(token))
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(response.json::<response::Status>()?)
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Output of a job in worker-api.  Compressed outputs are
+    /// decompressed.
     ///
     /// * `token` - authenticated JWT that is obtained from the login command.
     /// * `id` - valid UUID of the job to be queried.
     pub fn output(&self, token: &str, id: Uuid) -> Result<String, Error> {
         let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
         let endpoint_with_id = format!("{}/{}/output", endpoint, id);
-        let client = Self::customized_client()?;
-        let response = client.get(&endpoint_with_id).bearer_auth(token).send()?;
+        let client = self.customized_client()?;
+        let response = client
+            .get(&endpoint_with_id)
+            .header(ACCEPT_ENCODING, "gzip, zstd")
+            .headers(Self::authorization(token))
+            .send()?;
 
         if response.status().is_success() {
-            let output_data = response.text()?;
+            let output_data = Self::decoded_text(response)?;
             Ok(output_data)
         } else {
             Err(Error::ApiError(response.status()))
         }
     }
 
+    // Text of the body of the response according to its encoding.
+    fn decoded_text(response: Response) -> Result<String, Error> {
+        let encoding = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .and_then(|encoding| encoding.to_str().ok())
+            .map(str::to_string);
+        let mut body = Vec::new();
+        match encoding.as_deref() {
+            Some("gzip") => {
+                flate2::read::GzDecoder::new(response).read_to_end(&mut body)?;
+            }
+            Some("zstd") => {
+                zstd::Decoder::new(response)?.read_to_end(&mut body)?;
+            }
+            _ => return Ok(response.text()?),
+        }
+        Ok(String::from_utf8_lossy(&body).into_owned())
+    }
+
+    /// Artifacts collected when a job in worker-api finished.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the job to be queried.
+    pub fn artifacts(&self, token: &str, id: Uuid) -> Result<Vec<response::Artifact>, Error> {
+        let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
+        let endpoint_with_id = format!("{}/{}/artifacts", endpoint, id);
+        let client = self.customized_client()?;
+        let response = client
+            .get(&endpoint_with_id)
+            .headers(Self::authorization(token))
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(response.json::<Vec<response::Artifact>>()?)
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Download an artifact of a job in worker-api and return its
+    /// size.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the job to be queried.
+    /// * `name` - name of the artifact as returned by `artifacts`.
+    /// * `destination` - where the content of the artifact is written.
+    pub fn download_artifact(
+        &self,
+        token: &str,
+        id: Uuid,
+        name: &str,
+        destination: &mut impl Write,
+    ) -> Result<u64, Error> {
+        let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
+        let mut url = Url::parse(&endpoint).map_err(|_| Error::InternalError)?;
+        // Every component of the name is a segment of the path, so
+        // they are escaped individually.
+        url.path_segments_mut()
+            .map_err(|_| Error::InternalError)?
+            .push(&id.to_string())
+            .push("artifacts")
+            .extend(name.split('/'));
+        // Artifacts can be large, so they can't have a timeout.
+        let client = self.customized_client_with_timeout(None)?;
+        let mut response = client.get(url).headers(Self::authorization(token)).send()?;
+
+        if response.status().is_success() {
+            Ok(response.copy_to(destination)?)
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Write to the stdin of a job in worker-api.  The job must have
+    /// been started keeping its stdin open.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the job to write to.
+    /// * `data` - content that is written to the stdin of the job.
+    /// * `close` - close the stdin after writing the data.
+    pub fn write_stdin(
+        &self,
+        token: &str,
+        id: Uuid,
+        data: Vec<u8>,
+        close: bool,
+    ) -> Result<(), Error> {
+        let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
+        let endpoint_with_id = format!("{}/{}/stdin", endpoint, id);
+        let client = self.customized_client()?;
+        let response = client
+            .post(&endpoint_with_id)
+            .query(&[("close", close)])
+            .headers(Self::authorization(token))
+            .body(data)
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Attach to the terminal of a job in worker-api.  A ticket is
+    /// requested with the token and it is used to open the WebSocket of
+    /// the terminal.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the job that was started with a terminal.
+    pub fn attach(&self, token: &str, id: Uuid) -> Result<TerminalAttachment, Error> {
+        let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
+        let endpoint_with_id = format!("{}/{}/attach", endpoint, id);
+        let client = self.customized_client()?;
+        let response = client
+            .post(&endpoint_with_id)
+            .headers(Self::authorization(token))
+            .send()?;
+        if !response.status().is_success() {
+            return Err(Error::ApiError(response.status()));
+        }
+        let ticket = response.json::<response::AttachTicket>()?;
+
+        // The WebSocket is served on its own port of the same host.
+        let url = Url::parse(&endpoint_with_id).map_err(|_| Error::InternalError)?;
+        let host = url.host_str().ok_or(Error::InternalError)?;
+        let path = format!("{}?ticket={}", url.path(), ticket.ticket);
+        TerminalAttachment::connect(
+            host,
+            ticket.port,
+            &path,
+            self.identity.as_deref(),
+            ATTACH_POLL_INTERVAL,
+        )
+    }
+
     /// Stop a job in worker-api.
     ///
     /// * `token` - authenticated JWT that is obtained from the login command.
@@ -138,8 +433,62 @@ impl WorkerClient {
     pub fn stop(&self, token: &str, id: Uuid) -> Result<(), Error> {
         let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
         let endpoint_with_id = format!("{}/{}", endpoint, id);
-        let client = Self::customized_client()?;
-        let response = client.delete(&endpoint_with_id).bearer_auth(token).send()?;
+        let client = self.customized_client()?;
+        let response = client
+            .delete(&endpoint_with_id)
+            .headers(Self::authorization(token))
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Share a job in worker-api with a user or a team.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the job to be shared.
+    /// * `kind` - `users` or `teams`.
+    /// * `name` - id of the user or name of the team.
+    /// * `share` - access granted to the user or the team.
+    pub fn share(
+        &self,
+        token: &str,
+        id: Uuid,
+        kind: &str,
+        name: &str,
+        share: &request::Share,
+    ) -> Result<(), Error> {
+        let url = self.share_url(id, kind, name)?;
+        let client = self.customized_client()?;
+        let response = client
+            .put(url)
+            .headers(Self::authorization(token))
+            .json(share)
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+
+    /// Stop sharing a job in worker-api with a user or a team.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    /// * `id` - valid UUID of the shared job.
+    /// * `kind` - `users` or `teams`.
+    /// * `name` - id of the user or name of the team.
+    pub fn unshare(&self, token: &str, id: Uuid, kind: &str, name: &str) -> Result<(), Error> {
+        let url = self.share_url(id, kind, name)?;
+        let client = self.customized_client()?;
+        let response = client
+            .delete(url)
+            .headers(Self::authorization(token))
+            .send()?;
 
         if response.status().is_success() {
             Ok(())
@@ -147,4 +496,134 @@ impl WorkerClient {
             Err(Error::ApiError(response.status()))
         }
     }
+
+    fn share_url(&self, id: Uuid, kind: &str, name: &str) -> Result<Url, Error> {
+        let endpoint = self.endpoint("jobs").ok_or(Error::InternalError)?;
+        let mut url = Url::parse(&endpoint).map_err(|_| Error::InternalError)?;
+        url.path_segments_mut()
+            .map_err(|_| Error::InternalError)?
+            .push(&id.to_string())
+            .push("shares")
+            .push(kind)
+            .push(name);
+        Ok(url)
+    }
+
+    /// Events of the jobs of the user in worker-api.  The returned
+    /// reader blocks until each event is received.
+    ///
+    /// * `token` - authenticated JWT that is obtained from the login command.
+    pub fn events(&self, token: &str) -> Result<EventReader, Error> {
+        let endpoint = self.endpoint("events").ok_or(Error::InternalError)?;
+        let client = self.customized_client_with_timeout(None)?;
+        let response = client
+            .get(&endpoint)
+            .headers(Self::authorization(token))
+            .send()?;
+
+        if response.status().is_success() {
+            Ok(EventReader::new(response))
+        } else {
+            Err(Error::ApiError(response.status()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustls::{server::AllowAnyAuthenticatedClient, RootCertStore, ServerConfig};
+    use std::{
+        io::BufReader,
+        net::TcpListener,
+        sync::Arc,
+        thread::{self, JoinHandle},
+    };
+
+    fn cert_file(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/certs")
+            .join(name)
+    }
+
+    fn certificates(name: &str) -> Vec<rustls::Certificate> {
+        let mut file = BufReader::new(File::open(cert_file(name)).unwrap());
+        rustls_pemfile::certs(&mut file)
+            .unwrap()
+            .into_iter()
+            .map(rustls::Certificate)
+            .collect()
+    }
+
+    // Server that requires client certificates signed by the test CA
+    // and answers one request.  It returns whether the request was
+    // answered.
+    fn server() -> (String, JoinHandle<bool>) {
+        let mut roots = RootCertStore::empty();
+        roots.add(&certificates("ca.pem")[0]).unwrap();
+        let mut key = BufReader::new(File::open(cert_file("server-key.pem")).unwrap());
+        let key = rustls_pemfile::pkcs8_private_keys(&mut key)
+            .unwrap()
+            .remove(0);
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            .with_single_cert(certificates("server.pem"), rustls::PrivateKey(key))
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!(
+            "https://localhost:{}",
+            listener.local_addr().unwrap().port()
+        );
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let connection = rustls::ServerConnection::new(Arc::new(config)).unwrap();
+            let mut stream = rustls::StreamOwned::new(connection, stream);
+            let mut request = Vec::new();
+            let mut byte = [0; 1];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut byte) {
+                    Ok(1) => request.push(byte[0]),
+                    _ => return false,
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .is_ok()
+        });
+        (base_url, handle)
+    }
+
+    fn client(base_url: &str) -> WorkerClient {
+        WorkerClient::new()
+            .with_base_url(base_url)
+            .with_root_certificate(&cert_file("ca.pem"))
+    }
+
+    #[test]
+    fn client_certificates_are_presented_to_the_server() {
+        let (base_url, server) = server();
+        let client = client(&base_url)
+            .with_client_certificate(&cert_file("client.pem"), &cert_file("client-key.pem"))
+            .unwrap();
+
+        assert!(client.stop("", Uuid::new_v4()).is_ok());
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn servers_with_mutual_tls_refuse_clients_without_certificates() {
+        let (base_url, server) = server();
+
+        assert!(client(&base_url).stop("token", Uuid::new_v4()).is_err());
+        assert!(!server.join().unwrap());
+    }
+
+    #[test]
+    fn client_certificates_need_their_key() {
+        let client = WorkerClient::new()
+            .with_client_certificate(&cert_file("client.pem"), &cert_file("client.pem"));
+
+        assert!(client.is_err());
+    }
 }
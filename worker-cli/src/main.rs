@@ -1,17 +1,50 @@
 mod client;
+mod terminal;
 
-use clap::{crate_authors, crate_name, crate_version, App, Arg, ArgMatches, SubCommand};
-use client::WorkerClient;
+use clap::{crate_authors, crate_name, crate_version, App, Arg, ArgGroup, ArgMatches, SubCommand};
+use client::{
+    request::{self, TerminalSize},
+    response::{self, Event},
+    OidcClient, Session, TerminalAttachment, TerminalMessage, WorkerClient,
+};
 use env_logger;
 use log::info;
+use std::{
+    error::Error,
+    fs,
+    io::{self, Read, Write},
+    path::{Component, Path},
+    process,
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+use terminal::RawMode;
 use uuid::Uuid;
 
+// Exit code used by the subcommands that propagate the exit code of a
+// job, when the job can't be waited for.
+const ERROR_EXIT_CODE: i32 = 255;
+const STDIN_CHUNK_SIZE: usize = 64 * 1024;
+const TERMINAL_SIZE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_TERMINAL_SIZE: TerminalSize = TerminalSize { rows: 24, cols: 80 };
+
 fn main() {
     const SUBC_LOGIN: &str = "login";
+    const SUBC_REFRESH: &str = "refresh";
+    const SUBC_LOGOUT: &str = "logout";
     const SUBC_START: &str = "start";
     const SUBC_OUTPUT: &str = "output";
     const SUBC_STATUS: &str = "status";
     const SUBC_STOP: &str = "stop";
+    const SUBC_EVENTS: &str = "events";
+    const SUBC_WAIT: &str = "wait";
+    const SUBC_RUN: &str = "run";
+    const SUBC_ATTACH: &str = "attach";
+    const SUBC_ARTIFACTS: &str = "artifacts";
+    const SUBC_ARTIFACTS_PULL: &str = "pull";
+    const SUBC_SHARE: &str = "share";
+    const SUBC_UNSHARE: &str = "unshare";
 
     env_logger::init();
 
@@ -23,15 +56,71 @@ fn main() {
              .short("d")
              .long("debug")
              .help("Show debug information"))
+	.arg(Arg::with_name("server")
+             .long("server")
+             .help("Base URL of worker-api, e.g. the address of its connections with mutual TLS")
+             .env("WORKER_API_URL")
+             .takes_value(true)
+             .value_name("URL"))
+	.arg(Arg::with_name("cert")
+             .long("cert")
+             .help("Client certificate (PEM) for servers that require mutual TLS")
+             .env("WORKER_CLIENT_CERT")
+             .takes_value(true)
+             .requires("key")
+             .value_name("FILE"))
+	.arg(Arg::with_name("key")
+             .long("key")
+             .help("Private key (PEM) of the client certificate")
+             .env("WORKER_CLIENT_KEY")
+             .takes_value(true)
+             .requires("cert")
+             .value_name("FILE"))
         .subcommand(
             SubCommand::with_name(SUBC_LOGIN)
                 .about("login and get a token")
+                .arg(Arg::with_name("oidc")
+                        .long("oidc")
+                        .help("Login at the OpenID Connect issuer of worker-api with a browser")
+                        .requires_all(&["issuer", "client_id"]))
+                .arg(Arg::with_name("issuer")
+                        .long("issuer")
+                        .help("URL of the OpenID Connect issuer")
+                        .env("WORKER_OIDC_ISSUER")
+                        .takes_value(true)
+                        .value_name("URL"))
+                .arg(Arg::with_name("client_id")
+                        .long("client-id")
+                        .help("Client id of worker-cli at the OpenID Connect issuer")
+                        .env("WORKER_OIDC_CLIENT_ID")
+                        .takes_value(true)
+                        .value_name("CLIENT_ID"))
                 .arg(Arg::with_name("user")
                         .help("Worker-api user name")
-                        .required(true))
+                        .required_unless("oidc"))
                 .arg(Arg::with_name("password")
                         .help("Worker-api password")
-                        .required(true)))
+                        .required_unless("oidc")))
+        .subcommand(
+            SubCommand::with_name(SUBC_REFRESH)
+                .about("get new tokens before the current one expires")
+                .arg(Arg::with_name("refresh_token")
+                        .help("Refresh token obtained with the current token")
+                        .required(true)
+                        .value_name("REFRESH_TOKEN")))
+        .subcommand(
+            SubCommand::with_name(SUBC_LOGOUT)
+                .about("revoke the current tokens")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE"))
+                .arg(Arg::with_name("refresh_token")
+                        .help("Refresh token obtained with the current token")
+                        .required(true)
+                        .value_name("REFRESH_TOKEN")))
         .subcommand(
             SubCommand::with_name(SUBC_START).about("start a job")
 		.arg(Arg::with_name("token")
@@ -46,6 +135,52 @@ fn main() {
                      .help("Id of the new job to be created (It must be unique). If ommitted, one is generated")
                      .takes_value(true)
                      .value_name("UUID_V4"))
+		.arg(Arg::with_name("stdin")
+                     .long("stdin")
+                     .help("File whose content is written to the stdin of the job")
+                     .takes_value(true)
+                     .value_name("FILE"))
+		.arg(Arg::with_name("tty")
+                     .long("tty")
+                     .help("Run the job in a terminal, so it can be attached to"))
+		.arg(Arg::with_name("hardening")
+                     .long("hardening")
+                     .help("Resource limits and seccomp filter applied to the job. The server decides if missing")
+                     .takes_value(true)
+                     .possible_values(&["none", "default", "strict"])
+                     .value_name("PROFILE"))
+		.arg(Arg::with_name("team")
+                     .long("team")
+                     .help("Team that owns the job together with the user. Its members can read and stop it")
+                     .takes_value(true)
+                     .value_name("TEAM"))
+		.arg(Arg::with_name("upload")
+                     .long("upload")
+                     .help("Directory whose content is uploaded to a new workspace of the job, that is used as its working directory")
+                     .takes_value(true)
+                     .value_name("DIR"))
+                .arg(Arg::with_name("command_line")
+                        .help("Command line to be executed in the job")
+                        .required(true)))
+        .subcommand(
+            SubCommand::with_name(SUBC_RUN).about("run a job streaming its input and output and exit with its exit code")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE"))
+		.arg(Arg::with_name("refresh_token")
+                     .long("refresh-token")
+                     .help("Refresh token to renew the JWT token if it expires")
+                     .takes_value(true)
+                     .value_name("REFRESH_TOKEN"))
+		.arg(Arg::with_name("id")
+                     .short("i")
+                     .long("id")
+                     .help("Id of the new job to be created (It must be unique). If ommitted, one is generated")
+                     .takes_value(true)
+                     .value_name("UUID_V4"))
                 .arg(Arg::with_name("command_line")
                         .help("Command line to be executed in the job")
                         .required(true)))
@@ -85,17 +220,158 @@ fn main() {
                      .help("Id of the job to be stopped.")
                      .required(true)
                      .value_name("UUID_V4")))
+        .subcommand(
+            SubCommand::with_name(SUBC_WAIT).about("wait for a job to finish and exit with its exit code")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE"))
+		.arg(Arg::with_name("refresh_token")
+                     .long("refresh-token")
+                     .help("Refresh token to renew the JWT token if it expires")
+                     .takes_value(true)
+                     .value_name("REFRESH_TOKEN"))
+		.arg(Arg::with_name("id")
+                     .help("Id of the job to wait for.")
+                     .required(true)
+                     .value_name("UUID_V4")))
+        .subcommand(
+            SubCommand::with_name(SUBC_ATTACH).about("attach the local terminal to a job started with a terminal")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE"))
+		.arg(Arg::with_name("refresh_token")
+                     .long("refresh-token")
+                     .help("Refresh token to renew the JWT token if it expires")
+                     .takes_value(true)
+                     .value_name("REFRESH_TOKEN"))
+		.arg(Arg::with_name("id")
+                     .help("Id of the job to attach to.")
+                     .required(true)
+                     .value_name("UUID_V4")))
+        .subcommand(
+            SubCommand::with_name(SUBC_ARTIFACTS).about("manage the artifacts of a job")
+                .subcommand(
+                    SubCommand::with_name(SUBC_ARTIFACTS_PULL).about("download all the artifacts of a job to a directory")
+		        .arg(Arg::with_name("token")
+                             .short("t")
+                             .long("token")
+                             .help("Authorized JWT token")
+                             .takes_value(true)
+                             .value_name("TOKEN_VALUE"))
+		        .arg(Arg::with_name("id")
+                             .help("Id of the job whose artifacts are downloaded.")
+                             .required(true)
+                             .value_name("UUID_V4"))
+		        .arg(Arg::with_name("directory")
+                             .help("Directory where the artifacts are stored. It is created if it doesn't exist.")
+                             .required(true)
+                             .value_name("DIR"))))
+        .subcommand(
+            SubCommand::with_name(SUBC_SHARE).about("share a job with a user or a team")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE"))
+		.arg(Arg::with_name("user")
+                     .long("user")
+                     .help("Id of the user the job is shared with")
+                     .takes_value(true)
+                     .value_name("USER_ID"))
+		.arg(Arg::with_name("team")
+                     .long("team")
+                     .help("Name of the team the job is shared with")
+                     .takes_value(true)
+                     .value_name("TEAM"))
+                .group(ArgGroup::with_name("grantee")
+                       .args(&["user", "team"])
+                       .required(true))
+		.arg(Arg::with_name("access")
+                     .long("access")
+                     .help("Operations that are allowed")
+                     .takes_value(true)
+                     .possible_values(&["read", "stop"])
+                     .default_value("read")
+                     .value_name("ACCESS"))
+		.arg(Arg::with_name("id")
+                     .help("Id of the job to be shared.")
+                     .required(true)
+                     .value_name("UUID_V4")))
+        .subcommand(
+            SubCommand::with_name(SUBC_UNSHARE).about("stop sharing a job with a user or a team")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE"))
+		.arg(Arg::with_name("user")
+                     .long("user")
+                     .help("Id of the user the job is no longer shared with")
+                     .takes_value(true)
+                     .value_name("USER_ID"))
+		.arg(Arg::with_name("team")
+                     .long("team")
+                     .help("Name of the team the job is no longer shared with")
+                     .takes_value(true)
+                     .value_name("TEAM"))
+                .group(ArgGroup::with_name("grantee")
+                       .args(&["user", "team"])
+                       .required(true))
+		.arg(Arg::with_name("id")
+                     .help("Id of the shared job.")
+                     .required(true)
+                     .value_name("UUID_V4")))
+        .subcommand(
+            SubCommand::with_name(SUBC_EVENTS).about("watch the events of the jobs")
+		.arg(Arg::with_name("token")
+                     .short("t")
+                     .long("token")
+                     .help("Authorized JWT token")
+                     .takes_value(true)
+                     .value_name("TOKEN_VALUE")))
         .get_matches();
 
     let debug = matches.is_present("debug");
-    let worker_client = WorkerClient::new();
+    let worker_client = match matches.value_of("server") {
+        Some(server) => WorkerClient::new().with_base_url(server),
+        None => WorkerClient::new(),
+    };
+    let worker_client = match (matches.value_of("cert"), matches.value_of("key")) {
+        (Some(cert), Some(key)) => {
+            match worker_client.with_client_certificate(Path::new(cert), Path::new(key)) {
+                Ok(worker_client) => worker_client,
+                Err(err) => {
+                    eprintln!("ERR: Client certificate error: {}", err);
+                    process::exit(ERROR_EXIT_CODE);
+                }
+            }
+        }
+        _ => worker_client,
+    };
     match matches.subcommand() {
         (SUBC_LOGIN, Some(subc_matches)) => {
             exec_login(&subc_matches, &worker_client, debug);
         }
+        (SUBC_REFRESH, Some(subc_matches)) => {
+            exec_refresh(&subc_matches, &worker_client, debug);
+        }
+        (SUBC_LOGOUT, Some(subc_matches)) => {
+            exec_logout(&subc_matches, &worker_client, debug);
+        }
         (SUBC_START, Some(subc_matches)) => {
             exec_start(&subc_matches, &worker_client, debug);
         }
+        (SUBC_RUN, Some(subc_matches)) => {
+            exec_run(&subc_matches, &worker_client, debug);
+        }
         (SUBC_STATUS, Some(subc_matches)) => {
             exec_status(&subc_matches, &worker_client, debug);
         }
@@ -105,6 +381,29 @@ fn main() {
         (SUBC_STOP, Some(subc_matches)) => {
             exec_stop(&subc_matches, &worker_client, debug);
         }
+        (SUBC_WAIT, Some(subc_matches)) => {
+            exec_wait(&subc_matches, &worker_client, debug);
+        }
+        (SUBC_ATTACH, Some(subc_matches)) => {
+            exec_attach(&subc_matches, &worker_client, debug);
+        }
+        (SUBC_ARTIFACTS, Some(subc_matches)) => match subc_matches.subcommand() {
+            (SUBC_ARTIFACTS_PULL, Some(pull_matches)) => {
+                exec_artifacts_pull(&pull_matches, &worker_client, debug);
+            }
+            _ => {
+                eprintln!("ERR: Unexpected artifacts subcommand")
+            }
+        },
+        (SUBC_SHARE, Some(subc_matches)) => {
+            exec_share(&subc_matches, &worker_client, debug);
+        }
+        (SUBC_UNSHARE, Some(subc_matches)) => {
+            exec_unshare(&subc_matches, &worker_client, debug);
+        }
+        (SUBC_EVENTS, Some(subc_matches)) => {
+            exec_events(&subc_matches, &worker_client, debug);
+        }
         _ => {
             eprintln!("ERR: Unexpected subcommand")
         }
@@ -112,6 +411,10 @@ fn main() {
 }
 
 fn exec_login(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    if matches.is_present("oidc") {
+        exec_oidc_login(matches, debug);
+        return;
+    }
     let user = matches
         .value_of("user")
         .expect("ERR: Required argument 'user' is unexpectedly missing");
@@ -122,9 +425,39 @@ fn exec_login(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
         println!("Login to worker-api as user '{}'", user);
     }
     match worker_client.login(user, password) {
-        Ok(token) => {
+        Ok(tokens) => print_tokens(&tokens),
+        Err(err) => {
+            eprintln!("ERR: Login error: {}", err);
+        }
+    }
+}
+
+fn exec_oidc_login(matches: &ArgMatches, debug: bool) {
+    let issuer = matches
+        .value_of("issuer")
+        .expect("ERR: Required argument 'issuer' is unexpectedly missing");
+    let client_id = matches
+        .value_of("client_id")
+        .expect("ERR: Required argument 'client_id' is unexpectedly missing");
+    if debug {
+        println!("Login to '{}' as client '{}'", issuer, client_id);
+    }
+    let oidc_client = OidcClient::new(issuer, client_id);
+    let login = oidc_client.device_login(|authorization| {
+        match &authorization.verification_uri_complete {
+            Some(uri) => eprintln!("Open {} to login.", uri),
+            None => eprintln!(
+                "Open {} and enter the code {} to login.",
+                authorization.verification_uri, authorization.user_code
+            ),
+        }
+        eprintln!("Waiting for the login to be approved...");
+    });
+    match login {
+        Ok(id_token) => {
+            // ID tokens can't be refreshed by worker-api.
             println!("Copy, paste and execute:");
-            println!("export TOKEN={:?}", token);
+            println!("export TOKEN={:?}", id_token);
         }
         Err(err) => {
             eprintln!("ERR: Login error: {}", err);
@@ -132,6 +465,45 @@ fn exec_login(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
     }
 }
 
+fn exec_refresh(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let refresh_token = matches
+        .value_of("refresh_token")
+        .expect("ERR: Required argument 'refresh_token' is unexpectedly missing");
+    if debug {
+        println!("Using refresh token: '{}'", refresh_token);
+    }
+    match worker_client.refresh(refresh_token) {
+        Ok(tokens) => print_tokens(&tokens),
+        Err(err) => {
+            eprintln!("ERR: Refresh error: {}", err);
+        }
+    }
+}
+
+fn exec_logout(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    let refresh_token = matches
+        .value_of("refresh_token")
+        .expect("ERR: Required argument 'refresh_token' is unexpectedly missing");
+    if debug {
+        println!("Using token: '{}'", token);
+    }
+    match worker_client.logout(token, refresh_token) {
+        Ok(()) => {
+            println!("The tokens have been revoked.");
+        }
+        Err(err) => {
+            eprintln!("ERR: Logout error: {}", err);
+        }
+    }
+}
+
+fn print_tokens(tokens: &response::Login) {
+    println!("Copy, paste and execute:");
+    println!("export TOKEN={:?}", tokens.token);
+    println!("export REFRESH_TOKEN={:?}", tokens.refresh_token);
+}
+
 fn exec_start(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
     let token = matches.value_of("token").unwrap_or("");
     let id = matches
@@ -140,6 +512,25 @@ fn exec_start(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
         .unwrap_or_else(Uuid::new_v4);
     let command_line = matches.value_of("command_line").unwrap_or_default();
 
+    let stdin = match matches.value_of("stdin").map(fs::read).transpose() {
+        Ok(stdin) => stdin,
+        Err(err) => {
+            eprintln!("ERR: Stdin file can't be read: {}", err);
+            return;
+        }
+    };
+    let workspace = match matches
+        .value_of("upload")
+        .map(|directory| request::Workspace::from_directory(Path::new(directory)))
+        .transpose()
+    {
+        Ok(workspace) => workspace,
+        Err(err) => {
+            eprintln!("ERR: Upload directory can't be archived: {}", err);
+            return;
+        }
+    };
+
     if !command_line.trim().is_empty() {
         info!("Starting a job");
         if debug {
@@ -148,7 +539,17 @@ fn exec_start(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
             println!("Command line: '{}'", command_line);
         }
 
-        match worker_client.start(token, id, command_line) {
+        let mut new_job = request::NewJob::new(id, command_line);
+        if let Some(ref stdin) = stdin {
+            new_job = new_job.with_stdin(stdin);
+        }
+        if matches.is_present("tty") {
+            new_job.terminal = Some(terminal::size().unwrap_or(DEFAULT_TERMINAL_SIZE));
+        }
+        new_job.workspace = workspace;
+        new_job.hardening = matches.value_of("hardening").map(str::to_string);
+        new_job.team = matches.value_of("team").map(str::to_string);
+        match worker_client.start(token, &new_job) {
             Ok(()) => {
                 println!("New job started with id: '{}'", id.to_string());
             }
@@ -161,6 +562,164 @@ fn exec_start(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
     }
 }
 
+fn exec_run(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    let session = Session::new(
+        worker_client.clone(),
+        token,
+        matches.value_of("refresh_token"),
+    );
+    let id = matches
+        .value_of("id")
+        .map(|id| Uuid::parse_str(id).unwrap_or_else(|_| Uuid::new_v4()))
+        .unwrap_or_else(Uuid::new_v4);
+    let command_line = matches.value_of("command_line").unwrap_or_default();
+
+    if command_line.trim().is_empty() {
+        eprintln!("ERR: empty command line.");
+        process::exit(ERROR_EXIT_CODE);
+    }
+    info!("Running a job");
+    if debug {
+        println!("Using token: '{}'", token);
+        println!("New job id: '{}'", id.to_string());
+        println!("Command line: '{}'", command_line);
+    }
+
+    // Subscribe before starting the job, so no output is missed.
+    let events = worker_client.events(token).unwrap_or_else(|err| {
+        eprintln!("ERR: Run command error: {}", err);
+        process::exit(ERROR_EXIT_CODE);
+    });
+    let new_job = request::NewJob {
+        stdin_open: true,
+        ..request::NewJob::new(id, command_line)
+    };
+    if let Err(err) = worker_client.start(token, &new_job) {
+        eprintln!("ERR: Run command error: {}", err);
+        process::exit(ERROR_EXIT_CODE);
+    }
+    forward_interrupts(session.clone(), id);
+    forward_stdin(session.clone(), id);
+
+    // If the stream of events is interrupted, the final status is
+    // still available.
+    let exit_code = stream_job_output(events, id)
+        .or_else(|| session.wait(id).ok().map(|s| s.exit_code()))
+        .unwrap_or(ERROR_EXIT_CODE);
+    exit_session(&session, exit_code);
+}
+
+// Stop the remote job when the user presses Ctrl-C.  The output keeps
+// being streamed until the job finishes.
+fn forward_interrupts(session: Session, id: Uuid) {
+    let result = ctrlc::set_handler(move || {
+        info!("Stopping job '{}'", id);
+        if let Err(err) = session.call(|client, token| client.stop(token, id)) {
+            eprintln!("ERR: Stop command error: {}", err);
+        }
+    });
+    if let Err(err) = result {
+        eprintln!("ERR: Ctrl-C won't stop the job: {}", err);
+    }
+}
+
+// Copy the local stdin to the stdin of the remote job in a background
+// thread, closing it when the local one ends.
+fn forward_stdin(session: Session, id: Uuid) {
+    thread::spawn(move || {
+        let mut buffer = vec![0; STDIN_CHUNK_SIZE];
+        loop {
+            let (data, close) = match io::stdin().read(&mut buffer) {
+                Ok(0) | Err(_) => (Vec::new(), true),
+                Ok(read) => (buffer[..read].to_vec(), false),
+            };
+            // The job may have finished without reading its input.
+            let written =
+                session.call(|client, token| client.write_stdin(token, id, data.clone(), close));
+            if written.is_err() || close {
+                break;
+            }
+        }
+    });
+}
+
+// Exit with the code of a command, showing the tokens of its session
+// if they have been renewed, since the refresh token that was given
+// can't be used again.
+fn exit_session(session: &Session, exit_code: i32) -> ! {
+    if let Some(tokens) = session.renewed_tokens() {
+        eprintln!("The tokens have been renewed. Copy, paste and execute:");
+        eprintln!("export TOKEN={:?}", tokens.token);
+        eprintln!("export REFRESH_TOKEN={:?}", tokens.refresh_token);
+    }
+    process::exit(exit_code);
+}
+
+// Copy the output of the job to the local stdout and stderr until it
+// finishes and return its exit code.  It returns `None` if the stream
+// of events ends before or some of them are lost.
+fn stream_job_output(
+    events: impl Iterator<Item = Result<Event, client::Error>>,
+    id: Uuid,
+) -> Option<i32> {
+    for event in events {
+        match event {
+            Ok(Event::Output {
+                id: event_id,
+                stream,
+                data,
+            }) if event_id == id => {
+                let data = match base64::decode(&data) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        eprintln!("ERR: Run command error: {}", err);
+                        return None;
+                    }
+                };
+                let result = if stream == "stderr" {
+                    write_and_flush(&mut io::stderr(), &data)
+                } else {
+                    write_and_flush(&mut io::stdout(), &data)
+                };
+                if result.is_err() {
+                    return None;
+                }
+            }
+            Ok(Event::Finished {
+                id: event_id,
+                status,
+                exit_status,
+                signal,
+            }) if event_id == id => {
+                let status = response::Status {
+                    status,
+                    exit_status,
+                    signal,
+                };
+                return Some(status.exit_code());
+            }
+            // The final status may have been lost too, so it is waited
+            // for instead.
+            Ok(Event::Lagged) => {
+                eprintln!("ERR: Run command error: some output of the job has been lost");
+                return None;
+            }
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("ERR: Run command error: {}", err);
+                return None;
+            }
+        }
+    }
+    None
+}
+
+fn write_and_flush(destination: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    destination.write_all(data)?;
+    destination.flush()
+}
+
 fn exec_status(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
     let token = matches.value_of("token").unwrap_or("");
     if let Some(id) = matches
@@ -242,3 +801,364 @@ fn exec_stop(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
         eprintln!("ERR: Invalid Id.");
     }
 }
+
+fn exec_share(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    if let Some(id) = matches
+        .value_of("id")
+        .map(|id| Uuid::parse_str(id).ok())
+        .flatten()
+    {
+        info!("Sharing a job");
+        let (kind, name) = grantee(matches);
+        let share = request::Share {
+            access: matches.value_of("access").unwrap_or("read").to_string(),
+        };
+        if debug {
+            println!("Using token: '{}'", token);
+            println!("Job id: '{}'", id.to_string());
+        }
+
+        match worker_client.share(token, id, kind, name, &share) {
+            Ok(()) => {
+                println!(
+                    "Job with id '{}' has been shared with {} '{}'.",
+                    id, kind, name
+                );
+            }
+            Err(err) => {
+                eprintln!("ERR: Share command error: {}", err);
+            }
+        }
+    } else {
+        eprintln!("ERR: Invalid Id.");
+    }
+}
+
+fn exec_unshare(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    if let Some(id) = matches
+        .value_of("id")
+        .map(|id| Uuid::parse_str(id).ok())
+        .flatten()
+    {
+        info!("Unsharing a job");
+        let (kind, name) = grantee(matches);
+        if debug {
+            println!("Using token: '{}'", token);
+            println!("Job id: '{}'", id.to_string());
+        }
+
+        match worker_client.unshare(token, id, kind, name) {
+            Ok(()) => {
+                println!(
+                    "Job with id '{}' is no longer shared with {} '{}'.",
+                    id, kind, name
+                );
+            }
+            Err(err) => {
+                eprintln!("ERR: Unshare command error: {}", err);
+            }
+        }
+    } else {
+        eprintln!("ERR: Invalid Id.");
+    }
+}
+
+// The kind of grantee, as named in the URL, and its name.  Clap
+// requires one of them.
+fn grantee<'a>(matches: &'a ArgMatches) -> (&'static str, &'a str) {
+    match matches.value_of("user") {
+        Some(user) => ("users", user),
+        None => ("teams", matches.value_of("team").unwrap_or("")),
+    }
+}
+
+fn exec_attach(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    let session = Session::new(
+        worker_client.clone(),
+        token,
+        matches.value_of("refresh_token"),
+    );
+    let id = match matches
+        .value_of("id")
+        .map(|id| Uuid::parse_str(id).ok())
+        .flatten()
+    {
+        Some(id) => id,
+        None => {
+            eprintln!("ERR: Invalid Id.");
+            process::exit(ERROR_EXIT_CODE);
+        }
+    };
+    info!("Attaching to a job");
+    if debug {
+        println!("Using token: '{}'", token);
+        println!("Job id: '{}'", id.to_string());
+    }
+
+    let attachment = session.call(|client, token| client.attach(token, id));
+    let mut attachment = attachment.unwrap_or_else(|err| {
+        eprintln!("ERR: Attach command error: {}", err);
+        process::exit(ERROR_EXIT_CODE);
+    });
+    let (input_sender, inputs) = mpsc::channel();
+    if let Some(size) = terminal::size() {
+        if let Err(err) = attachment.resize(size) {
+            eprintln!("ERR: Attach command error: {}", err);
+            process::exit(ERROR_EXIT_CODE);
+        }
+        forward_resizes(input_sender.clone(), size);
+    }
+    // Special characters, like Ctrl-C, are handled by the remote
+    // terminal.
+    let raw_mode = RawMode::enable().ok();
+    forward_terminal_input(input_sender);
+
+    let exit_code = copy_terminal(&mut attachment, &inputs)
+        .or_else(|| session.wait(id).ok().map(|s| s.exit_code()))
+        .unwrap_or(ERROR_EXIT_CODE);
+    if let Some(raw_mode) = raw_mode {
+        raw_mode.restore();
+    }
+    exit_session(&session, exit_code);
+}
+
+// Input for the remote terminal, produced by the threads that watch
+// the local one.
+enum TerminalInput {
+    Data(Vec<u8>),
+    Resize(TerminalSize),
+}
+
+// Send the local stdin to the remote terminal from a background
+// thread.  The remote terminal is kept open when the local stdin ends.
+fn forward_terminal_input(inputs: mpsc::Sender<TerminalInput>) {
+    thread::spawn(move || {
+        let mut buffer = vec![0; STDIN_CHUNK_SIZE];
+        loop {
+            match io::stdin().read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if inputs
+                        .send(TerminalInput::Data(buffer[..read].to_vec()))
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+// Resize the remote terminal when the local one changes its size.
+fn forward_resizes(inputs: mpsc::Sender<TerminalInput>, size: TerminalSize) {
+    thread::spawn(move || {
+        let mut current_size = size;
+        loop {
+            thread::sleep(TERMINAL_SIZE_POLL_INTERVAL);
+            match terminal::size() {
+                Some(size) if size != current_size => {
+                    if inputs.send(TerminalInput::Resize(size)).is_err() {
+                        break;
+                    }
+                    current_size = size;
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+// Copy the input to the remote terminal and its output to the local
+// stdout until the connection is closed, and return the exit code of
+// the job.  It returns `None` if the connection ends before the job.
+fn copy_terminal(
+    attachment: &mut TerminalAttachment,
+    inputs: &mpsc::Receiver<TerminalInput>,
+) -> Option<i32> {
+    let mut exit_code = None;
+    loop {
+        for input in inputs.try_iter() {
+            let sent = match input {
+                TerminalInput::Data(data) => attachment.write_input(&data),
+                TerminalInput::Resize(size) => attachment.resize(size),
+            };
+            if let Err(err) = sent {
+                eprintln!("ERR: Attach command error: {}", err);
+                return exit_code;
+            }
+        }
+        match attachment.read() {
+            Ok(Some(TerminalMessage::Output(data))) => {
+                if write_and_flush(&mut io::stdout(), &data).is_err() {
+                    return exit_code;
+                }
+            }
+            Ok(Some(TerminalMessage::Finished(status))) => exit_code = Some(status.exit_code()),
+            Ok(Some(TerminalMessage::Closed(reason))) => {
+                if let Some(reason) = reason {
+                    eprintln!("ERR: Attach command error: {}", reason);
+                }
+                return exit_code;
+            }
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("ERR: Attach command error: {}", err);
+                return exit_code;
+            }
+        }
+    }
+}
+
+fn exec_wait(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    let session = Session::new(
+        worker_client.clone(),
+        token,
+        matches.value_of("refresh_token"),
+    );
+    if let Some(id) = matches
+        .value_of("id")
+        .map(|id| Uuid::parse_str(id).ok())
+        .flatten()
+    {
+        info!("Waiting for a job to finish");
+        if debug {
+            println!("Using token: '{}'", token);
+            println!("Job id: '{}'", id.to_string());
+        }
+
+        match session.wait(id) {
+            Ok(status) => {
+                if debug {
+                    println!("Job '{}' status is {}.", id.to_string(), status.status);
+                }
+                exit_session(&session, status.exit_code());
+            }
+            Err(err) => {
+                eprintln!("ERR: Wait command error: {}", err);
+                exit_session(&session, ERROR_EXIT_CODE);
+            }
+        }
+    } else {
+        eprintln!("ERR: Invalid Id.");
+        process::exit(ERROR_EXIT_CODE);
+    }
+}
+
+fn exec_artifacts_pull(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    let directory = Path::new(matches.value_of("directory").unwrap_or_default());
+    if let Some(id) = matches
+        .value_of("id")
+        .map(|id| Uuid::parse_str(id).ok())
+        .flatten()
+    {
+        info!("Downloading the artifacts of a job");
+        if debug {
+            println!("Using token: '{}'", token);
+            println!("Job id: '{}'", id.to_string());
+        }
+
+        let artifacts = match worker_client.artifacts(token, id) {
+            Ok(artifacts) => artifacts,
+            Err(err) => {
+                eprintln!("ERR: Artifacts command error: {}", err);
+                return;
+            }
+        };
+        for artifact in artifacts {
+            match pull_artifact(worker_client, token, id, &artifact.name, directory) {
+                Ok(size) if size == artifact.size => println!("{} ({} bytes)", artifact.name, size),
+                Ok(size) => eprintln!(
+                    "ERR: Artifact {} is incomplete: {} of {} bytes",
+                    artifact.name, size, artifact.size
+                ),
+                Err(err) => eprintln!("ERR: Artifact {} can't be pulled: {}", artifact.name, err),
+            }
+        }
+    } else {
+        eprintln!("ERR: Invalid Id.");
+    }
+}
+
+// Download an artifact to the same relative path in the directory.
+// Names that would be stored outside of the directory are rejected.
+fn pull_artifact(
+    worker_client: &WorkerClient,
+    token: &str,
+    id: Uuid,
+    name: &str,
+    directory: &Path,
+) -> Result<u64, Box<dyn Error>> {
+    let relative = Path::new(name);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(format!("Invalid artifact name {}", name).into());
+    }
+    let path = directory.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = fs::File::create(&path)?;
+    Ok(worker_client.download_artifact(token, id, name, &mut file)?)
+}
+
+fn exec_events(matches: &ArgMatches, worker_client: &WorkerClient, debug: bool) {
+    let token = matches.value_of("token").unwrap_or("");
+    info!("Watching the events of the jobs");
+    if debug {
+        println!("Using token: '{}'", token);
+    }
+
+    match worker_client.events(token) {
+        Ok(events) => {
+            for event in events {
+                match event {
+                    Ok(event) => print_event(&event),
+                    Err(err) => {
+                        eprintln!("ERR: Events command error: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("ERR: Events command error: {}", err);
+        }
+    }
+}
+
+fn print_event(event: &Event) {
+    match event {
+        Event::Started { id } => println!("Job '{}' has started.", id),
+        Event::Output { id, stream, data } => {
+            let data = base64::decode(data).unwrap_or_default();
+            for line in String::from_utf8_lossy(&data).lines() {
+                println!("Job '{}' {}: {}", id, stream, line);
+            }
+        }
+        Event::Stopped { id } => println!("Job '{}' has been stopped.", id),
+        Event::Finished {
+            id,
+            status,
+            exit_status,
+            signal,
+        } => println!(
+            "Job '{}' has finished with status {} ({}).",
+            id,
+            status,
+            exit_status
+                .map(|s| s.to_string())
+                .or_else(|| signal.map(|s| format!("signal {}", s)))
+                .unwrap_or_else(|| "_".to_string())
+        ),
+        Event::Removed { id } => println!("Job '{}' has been removed.", id),
+        Event::Lagged => println!("Some events have been lost."),
+    }
+}
@@ -0,0 +1,51 @@
+use crate::client::request::TerminalSize;
+use std::{io, mem};
+
+// Size of the local terminal, if the stdout is a terminal.
+pub fn size() -> Option<TerminalSize> {
+    // Safe because the ioctl only writes to the given struct.
+    unsafe {
+        let mut winsize: libc::winsize = mem::zeroed();
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut winsize) < 0 {
+            return None;
+        }
+        Some(TerminalSize {
+            rows: winsize.ws_row,
+            cols: winsize.ws_col,
+        })
+    }
+}
+
+/// Raw mode of the local terminal.  Input is passed byte by byte
+/// without echo or special characters, so the remote terminal handles
+/// them.  The previous mode is restored with `restore`.
+pub struct RawMode {
+    original: libc::termios,
+}
+
+impl RawMode {
+    /// Put the stdin terminal in raw mode.
+    pub fn enable() -> io::Result<RawMode> {
+        // Safe because the calls only access the given structs.
+        unsafe {
+            let mut original: libc::termios = mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(RawMode { original })
+        }
+    }
+
+    /// Restore the mode that the terminal had before.
+    pub fn restore(&self) {
+        // Safe because the call only reads the given struct.
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::Permission;
+
+/// Access to a job that is granted by sharing it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Access {
+    /// Query the status, the output and the artifacts of the job.
+    Read,
+    /// Read and stop the job.
+    Stop,
+}
+
+impl Access {
+    /// Check if the access includes an operation.
+    ///
+    /// * `permission` - Operation that will be performed.
+    pub fn grants(&self, permission: Permission) -> bool {
+        matches!(
+            (self, permission),
+            (_, Permission::Read) | (Access::Stop, Permission::Stop)
+        )
+    }
+}
@@ -0,0 +1,10 @@
+/// Type that describes a file produced by a job and collected as an
+/// artifact once the job has finished.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Artifact {
+    /// Path of the file relative to the working directory of the job.
+    /// It is used as the name of the artifact.
+    pub name: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+}
@@ -0,0 +1,72 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read},
+    os::unix::fs::OpenOptionsExt,
+    path::Path,
+};
+
+/// Algorithm used to compress the output of a job once it has
+/// finished.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Compression {
+    /// Gzip compression.
+    Gzip,
+    /// Zstandard compression.
+    Zstd,
+}
+
+impl Compression {
+    /// Name of the algorithm as used in the HTTP `Content-Encoding`
+    /// and `Accept-Encoding` headers.
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Return a reader that decompresses the content of the given one.
+    ///
+    /// * `reader` - Reader of the compressed content.
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+
+    // Extension added to the name of the compressed file.
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    // Write a compressed copy of the source file.  It blocks, so it
+    // must be run out of the async tasks.
+    pub(crate) fn compress(&self, source: &Path, destination: &Path) -> io::Result<()> {
+        let mut source = BufReader::new(File::open(source)?);
+        let destination = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(destination)?;
+        let destination = BufWriter::new(destination);
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(destination, flate2::Compression::default());
+                io::copy(&mut source, &mut encoder)?;
+                encoder.finish()?;
+            }
+            Compression::Zstd => {
+                let mut encoder = zstd::Encoder::new(destination, 0)?;
+                io::copy(&mut source, &mut encoder)?;
+                encoder.finish()?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use std::{ffi::CString, fs, io, os::unix::ffi::OsStrExt, path::Path};
+use tokio::process::Command;
+
+/// Type that defines the Unix user that runs a job: its user id, its
+/// primary group and its supplementary groups.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credentials {
+    /// User id.
+    pub uid: u32,
+    /// Primary group id.
+    pub gid: u32,
+    /// Supplementary group ids.
+    pub groups: Vec<u32>,
+}
+
+impl Credentials {
+    /// Whether these credentials are root, belong to its group or are
+    /// the user of the current process, so the jobs that run with them
+    /// wouldn't be isolated from the server.
+    pub fn is_privileged(&self) -> bool {
+        // Safe because geteuid always succeeds.
+        let server_uid = unsafe { libc::geteuid() };
+        self.uid == 0 || self.gid == 0 || self.groups.contains(&0) || self.uid == server_uid
+    }
+
+    // Switch to these credentials in the child process before exec.
+    pub(crate) fn apply(&self, command: &mut Command) {
+        let credentials = self.clone();
+        // Safe because only async-signal-safe functions are called
+        // between fork and exec and nothing is allocated.
+        unsafe {
+            command.pre_exec(move || credentials.switch());
+        }
+    }
+
+    // Change the user of the current process to these credentials.
+    // The groups are changed first, because it can't be done once the
+    // user has been changed.  They are only left untouched when the
+    // process keeps its own user without any supplementary groups, so
+    // it fails instead of keeping the groups of the server for another
+    // user.  It is only meant to be called in a child process between
+    // fork and exec, since it only calls async-signal-safe functions.
+    pub(crate) fn switch(&self) -> io::Result<()> {
+        let groups = &self.groups;
+        // Safe because the pointer and the length come from the same
+        // vector.
+        unsafe {
+            if (!groups.is_empty() || self.uid != libc::geteuid())
+                && libc::setgroups(groups.len() as _, groups.as_ptr()) < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::setgid(self.gid) < 0 || libc::setuid(self.uid) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    // Make these credentials the owner of a directory and everything
+    // in it.  Symbolic links are changed, but not followed.
+    pub(crate) fn own(&self, path: &Path) -> io::Result<()> {
+        let c_path = CString::new(path.as_os_str().as_bytes())?;
+        // Safe because the path is a valid C string.
+        if unsafe { libc::lchown(c_path.as_ptr(), self.uid, self.gid) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if fs::symlink_metadata(path)?.is_dir() {
+            for entry in fs::read_dir(path)? {
+                self.own(&entry?.path())?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use crate::Principal;
+
+/// User or team that a job is shared with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Grantee {
+    /// User with this string id.
+    User(String),
+    /// Every member of the team with this name.
+    Team(String),
+}
+
+impl Grantee {
+    // Check if the principal is, or is a member of, the grantee.
+    pub(crate) fn includes(&self, principal: Principal<'_>) -> bool {
+        match self {
+            Grantee::User(id) => id == principal.id,
+            Grantee::Team(team) => principal.teams.contains(team),
+        }
+    }
+}
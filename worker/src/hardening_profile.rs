@@ -0,0 +1,83 @@
+use crate::seccomp_filter::SeccompFilter;
+use std::io;
+use tokio::process::Command;
+
+const MIB: u64 = 1024 * 1024;
+
+/// Restrictions applied to the process of a job before exec.  The
+/// profiles are ordered from the least to the most restrictive.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum HardeningProfile {
+    /// No restrictions.
+    None,
+    /// Resource limits and a seccomp filter that denies the system
+    /// calls that administer the system, like `mount`, `ptrace` or
+    /// `reboot`.
+    Default,
+    /// Lower resource limits and a seccomp filter that also denies
+    /// network sockets.
+    Strict,
+}
+
+impl Default for HardeningProfile {
+    fn default() -> Self {
+        HardeningProfile::None
+    }
+}
+
+impl HardeningProfile {
+    /// Name of the profile: `none`, `default` or `strict`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            HardeningProfile::None => "none",
+            HardeningProfile::Default => "default",
+            HardeningProfile::Strict => "strict",
+        }
+    }
+
+    // Apply the resource limits and install the seccomp filter in the
+    // child process before exec.  It must be the last change to the
+    // command, because the filter also applies to the code that runs
+    // after it.  The limits never exceed the current hard limits, so
+    // they can be applied without privileges.
+    pub(crate) fn apply(&self, command: &mut Command) {
+        let limits = match self {
+            HardeningProfile::None => return,
+            HardeningProfile::Default => [
+                (libc::RLIMIT_NOFILE, 1024),
+                (libc::RLIMIT_CORE, 0),
+                (libc::RLIMIT_FSIZE, 1024 * MIB),
+                (libc::RLIMIT_NPROC, 1024),
+            ],
+            HardeningProfile::Strict => [
+                (libc::RLIMIT_NOFILE, 256),
+                (libc::RLIMIT_CORE, 0),
+                (libc::RLIMIT_FSIZE, 64 * MIB),
+                (libc::RLIMIT_NPROC, 64),
+            ],
+        };
+        let filter = SeccompFilter::new(*self == HardeningProfile::Strict);
+        // Safe because only async-signal-safe functions are called
+        // between fork and exec and nothing is allocated.
+        unsafe {
+            command.pre_exec(move || {
+                for &(resource, value) in &limits {
+                    let mut limit = libc::rlimit {
+                        rlim_cur: 0,
+                        rlim_max: 0,
+                    };
+                    if libc::getrlimit(resource, &mut limit) < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    let value = (value as libc::rlim_t).min(limit.rlim_max);
+                    limit.rlim_cur = value;
+                    limit.rlim_max = value;
+                    if libc::setrlimit(resource, &limit) < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                filter.install()
+            });
+        }
+    }
+}
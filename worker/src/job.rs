@@ -1,15 +1,58 @@
-use crate::{JobError, JobStatus};
-use std::{fs::File, process::Stdio};
-use tokio::process::{Child, Command};
+use crate::{
+    job_acl::JobAcl,
+    job_artifacts::JobArtifacts,
+    job_attachment::JobAttachments,
+    job_event::{EventPublisher, OutputStream, PublishedEvent},
+    job_output::JobOutput,
+    job_workspace::JobWorkspace,
+    pty::Pty,
+    Artifact, Grantee, JobAttachment, JobCompletion, JobError, JobInfo, JobOptions, JobStatus,
+    OutputFile, Permission, Principal, Share, TerminalSize,
+};
+use std::{
+    fs::File,
+    io,
+    process::Stdio,
+    sync::{self, Arc},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    process::{Child, Command},
+    sync::{broadcast, watch, Mutex, Notify},
+    task::JoinHandle,
+    time,
+};
 use uuid::Uuid;
 
+const OUTPUT_CHUNK_SIZE: usize = 8 * 1024;
+// Time that a write to the stdin may wait for the process to read it.
+const STDIN_WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Where the input of the job is written: a pipe or a pseudo terminal.
+type Input = Box<dyn AsyncWrite + Send + Unpin>;
+
 /// Type that contains the details of a job.
+///
+/// The child process is owned by a task that waits for it to finish,
+/// so all the methods take `&self` and a `Job` can be shared among
+/// tasks using an `Arc`.
 pub struct Job {
     id: Uuid,
     command_line: String,
-    owner: String,
-    child: Option<Child>,
-    status: JobStatus,
+    acl: Arc<JobAcl>,
+    status: watch::Receiver<JobStatus>,
+    stop_request: Arc<Notify>,
+    publisher: EventPublisher,
+    stdin: Arc<Mutex<Option<Input>>>,
+    stdin_closing: watch::Sender<bool>,
+    stdin_closed: watch::Receiver<bool>,
+    terminal: Option<File>,
+    attachments: JobAttachments,
+    output: Arc<JobOutput>,
+    artifacts: Arc<JobArtifacts>,
+    workspace: JobWorkspace,
+    finished_at: Arc<sync::Mutex<Option<Instant>>>,
 }
 
 impl Job {
@@ -20,118 +63,571 @@ impl Job {
     /// * `owner` - String id of the owner of the job.  It is used for authorizing operations.
     /// * `command_line` - Command line that will be executed in this job.
     pub fn new(id: Uuid, owner: &str, command_line: &str) -> Result<Job, JobError> {
-        let job = Job {
+        Self::with_options(id, owner, command_line, JobOptions::default())
+    }
+
+    /// Creates a new `Job` like `new`, but using the given options.
+    ///
+    /// * `id` - UUID that will be assigned to the `Job`.
+    /// * `owner` - String id of the owner of the job.  It is used for authorizing operations.
+    /// * `command_line` - Command line that will be executed in this job.
+    /// * `options` - Optional settings of the job, like its input.
+    pub fn with_options(
+        id: Uuid,
+        owner: &str,
+        command_line: &str,
+        options: JobOptions,
+    ) -> Result<Job, JobError> {
+        let (events, _) = broadcast::channel(1);
+        let workspace =
+            JobWorkspace::create(id, options.workspace.as_ref(), options.credentials.as_ref())?;
+        match Self::with_events(id, owner, command_line, options, workspace.clone(), events) {
+            Ok(job) => Ok(job),
+            Err(err) => {
+                // The workspace is never used if the job can't be started.
+                workspace.discard();
+                Err(err)
+            }
+        }
+    }
+
+    // Creates a new `Job` that runs in the given workspace and
+    // publishes its events using the given sender.  Used by the
+    // `JobManager` to create the workspace out of the async tasks and
+    // to share the channel among jobs.  The caller must discard the
+    // workspace if the job can't be started.
+    pub(crate) fn with_events(
+        id: Uuid,
+        owner: &str,
+        command_line: &str,
+        options: JobOptions,
+        workspace: JobWorkspace,
+        events: broadcast::Sender<PublishedEvent>,
+    ) -> Result<Job, JobError> {
+        let acl = Arc::new(JobAcl::new(owner, options.team.clone()));
+        let publisher = EventPublisher::new(id, Arc::clone(&acl), events);
+        let mut command = Self::command(command_line, &options)?;
+        let pty = options.terminal.map(Pty::open).transpose()?;
+        if let Some(ref pty) = pty {
+            pty.attach(&mut command)?;
+        }
+        options.hardening.apply(&mut command);
+        JobArtifacts::validate(&options.artifacts, workspace.is_temporary())?;
+        let stop_request = Arc::new(Notify::new());
+        let output = Arc::new(JobOutput::create(
+            format!("{}.txt", id),
+            options.output_limit,
+            options.output_compression,
+            Arc::clone(&stop_request),
+        )?);
+        command.current_dir(workspace.path());
+        let artifacts = Arc::new(JobArtifacts::new(
+            options.artifacts.clone(),
+            workspace.path().to_path_buf(),
+            format!("{}.artifacts", id).into(),
+            options.credentials.clone(),
+            options.artifacts_max_bytes,
+        ));
+        let mut child = command.spawn()?;
+        // The copies of the slave side of the terminal must be closed,
+        // so reading the master side ends when the process exits.
+        drop(command);
+        publisher.started();
+        let attachments = JobAttachments::new();
+        let (input, copiers, terminal) = match pty {
+            Some(pty) => {
+                let master = pty.into_master();
+                let input: Input = Box::new(tokio::fs::File::from_std(master.try_clone()?));
+                let copiers = vec![Self::spawn_copier(
+                    Some(tokio::fs::File::from_std(master.try_clone()?)),
+                    Arc::clone(&output),
+                    OutputStream::Stdout,
+                    publisher.clone(),
+                    Some(attachments.clone()),
+                )];
+                (Some(input), copiers, Some(master))
+            }
+            None => {
+                let input = child.stdin.take().map(|stdin| Box::new(stdin) as Input);
+                let copiers = vec![
+                    Self::spawn_copier(
+                        child.stdout.take(),
+                        Arc::clone(&output),
+                        OutputStream::Stdout,
+                        publisher.clone(),
+                        None,
+                    ),
+                    Self::spawn_copier(
+                        child.stderr.take(),
+                        Arc::clone(&output),
+                        OutputStream::Stderr,
+                        publisher.clone(),
+                        None,
+                    ),
+                ];
+                (input, copiers, None)
+            }
+        };
+        let (stdin_closing, stdin_closed) = watch::channel(false);
+        let stdin = Self::spawn_feeder(input, options, stdin_closed.clone());
+        let (status_sender, status) = watch::channel(JobStatus::InProgress);
+        let finished_at = Arc::new(sync::Mutex::new(None));
+        let monitored_stop_request = Arc::clone(&stop_request);
+        let finished_output = Arc::clone(&output);
+        let finished_artifacts = Arc::clone(&artifacts);
+        let finished_publisher = publisher.clone();
+        let finished = Arc::clone(&finished_at);
+        tokio::spawn(async move {
+            let status = Self::monitor(child, copiers, monitored_stop_request).await;
+            Self::finish(
+                status,
+                status_sender,
+                finished_output,
+                finished_artifacts,
+                finished,
+                finished_publisher,
+            )
+            .await;
+        });
+
+        Ok(Job {
             id,
             command_line: command_line.to_string(),
-            owner: owner.to_string(),
-            child: None,
-            status: JobStatus::InProgress,
-        };
-        job.start()
+            acl,
+            status,
+            stop_request,
+            publisher,
+            stdin,
+            stdin_closing,
+            stdin_closed,
+            terminal,
+            attachments,
+            output,
+            artifacts,
+            workspace,
+            finished_at,
+        })
     }
 
-    // Start the job in a different process. This is a private
-    // function because `Job`s are immediatelly started from `new()`
-    fn start(mut self) -> Result<Job, JobError> {
+    // Prepare the command that will be run in a different process
+    // with its outputs redirected to pipes.  Its input is only
+    // redirected to a pipe if the options require it.  It runs as the
+    // user of the options, if any.
+    fn command(command_line: &str, options: &JobOptions) -> Result<Command, JobError> {
         // TODO: This doesn't take into account quotes.
-        let mut parts = self.command_line.split_whitespace();
-        let command = parts
+        let mut parts = command_line.split_whitespace();
+        let program = parts
             .next()
-            .ok_or_else(|| JobError::InvalidCommand(self.command_line.to_string()))?;
+            .ok_or_else(|| JobError::InvalidCommand(command_line.to_string()))?;
         let args = parts;
-        let filename = format!("{}.txt", self.id);
-        let output = File::create(filename)?;
-        let error = output.try_clone()?;
-        self.child = Some(
-            Command::new(command)
-                .args(args)
-                .stdout(Stdio::from(output))
-                .stderr(Stdio::from(error))
-                .spawn()?,
-        );
+        let mut command = Command::new(program);
+        let stdin = if options.has_stdin() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        command
+            .args(args)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(ref credentials) = options.credentials {
+            credentials.apply(&mut command);
+        }
 
-        Ok(self)
+        Ok(command)
     }
 
-    /// Return the status of job.
-    ///
-    /// * `as_user` - Perform this operation for this user id.  It
-    /// must match the onwer or it will return a `Unauthorized` error.
-    pub fn status(&mut self, as_user: &str) -> Result<JobStatus, JobError> {
-        if as_user != self.owner {
-            return Err(JobError::Unauthorized);
+    // Write the initial content to the stdin of the process and keep
+    // the pipe for later writes if requested.  The lock is taken
+    // before spawning the task, so later writes can't overtake the
+    // initial content.  Closing the stdin interrupts the initial write.
+    fn spawn_feeder(
+        stdin: Option<Input>,
+        options: JobOptions,
+        closed: watch::Receiver<bool>,
+    ) -> Arc<Mutex<Option<Input>>> {
+        let shared_stdin = Arc::new(Mutex::new(None));
+        let mut guard = Arc::clone(&shared_stdin)
+            .try_lock_owned()
+            .expect("New mutex can't be locked");
+        tokio::spawn(async move {
+            let mut stdin = match stdin {
+                Some(stdin) => stdin,
+                None => return,
+            };
+            if let Some(content) = options.stdin {
+                let written = tokio::select! {
+                    written = stdin.write_all(&content) => written.is_ok(),
+                    _ = Self::closing(closed) => false,
+                };
+                if !written {
+                    return;
+                }
+            }
+            if options.keep_stdin_open || options.terminal.is_some() {
+                *guard = Some(stdin);
+            }
+        });
+        shared_stdin
+    }
+
+    // Wait until the stdin is requested to be closed or the job is
+    // dropped.
+    async fn closing(mut closed: watch::Receiver<bool>) {
+        while !*closed.borrow() {
+            if closed.changed().await.is_err() {
+                return;
+            }
         }
-        // Refresh status only if InProgress
-        if matches!(self.status, JobStatus::InProgress) {
-            if let Some(ref mut child) = self.child {
-                match child.try_wait() {
-                    Ok(None) => {}
-                    Ok(Some(status)) => {
-                        self.status = JobStatus::Done(status);
-                        self.child = None;
-                    }
-                    Err(_) => {
-                        self.status = JobStatus::Failed;
-                        self.child = None;
+    }
+
+    // Copy one of the outputs of the process to the output file,
+    // publishing every chunk as an event.  The output of a terminal is
+    // also sent to its attachments, which are ended with it.
+    fn spawn_copier<R>(
+        source: Option<R>,
+        destination: Arc<JobOutput>,
+        stream: OutputStream,
+        publisher: EventPublisher,
+        attachments: Option<JobAttachments>,
+    ) -> JoinHandle<()>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        tokio::spawn(async move {
+            let mut source = match source {
+                Some(source) => source,
+                None => return,
+            };
+            let mut buffer = vec![0; OUTPUT_CHUNK_SIZE];
+            loop {
+                match source.read(&mut buffer).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(read) => {
+                        if destination.write(&buffer[..read]).await.is_err() {
+                            break;
+                        }
+                        publisher.output(stream, &buffer[..read]);
+                        if let Some(ref attachments) = attachments {
+                            attachments.send(&buffer[..read]);
+                        }
                     }
                 }
             }
+            if let Some(attachments) = attachments {
+                attachments.close();
+            }
+        })
+    }
+
+    // Wait for the child process to finish and for all its output to
+    // be stored.  The process is killed if a stop is requested before.
+    async fn monitor(
+        mut child: Child,
+        copiers: Vec<JoinHandle<()>>,
+        stop_request: Arc<Notify>,
+    ) -> JobStatus {
+        let stop_requested = tokio::select! {
+            _ = child.wait() => false,
+            _ = stop_request.notified() => true,
+        };
+        let status = if stop_requested && child.start_kill().is_err() {
+            JobStatus::Failed
+        } else {
+            match child.wait().await {
+                Ok(exit_status) => JobStatus::Done(exit_status),
+                Err(_) => JobStatus::Failed,
+            }
+        };
+        for copier in copiers {
+            let _ = copier.await;
         }
+        status
+    }
 
-        Ok(self.status.clone())
+    // Store the files produced by the job and publish its final
+    // status.
+    async fn finish(
+        status: JobStatus,
+        status_sender: watch::Sender<JobStatus>,
+        output: Arc<JobOutput>,
+        artifacts: Arc<JobArtifacts>,
+        finished_at: Arc<sync::Mutex<Option<Instant>>>,
+        publisher: EventPublisher,
+    ) {
+        // The uncompressed output is kept if it can't be compressed.
+        let _ = output.compress().await;
+        // The artifacts that can't be copied are missing from the list.
+        let _ = artifacts.collect().await;
+        *finished_at.lock().unwrap() = Some(Instant::now());
+        // Nobody may be listening anymore if the job has been dropped.
+        let _ = status_sender.send(status.clone());
+        publisher.finished(status);
     }
 
-    /// Return the filename that contains the output of the job.
+    /// Return the status of job.
     ///
-    /// * `as_user` - Perform this operation for this user id.  It
-    /// must match the onwer or it will return a `Unauthorized` error.
-    pub fn output(&mut self, as_user: &str) -> Result<String, JobError> {
-        if as_user != self.owner {
-            return Err(JobError::Unauthorized);
-        }
-        Ok(format!("{}.txt", self.id))
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub async fn status(&self, as_user: Principal<'_>) -> Result<JobStatus, JobError> {
+        self.authorize(as_user, Permission::Read)?;
+
+        Ok(self.status.borrow().clone())
     }
 
-    /// Stop the job using a kill signal.
+    /// Return a handle to wait for the job to finish.
     ///
-    /// * `as_user` - Perform this operation for this user id.  It
-    /// must match the onwer or it will return a `Unauthorized` error.
-    pub fn stop(&mut self, as_user: &str) -> Result<(), JobError> {
-        if as_user != self.owner {
-            return Err(JobError::Unauthorized);
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub fn completion(&self, as_user: Principal<'_>) -> Result<JobCompletion, JobError> {
+        self.authorize(as_user, Permission::Read)?;
+        Ok(JobCompletion::new(self.status.clone()))
+    }
+
+    /// Return the file that contains the output of the job.  It is
+    /// compressed once the job has finished if the options of the job
+    /// require it.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub async fn output(&self, as_user: Principal<'_>) -> Result<OutputFile, JobError> {
+        self.authorize(as_user, Permission::Read)?;
+        Ok(self.output.file().await)
+    }
+
+    /// Discard the output stored so far.  The output produced
+    /// afterwards by a job in progress is still stored.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub async fn purge_output(&self, as_user: Principal<'_>) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::PurgeOutput)?;
+        self.output.truncate().await?;
+        Ok(())
+    }
+
+    /// Return the artifacts collected when the job finished.  The
+    /// list is empty while the job is in progress.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub fn artifacts(&self, as_user: Principal<'_>) -> Result<Vec<Artifact>, JobError> {
+        self.authorize(as_user, Permission::Read)?;
+        Ok(self.artifacts.list())
+    }
+
+    /// Return the path of the stored copy of an artifact.  It returns
+    /// a `NotFound` error if there is no artifact with that name.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    /// * `name` - Name of the artifact, as returned by `artifacts`.
+    pub fn artifact(&self, as_user: Principal<'_>, name: &str) -> Result<String, JobError> {
+        self.authorize(as_user, Permission::Read)?;
+        self.artifacts.path(name).ok_or(JobError::NotFound)
+    }
+
+    // Instant when the job finished and all its output was stored.
+    pub(crate) fn finished_at(&self) -> Option<Instant> {
+        *self.finished_at.lock().unwrap()
+    }
+
+    // Delete the output file, the artifacts and the workspace of the
+    // job and announce its removal.  All of them are deleted even if
+    // one fails, and the first error is returned.
+    pub(crate) async fn remove_files(&self) -> io::Result<()> {
+        self.publisher.removed();
+        let output = self.output.remove().await;
+        let artifacts = self.artifacts.remove().await;
+        let workspace = self.workspace.remove().await;
+        output.and(artifacts).and(workspace)
+    }
+
+    /// Stop the job using a kill signal.  The signal is sent
+    /// asynchronously, so the status of the job will change once the
+    /// process has been killed.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub async fn stop(&self, as_user: Principal<'_>) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::Stop)?;
+        if matches!(*self.status.borrow(), JobStatus::InProgress) {
+            self.stop_request.notify_one();
+            self.publisher.stopped();
         }
-        if matches!(self.status, JobStatus::InProgress) {
-            if let Some(ref mut child) = self.child {
-                match child.start_kill() {
-                    Ok(_) => {} //self.status = JobStatus::Stopped,
-                    Err(_) => {
-                        self.status = JobStatus::Failed;
-                        self.child = None;
-                    }
-                }
+        Ok(())
+    }
+
+    /// Write data to the stdin of the job.  It is only possible if
+    /// the job has been created keeping its stdin open.
+    ///
+    /// The write fails with a `StdinTimeout` error if the process
+    /// doesn't read the data in time.  The stdin is closed then,
+    /// because part of the data may have been written.  Closing the
+    /// stdin interrupts the write with a `StdinClosed` error.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    /// * `data` - Bytes that will be written to the stdin.
+    pub async fn write_stdin(&self, as_user: Principal<'_>, data: &[u8]) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::Interact)?;
+        let closing = Self::closing(self.stdin_closed.clone());
+        let mut stdin = time::timeout(STDIN_WRITE_TIMEOUT, self.stdin.lock())
+            .await
+            .map_err(|_| JobError::StdinTimeout)?;
+        let pipe = stdin.as_mut().ok_or(JobError::StdinClosed)?;
+        let write = async {
+            pipe.write_all(data).await?;
+            pipe.flush().await
+        };
+        let written = tokio::select! {
+            written = time::timeout(STDIN_WRITE_TIMEOUT, write) => Some(written),
+            _ = closing => None,
+        };
+        match written {
+            Some(Ok(Ok(()))) => Ok(()),
+            Some(Err(_)) => {
+                *stdin = None;
+                Err(JobError::StdinTimeout)
+            }
+            // The process may have exited or closed its input.
+            Some(Ok(Err(_))) | None => {
+                *stdin = None;
+                Err(JobError::StdinClosed)
             }
         }
+    }
+
+    /// Close the stdin of the job, so it receives an end of file.
+    /// Closing it more than once has no effect.  A write in progress
+    /// is interrupted.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub async fn close_stdin(&self, as_user: Principal<'_>) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::Interact)?;
+        // The job holds a receiver, so it can't fail.
+        let _ = self.stdin_closing.send(true);
+        self.stdin.lock().await.take();
+        Ok(())
+    }
+
+    /// Change the size of the terminal of the job.  It is only
+    /// possible if the job has been created with a terminal.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    /// * `size` - New size of the terminal.
+    pub async fn resize_terminal(
+        &self,
+        as_user: Principal<'_>,
+        size: TerminalSize,
+    ) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::Interact)?;
+        let terminal = self.terminal.as_ref().ok_or(JobError::NoTerminal)?;
+        Pty::resize(terminal, size)?;
         Ok(())
     }
 
+    /// Attach to the terminal of the job to receive its output as it
+    /// is produced.  It is only possible if the job has been created
+    /// with a terminal.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub fn attach(&self, as_user: Principal<'_>) -> Result<JobAttachment, JobError> {
+        self.authorize(as_user, Permission::Interact)?;
+        if self.terminal.is_none() {
+            return Err(JobError::NoTerminal);
+        }
+        Ok(self.attachments.attach())
+    }
+
+    /// Get a summary of the job data and its current status.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    pub async fn info(&self, as_user: Principal<'_>) -> Result<JobInfo, JobError> {
+        let status = self.status(as_user).await?;
+        Ok(JobInfo {
+            id: self.id,
+            owner: self.acl.owner().to_string(),
+            team: self.acl.team().cloned(),
+            shares: self.acl.shares(),
+            command_line: self.command_line.clone(),
+            status,
+        })
+    }
+
+    /// Share the job with a user or a team.  It replaces the access
+    /// previously granted to the same grantee.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    /// * `share` - Grantee and the access that is granted to it.
+    pub fn share(&self, as_user: Principal<'_>, share: Share) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::Share)?;
+        self.acl.share(share);
+        Ok(())
+    }
+
+    /// Stop sharing the job with a user or a team.  It has no effect if
+    /// the job isn't shared with it.
+    ///
+    /// * `as_user` - Perform this operation for this user.  Its role
+    /// must allow it or it will return a `Unauthorized` error.
+    /// * `grantee` - User or team that loses its access.
+    pub fn unshare(&self, as_user: Principal<'_>, grantee: &Grantee) -> Result<(), JobError> {
+        self.authorize(as_user, Permission::Share)?;
+        self.acl.unshare(grantee);
+        Ok(())
+    }
+
+    /// Check that the operation can be performed by the given user.
+    /// It depends on its role and on whether it owns the job.  The
+    /// members of the team of the job can read and stop it, and a job
+    /// shared with the user allows the operations of its access, as
+    /// long as its role would allow them on its own jobs.
+    ///
+    /// * `as_user` - User that will perform the operation.
+    /// * `permission` - Operation that will be performed.
+    pub fn authorize(
+        &self,
+        as_user: Principal<'_>,
+        permission: Permission,
+    ) -> Result<(), JobError> {
+        self.acl.authorize(as_user, permission)
+    }
+
     /// Get the value of the job id. This is a uuid.
     pub fn get_id(&self) -> Uuid {
         self.id
     }
+
+    /// Get the command line that is executed by this job.
+    pub fn get_command_line(&self) -> &str {
+        &self.command_line
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{thread, time::Duration};
+    use std::{os::unix::io::AsRawFd, time::Duration};
+    use tokio::time;
 
     use super::*;
+    use crate::{
+        Access, Compression, Credentials, HardeningProfile, OutputLimit, OutputLimitAction, Role,
+        Workspace,
+    };
 
-    const OWNER_1: &str = "owner 1";
-    const OWNER_2: &str = "owner 2";
+    const OWNER_1: Principal = Principal::submitter("owner 1");
+    const OWNER_2: Principal = Principal::submitter("owner 2");
 
     #[tokio::test]
     async fn new_produces_valid_job() {
-        let job = Job::new(Uuid::new_v4(), OWNER_1, "ls");
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "ls");
 
         assert!(job.is_ok())
     }
@@ -139,7 +635,7 @@ mod tests {
     #[tokio::test]
     async fn new_preserves_id() {
         let id = Uuid::new_v4();
-        let job = Job::new(id, OWNER_1, "ls").unwrap();
+        let job = Job::new(id, OWNER_1.id, "ls").unwrap();
 
         assert_eq!(id, job.get_id());
     }
@@ -147,7 +643,7 @@ mod tests {
     #[tokio::test]
     async fn new_produces_error_if_command_is_empty() {
         let command = "  ";
-        let job = Job::new(Uuid::new_v4(), OWNER_1, command);
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, command);
 
         assert!(job.is_err());
         assert!(
@@ -157,7 +653,7 @@ mod tests {
 
     #[tokio::test]
     async fn non_existing_command_returns_failure() {
-        let job = Job::new(Uuid::new_v4(), OWNER_1, "mxyzptlk -s");
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "mxyzptlk -s");
 
         assert!(job.is_err());
         assert!(matches!(job.err(), Some(JobError::CommandNotFound)));
@@ -165,32 +661,32 @@ mod tests {
 
     #[tokio::test]
     async fn command_status_is_only_available_to_owner() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "true").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "true").unwrap();
 
-        assert!(job.status(OWNER_2).is_err());
+        assert!(job.status(OWNER_2).await.is_err());
         assert!(matches!(
-            job.status(OWNER_2).err(),
+            job.status(OWNER_2).await.err(),
             Some(JobError::Unauthorized)
         ));
     }
 
     #[tokio::test]
     async fn valid_command_initial_status_is_in_progress() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "sleep 1").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 1").unwrap();
 
         assert!(matches!(
-            job.status(OWNER_1).ok(),
+            job.status(OWNER_1).await.ok(),
             Some(JobStatus::InProgress)
         ));
     }
 
     #[tokio::test]
     async fn command_output_filename_is_only_available_to_owner() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "true").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "true").unwrap();
 
-        assert!(job.output(OWNER_2).is_err());
+        assert!(job.output(OWNER_2).await.is_err());
         assert!(matches!(
-            job.output(OWNER_2).err(),
+            job.output(OWNER_2).await.err(),
             Some(JobError::Unauthorized)
         ));
     }
@@ -198,57 +694,157 @@ mod tests {
     #[tokio::test]
     async fn owner_can_retrieve_output_filename() {
         let id = Uuid::new_v4();
-        let mut job = Job::new(id, OWNER_1, "ls").unwrap();
+        let job = Job::new(id, OWNER_1.id, "ls").unwrap();
 
         let filename = format!("{}.txt", id);
-        assert!(matches!(job.output(OWNER_1), Ok(output) if output == filename));
+        assert!(matches!(job.output(OWNER_1).await, Ok(output) if output.path == filename));
     }
 
     #[tokio::test]
     async fn valid_command_status_is_done_successful() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "true").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "true").unwrap();
 
-        while matches!(job.status(OWNER_1).ok(), Some(JobStatus::InProgress)) {
-            thread::sleep(Duration::from_millis(50));
+        while matches!(job.status(OWNER_1).await.ok(), Some(JobStatus::InProgress)) {
+            time::sleep(Duration::from_millis(50)).await;
         }
 
         assert!(
-            matches!(job.status(OWNER_1).ok(), Some(JobStatus::Done(ref status)) if status.success())
+            matches!(job.status(OWNER_1).await.ok(), Some(JobStatus::Done(ref status)) if status.success())
         );
     }
 
     #[tokio::test]
     async fn failing_command_status_is_done_failed() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "false").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "false").unwrap();
 
-        while matches!(job.status(OWNER_1).ok(), Some(JobStatus::InProgress)) {
-            thread::sleep(Duration::from_millis(50));
+        while matches!(job.status(OWNER_1).await.ok(), Some(JobStatus::InProgress)) {
+            time::sleep(Duration::from_millis(50)).await;
         }
 
         assert!(
-            matches!(job.status(OWNER_1).ok(), Some(JobStatus::Done(ref status)) if !status.success())
+            matches!(job.status(OWNER_1).await.ok(), Some(JobStatus::Done(ref status)) if !status.success())
         );
     }
 
     #[tokio::test]
     async fn command_stop_is_only_available_to_owner() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "true").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "true").unwrap();
+
+        assert!(job.stop(OWNER_2).await.is_err());
+        assert!(matches!(
+            job.stop(OWNER_2).await.err(),
+            Some(JobError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn role_authorizes_operations_on_jobs_of_others() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 1").unwrap();
+        let viewer = Principal {
+            id: "viewer",
+            role: Role::Viewer,
+            teams: &[],
+        };
+        let admin = Principal {
+            id: "admin",
+            role: Role::Admin,
+            teams: &[],
+        };
+
+        assert!(job.status(viewer).await.is_ok());
+        assert!(matches!(
+            job.stop(viewer).await.err(),
+            Some(JobError::Unauthorized)
+        ));
+        job.stop(admin).await.unwrap();
+        let status = job.completion(admin).unwrap().wait().await;
+        assert!(matches!(status, JobStatus::Done(ref status) if !status.success()));
+    }
+
+    #[tokio::test]
+    async fn shares_grant_their_access_within_the_role() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 1").unwrap();
+        let teams = vec!["ops".to_string()];
+        let member = Principal {
+            id: "member",
+            role: Role::Submitter,
+            teams: &teams,
+        };
+        let viewer = Principal {
+            id: "viewer",
+            role: Role::Viewer,
+            teams: &teams,
+        };
+        job.share(
+            OWNER_1,
+            Share {
+                grantee: Grantee::User(OWNER_2.id.to_string()),
+                access: Access::Read,
+            },
+        )
+        .unwrap();
+        job.share(
+            OWNER_1,
+            Share {
+                grantee: Grantee::Team("ops".to_string()),
+                access: Access::Stop,
+            },
+        )
+        .unwrap();
 
-        assert!(job.stop(OWNER_2).is_err());
+        assert!(job.status(OWNER_2).await.is_ok());
         assert!(matches!(
-            job.stop(OWNER_2).err(),
+            job.stop(OWNER_2).await.err(),
             Some(JobError::Unauthorized)
         ));
+        assert!(matches!(
+            job.stop(viewer).await.err(),
+            Some(JobError::Unauthorized)
+        ));
+        assert!(matches!(
+            job.purge_output(member).await.err(),
+            Some(JobError::Unauthorized)
+        ));
+        job.stop(member).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn team_members_can_read_and_stop_the_job() {
+        let options = JobOptions {
+            team: Some("ops".to_string()),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(Uuid::new_v4(), OWNER_1.id, "sleep 1", options).unwrap();
+        let teams = vec!["ops".to_string()];
+        let member = Principal {
+            id: "member",
+            role: Role::Submitter,
+            teams: &teams,
+        };
+
+        assert!(job.status(OWNER_2).await.is_err());
+        assert!(job.status(member).await.is_ok());
+        for permission in &[
+            Permission::Interact,
+            Permission::Share,
+            Permission::PurgeOutput,
+        ] {
+            assert!(matches!(
+                job.authorize(member, *permission),
+                Err(JobError::Unauthorized)
+            ));
+        }
+        job.stop(member).await.unwrap();
     }
 
     #[tokio::test]
     async fn long_running_command_can_be_stopped() {
-        let mut job = Job::new(Uuid::new_v4(), OWNER_1, "sleep 100").unwrap();
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 100").unwrap();
 
         let mut i = 0;
-        while matches!(job.status(OWNER_1).ok(), Some(JobStatus::InProgress)) {
-            thread::sleep(Duration::from_millis(50));
-            if i == 7 && job.stop(OWNER_1).is_err() {
+        while matches!(job.status(OWNER_1).await.ok(), Some(JobStatus::InProgress)) {
+            time::sleep(Duration::from_millis(50)).await;
+            if i == 7 && job.stop(OWNER_1).await.is_err() {
                 break;
             }
             i += 1;
@@ -256,7 +852,446 @@ mod tests {
 
         assert!(i < 10);
         assert!(
-            matches!(job.status(OWNER_1).ok(), Some(JobStatus::Done(ref status)) if !status.success())
+            matches!(job.status(OWNER_1).await.ok(), Some(JobStatus::Done(ref status)) if !status.success())
+        );
+    }
+
+    #[tokio::test]
+    async fn completion_returns_final_status() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 0.2").unwrap();
+
+        let status = job.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(ref status) if status.success()));
+    }
+
+    #[tokio::test]
+    async fn command_completion_is_only_available_to_owner() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "true").unwrap();
+
+        assert!(matches!(
+            job.completion(OWNER_2).err(),
+            Some(JobError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn initial_stdin_is_written_to_the_process() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            stdin: Some(b"hello stdin".to_vec()),
+            keep_stdin_open: false,
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "cat", options).unwrap();
+
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert_eq!("hello stdin", output);
+    }
+
+    #[tokio::test]
+    async fn open_stdin_can_be_written_and_closed() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            stdin: Some(b"first ".to_vec()),
+            keep_stdin_open: true,
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "cat", options).unwrap();
+
+        job.write_stdin(OWNER_1, b"second").await.unwrap();
+        job.close_stdin(OWNER_1).await.unwrap();
+        let status = job.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(ref status) if status.success()));
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert_eq!("first second", output);
+    }
+
+    #[tokio::test]
+    async fn closed_stdin_cannot_be_written() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "cat").unwrap();
+
+        assert!(matches!(
+            job.write_stdin(OWNER_1, b"data").await.err(),
+            Some(JobError::StdinClosed)
+        ));
+    }
+
+    #[tokio::test]
+    async fn closing_stdin_interrupts_a_blocked_write() {
+        let options = JobOptions {
+            stdin: None,
+            keep_stdin_open: true,
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(Uuid::new_v4(), OWNER_1.id, "sleep 100", options).unwrap();
+        // Larger than the buffer of the pipe, which isn't read.
+        let data = vec![b'x'; 1024 * 1024];
+        let close = async {
+            time::sleep(Duration::from_millis(100)).await;
+            job.close_stdin(OWNER_1).await
+        };
+
+        let (written, closed) = time::timeout(Duration::from_secs(5), async {
+            tokio::join!(job.write_stdin(OWNER_1, &data), close)
+        })
+        .await
+        .unwrap();
+
+        assert!(matches!(written, Err(JobError::StdinClosed)));
+        assert!(closed.is_ok());
+        job.stop(OWNER_1).await.unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+    }
+
+    #[tokio::test]
+    async fn stdin_is_only_available_to_owner() {
+        let options = JobOptions {
+            stdin: None,
+            keep_stdin_open: true,
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(Uuid::new_v4(), OWNER_1.id, "cat", options).unwrap();
+
+        assert!(matches!(
+            job.write_stdin(OWNER_2, b"data").await.err(),
+            Some(JobError::Unauthorized)
+        ));
+        assert!(matches!(
+            job.close_stdin(OWNER_2).await.err(),
+            Some(JobError::Unauthorized)
+        ));
+        job.close_stdin(OWNER_1).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn job_with_terminal_runs_in_a_tty() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            terminal: Some(TerminalSize {
+                rows: 30,
+                cols: 100,
+            }),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "stty size", options).unwrap();
+
+        let status = job.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(ref status) if status.success()));
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert_eq!("30 100", output.trim());
+    }
+
+    #[tokio::test]
+    async fn terminal_of_a_job_can_be_written_and_resized() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            terminal: Some(TerminalSize::default()),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "sh", options).unwrap();
+
+        job.resize_terminal(
+            OWNER_1,
+            TerminalSize {
+                rows: 50,
+                cols: 132,
+            },
+        )
+        .await
+        .unwrap();
+        job.write_stdin(OWNER_1, b"stty size; exit 3\n")
+            .await
+            .unwrap();
+        let status = job.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(ref status) if status.code() == Some(3)));
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert!(output.contains("50 132"));
+    }
+
+    #[tokio::test]
+    async fn terminal_of_a_job_is_not_inherited_by_other_jobs() {
+        let options = JobOptions {
+            terminal: Some(TerminalSize::default()),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(Uuid::new_v4(), OWNER_1.id, "cat", options).unwrap();
+        let master = job.terminal.as_ref().unwrap().as_raw_fd();
+
+        let other = Job::new(
+            Uuid::new_v4(),
+            OWNER_1.id,
+            &format!("ls /proc/self/fd/{}", master),
+        )
+        .unwrap();
+        let status = other.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(ref status) if !status.success()));
+        job.stop(OWNER_1).await.unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+    }
+
+    #[tokio::test]
+    async fn job_without_terminal_cannot_be_resized() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 1").unwrap();
+
+        assert!(matches!(
+            job.resize_terminal(OWNER_1, TerminalSize::default())
+                .await
+                .err(),
+            Some(JobError::NoTerminal)
+        ));
+    }
+
+    #[tokio::test]
+    async fn attachments_receive_the_output_of_the_terminal() {
+        let options = JobOptions {
+            terminal: Some(TerminalSize::default()),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(Uuid::new_v4(), OWNER_1.id, "cat", options).unwrap();
+        let mut attachment = job.attach(OWNER_1).unwrap();
+
+        job.write_stdin(OWNER_1, b"hello\n").await.unwrap();
+        job.stop(OWNER_1).await.unwrap();
+
+        let mut output = Vec::new();
+        while let Some(data) = attachment.next().await {
+            output.extend(data);
+        }
+        assert!(String::from_utf8_lossy(&output).contains("hello"));
+        assert!(!attachment.lagged());
+        job.completion(OWNER_1).unwrap().wait().await;
+    }
+
+    #[tokio::test]
+    async fn job_without_terminal_cannot_be_attached() {
+        let job = Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 1").unwrap();
+
+        assert!(matches!(
+            job.attach(OWNER_1).err(),
+            Some(JobError::NoTerminal)
+        ));
+        assert!(matches!(
+            job.attach(OWNER_2).err(),
+            Some(JobError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn job_is_stopped_when_output_reaches_limit() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            output_limit: Some(OutputLimit {
+                max_bytes: 1000,
+                action: OutputLimitAction::StopJob,
+            }),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "yes", options).unwrap();
+
+        let status = job.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(ref status) if !status.success()));
+        let output = std::fs::metadata(format!("{}.txt", id)).unwrap();
+        assert_eq!(1000, output.len());
+    }
+
+    #[tokio::test]
+    async fn output_of_finished_job_is_compressed() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            output_compression: Some(Compression::Zstd),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "echo compressed", options).unwrap();
+
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        let output = job.output(OWNER_1).await.unwrap();
+        assert_eq!(format!("{}.txt.zst", id), output.path);
+        assert_eq!(Some(Compression::Zstd), output.compression);
+    }
+
+    #[tokio::test]
+    async fn purged_output_is_empty() {
+        let id = Uuid::new_v4();
+        let job = Job::new(id, OWNER_1.id, "echo purge me").unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        job.purge_output(OWNER_1).await.unwrap();
+
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert!(output.is_empty());
+        assert!(matches!(
+            job.purge_output(OWNER_2).await.err(),
+            Some(JobError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn artifacts_are_collected_when_job_finishes() {
+        let id = Uuid::new_v4();
+        let name = format!("{}.artifact", id);
+        let options = JobOptions {
+            artifacts: vec![name.clone()],
+            workspace: Some(Workspace::Empty),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, &format!("touch {}", name), options).unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        let artifacts = job.artifacts(OWNER_1).unwrap();
+
+        assert_eq!(
+            vec![Artifact {
+                name: name.clone(),
+                size: 0
+            }],
+            artifacts
+        );
+        assert_eq!(
+            format!("{}.artifacts/{}", id, name),
+            job.artifact(OWNER_1, &name).unwrap()
+        );
+        assert!(matches!(
+            job.artifact(OWNER_1, "missing"),
+            Err(JobError::NotFound)
+        ));
+        job.remove_files().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn job_runs_in_its_workspace() {
+        let id = Uuid::new_v4();
+        let mut archive = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        header.set_cksum();
+        archive
+            .append_data(&mut header, "input.txt", &b"hello"[..])
+            .unwrap();
+        let options = JobOptions {
+            workspace: Some(Workspace::Tar(archive.into_inner().unwrap())),
+            artifacts: vec!["*.txt".to_string()],
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "cat input.txt", options).unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert_eq!("hello", output);
+        assert_eq!(
+            vec!["input.txt".to_string()],
+            job.artifacts(OWNER_1)
+                .unwrap()
+                .into_iter()
+                .map(|artifact| artifact.name)
+                .collect::<Vec<_>>()
         );
+        job.remove_files().await.unwrap();
+        assert!(!std::env::temp_dir().join(format!("worker-{}", id)).exists());
+    }
+
+    #[tokio::test]
+    async fn files_are_removed_even_if_the_output_is_missing() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            workspace: Some(Workspace::Empty),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "true", options).unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+        std::fs::remove_file(format!("{}.txt", id)).unwrap();
+
+        assert!(job.remove_files().await.is_err());
+        assert!(!std::env::temp_dir().join(format!("worker-{}", id)).exists());
+    }
+
+    #[tokio::test]
+    async fn job_runs_with_its_credentials() {
+        // Switching to the same user is allowed without being root.
+        let credentials = unsafe {
+            Credentials {
+                uid: libc::getuid(),
+                gid: libc::getgid(),
+                groups: Vec::new(),
+            }
+        };
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            workspace: Some(Workspace::Empty),
+            credentials: Some(credentials.clone()),
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(id, OWNER_1.id, "id -u", options).unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert_eq!(format!("{}\n", credentials.uid), output);
+        job.remove_files().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn strict_profile_limits_job() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            hardening: HardeningProfile::Strict,
+            ..JobOptions::default()
+        };
+        let job = Job::with_options(
+            id,
+            OWNER_1.id,
+            "cat /proc/self/status /proc/self/limits",
+            options,
+        )
+        .unwrap();
+        job.completion(OWNER_1).unwrap().wait().await;
+
+        let output = std::fs::read_to_string(format!("{}.txt", id)).unwrap();
+        assert!(output.contains("Seccomp:\t2"));
+        assert!(output
+            .lines()
+            .any(|line| line.starts_with("Max open files") && line.contains(" 256 ")));
+    }
+
+    #[tokio::test]
+    async fn denied_syscalls_fail() {
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            hardening: HardeningProfile::Default,
+            ..JobOptions::default()
+        };
+        // `unshare` is denied, so the command can't run.
+        let job = Job::with_options(id, OWNER_1.id, "unshare -U true", options).unwrap();
+
+        let status = job.completion(OWNER_1).unwrap().wait().await;
+
+        assert!(matches!(status, JobStatus::Done(exit_status) if !exit_status.success()));
+    }
+
+    #[tokio::test]
+    async fn status_can_be_queried_concurrently() {
+        let job = Arc::new(Job::new(Uuid::new_v4(), OWNER_1.id, "sleep 1").unwrap());
+
+        let queries: Vec<_> = (0..4)
+            .map(|_| {
+                let job = Arc::clone(&job);
+                tokio::spawn(async move { job.status(OWNER_1).await })
+            })
+            .collect();
+
+        for query in queries {
+            assert!(matches!(
+                query.await.unwrap().ok(),
+                Some(JobStatus::InProgress)
+            ));
+        }
     }
 }
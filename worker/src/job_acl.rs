@@ -0,0 +1,72 @@
+use crate::{Access, Grantee, JobError, Permission, Principal, Share};
+use std::sync;
+
+// Users that can access a job: its owner, its team and the grantees
+// of its shares.  It is shared by the job and its events, so both
+// are authorized in the same way.
+pub(crate) struct JobAcl {
+    owner: String,
+    team: Option<String>,
+    shares: sync::Mutex<Vec<Share>>,
+}
+
+impl JobAcl {
+    pub(crate) fn new(owner: &str, team: Option<String>) -> JobAcl {
+        JobAcl {
+            owner: owner.to_string(),
+            team,
+            shares: sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub(crate) fn team(&self) -> Option<&String> {
+        self.team.as_ref()
+    }
+
+    pub(crate) fn shares(&self) -> Vec<Share> {
+        self.shares.lock().unwrap().clone()
+    }
+
+    // It replaces the access previously granted to the same grantee.
+    pub(crate) fn share(&self, share: Share) {
+        let mut shares = self.shares.lock().unwrap();
+        shares.retain(|shared| shared.grantee != share.grantee);
+        shares.push(share);
+    }
+
+    pub(crate) fn unshare(&self, grantee: &Grantee) {
+        self.shares
+            .lock()
+            .unwrap()
+            .retain(|shared| shared.grantee != *grantee);
+    }
+
+    // See `Job::authorize`.
+    pub(crate) fn authorize(
+        &self,
+        as_user: Principal<'_>,
+        permission: Permission,
+    ) -> Result<(), JobError> {
+        if as_user.role.allows(permission, as_user.id == self.owner) {
+            return Ok(());
+        }
+        // The members of the team can read and stop the job, but only
+        // the owner acts as the user that runs it.
+        let member = matches!(&self.team, Some(team) if as_user.teams.contains(team))
+            && Access::Stop.grants(permission);
+        let shared = self
+            .shares
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|share| share.grantee.includes(as_user) && share.access.grants(permission));
+        if (member || shared) && as_user.role.allows(permission, true) {
+            return Ok(());
+        }
+        Err(JobError::Unauthorized)
+    }
+}
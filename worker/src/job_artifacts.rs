@@ -0,0 +1,354 @@
+use crate::{Artifact, Credentials, JobError};
+use glob::Pattern;
+use std::{
+    ffi::CString,
+    fs::{self, DirBuilder, OpenOptions},
+    io, mem,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{DirBuilderExt, OpenOptionsExt},
+        process::CommandExt,
+    },
+    path::{Component, Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+// Files produced by a job that are collected when it finishes.  They
+// are copied to a directory of the job, so they are kept even if the
+// working directory changes.  They are read with the credentials of
+// the job, so it can't collect files that it couldn't read.  Only the
+// user of the server can read the copies, and their total size can be
+// limited.
+pub(crate) struct JobArtifacts {
+    patterns: Vec<String>,
+    working_dir: PathBuf,
+    directory: PathBuf,
+    credentials: Option<Credentials>,
+    max_bytes: Option<u64>,
+    collected: Mutex<Vec<Artifact>>,
+}
+
+impl JobArtifacts {
+    // Check that the glob patterns are valid.  They must be relative
+    // to the working directory and they can't refer to its parent.
+    // They are only collected from the temporary workspace of a job,
+    // never from the working directory of the server.
+    pub(crate) fn validate(patterns: &[String], has_workspace: bool) -> Result<(), JobError> {
+        if !patterns.is_empty() && !has_workspace {
+            return Err(JobError::ArtifactsWithoutWorkspace);
+        }
+        for pattern in patterns {
+            let path = Path::new(pattern);
+            let escapes = path
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+            if escapes || Pattern::new(pattern).is_err() {
+                return Err(JobError::InvalidArtifactPattern(pattern.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // The patterns must have been validated.
+    pub(crate) fn new(
+        patterns: Vec<String>,
+        working_dir: PathBuf,
+        directory: PathBuf,
+        credentials: Option<Credentials>,
+        max_bytes: Option<u64>,
+    ) -> JobArtifacts {
+        JobArtifacts {
+            patterns,
+            working_dir,
+            directory,
+            credentials,
+            max_bytes,
+            collected: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Copy the files that match the patterns to the directory of the
+    // artifacts.
+    pub(crate) async fn collect(&self) -> io::Result<()> {
+        if self.patterns.is_empty() {
+            return Ok(());
+        }
+        let patterns = self.patterns.clone();
+        let working_dir = self.working_dir.clone();
+        let directory = self.directory.clone();
+        let credentials = self.credentials.clone();
+        let max_bytes = self.max_bytes;
+        let artifacts = tokio::task::spawn_blocking(move || {
+            Self::copy_matches(
+                &patterns,
+                &working_dir,
+                &directory,
+                credentials.as_ref(),
+                max_bytes,
+            )
+        })
+        .await
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))??;
+        *self.collected.lock().unwrap() = artifacts;
+        Ok(())
+    }
+
+    // Copy the regular files that match the patterns.  Symbolic links,
+    // the files that can't be read with the credentials of the job and
+    // those that don't fit in the maximum size are skipped.
+    fn copy_matches(
+        patterns: &[String],
+        working_dir: &Path,
+        directory: &Path,
+        credentials: Option<&Credentials>,
+        max_bytes: Option<u64>,
+    ) -> io::Result<Vec<Artifact>> {
+        let mut artifacts: Vec<Artifact> = Vec::new();
+        let mut remaining = max_bytes;
+        for pattern in patterns {
+            let full_pattern = working_dir.join(pattern);
+            let paths = glob::glob(&full_pattern.to_string_lossy())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            for path in paths.filter_map(Result::ok) {
+                let is_file = fs::symlink_metadata(&path)
+                    .map(|metadata| metadata.file_type().is_file())
+                    .unwrap_or(false);
+                let relative = match path.strip_prefix(working_dir) {
+                    Ok(relative) if is_file => relative.to_path_buf(),
+                    _ => continue,
+                };
+                let name = relative.to_string_lossy().into_owned();
+                if artifacts.iter().any(|artifact| artifact.name == name) {
+                    continue;
+                }
+                let destination = directory.join(&relative);
+                if let Some(parent) = destination.parent() {
+                    DirBuilder::new()
+                        .recursive(true)
+                        .mode(0o700)
+                        .create(parent)?;
+                }
+                match Self::copy(&path, &destination, credentials, remaining) {
+                    Ok(size) if remaining.map_or(true, |remaining| size <= remaining) => {
+                        remaining = remaining.map(|remaining| remaining - size);
+                        artifacts.push(Artifact { name, size });
+                    }
+                    _ => {
+                        let _ = fs::remove_file(&destination);
+                    }
+                }
+            }
+        }
+        Ok(artifacts)
+    }
+
+    // Copy a file with `cat` in a child process that runs with the
+    // credentials of the job.  The source is opened by the child
+    // without following symbolic links and it must be a regular file,
+    // so it can't be replaced after it has been matched.  With a
+    // maximum size, `head` copies one byte more than it, so a larger
+    // file is detected without copying all of it.
+    fn copy(
+        source: &Path,
+        destination: &Path,
+        credentials: Option<&Credentials>,
+        max_bytes: Option<u64>,
+    ) -> io::Result<u64> {
+        let source = CString::new(source.as_os_str().as_bytes())?;
+        let credentials = credentials.cloned();
+        let copy = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(destination)?;
+        let mut command = match max_bytes {
+            Some(max_bytes) => {
+                let mut head = Command::new("head");
+                head.arg("-c").arg((max_bytes + 1).to_string());
+                head
+            }
+            None => Command::new("cat"),
+        };
+        command
+            .stdin(Stdio::null())
+            .stdout(copy)
+            .stderr(Stdio::null());
+        // Safe because only async-signal-safe functions are called
+        // between fork and exec and nothing is allocated.
+        unsafe {
+            command.pre_exec(move || {
+                if let Some(ref credentials) = credentials {
+                    credentials.switch()?;
+                }
+                let fd = libc::open(
+                    source.as_ptr(),
+                    libc::O_RDONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK,
+                );
+                if fd < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                let mut stat: libc::stat = mem::zeroed();
+                if libc::fstat(fd, &mut stat) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if stat.st_mode & libc::S_IFMT != libc::S_IFREG {
+                    return Err(io::Error::from_raw_os_error(libc::EINVAL));
+                }
+                if libc::dup2(fd, libc::STDIN_FILENO) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                libc::close(fd);
+                Ok(())
+            });
+        }
+        if !command.status()?.success() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The artifact can't be copied",
+            ));
+        }
+        Ok(fs::metadata(destination)?.len())
+    }
+
+    // Return the artifacts that have been collected.
+    pub(crate) fn list(&self) -> Vec<Artifact> {
+        self.collected.lock().unwrap().clone()
+    }
+
+    // Return the path of the stored copy of an artifact.
+    pub(crate) fn path(&self, name: &str) -> Option<String> {
+        self.collected
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|artifact| artifact.name == name)
+            .map(|artifact| {
+                self.directory
+                    .join(&artifact.name)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+    }
+
+    // Delete the stored copies of the artifacts.  The directory may
+    // exist even if none has been collected, when they were skipped.
+    pub(crate) async fn remove(&self) -> io::Result<()> {
+        match tokio::fs::remove_dir_all(&self.directory).await {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            removed => removed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use uuid::Uuid;
+
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn patterns_outside_of_working_dir_are_invalid() {
+        for pattern in &["/etc/passwd", "../secret", "out/../../secret", "[z-a"] {
+            let result = JobArtifacts::validate(&[pattern.to_string()], true);
+
+            assert!(matches!(result, Err(JobError::InvalidArtifactPattern(ref p)) if p == pattern));
+        }
+    }
+
+    #[tokio::test]
+    async fn matching_files_are_collected() {
+        let working_dir = scratch_dir();
+        fs::create_dir_all(working_dir.join("out")).unwrap();
+        fs::write(working_dir.join("out/app"), b"binary").unwrap();
+        fs::write(working_dir.join("out/app.log"), b"log").unwrap();
+        fs::write(working_dir.join("README"), b"readme").unwrap();
+        let directory = scratch_dir().join("artifacts");
+        let artifacts = JobArtifacts::new(
+            vec!["out/*".to_string(), "out/app".to_string()],
+            working_dir.clone(),
+            directory.clone(),
+            None,
+            None,
+        );
+
+        artifacts.collect().await.unwrap();
+
+        let mut names: Vec<String> = artifacts.list().into_iter().map(|a| a.name).collect();
+        names.sort();
+        assert_eq!(vec!["out/app", "out/app.log"], names);
+        let path = artifacts.path("out/app").unwrap();
+        assert_eq!(b"binary".to_vec(), fs::read(path).unwrap());
+        assert!(artifacts.path("README").is_none());
+        let mode = fs::metadata(&directory).unwrap().permissions().mode();
+        assert_eq!(0o700, mode & 0o777);
+        artifacts.remove().await.unwrap();
+        assert!(!directory.exists());
+        fs::remove_dir_all(working_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn artifacts_over_the_maximum_size_are_skipped() {
+        let working_dir = scratch_dir();
+        fs::write(working_dir.join("a.bin"), b"binary").unwrap();
+        fs::write(working_dir.join("b.log"), b"log").unwrap();
+        fs::write(working_dir.join("c.txt"), b"tx").unwrap();
+        let directory = scratch_dir().join("artifacts");
+        let artifacts = JobArtifacts::new(
+            vec!["*".to_string()],
+            working_dir.clone(),
+            directory.clone(),
+            None,
+            Some(8),
+        );
+
+        artifacts.collect().await.unwrap();
+
+        let names: Vec<String> = artifacts.list().into_iter().map(|a| a.name).collect();
+        assert_eq!(vec!["a.bin", "c.txt"], names);
+        assert!(!directory.join("b.log").exists());
+        artifacts.remove().await.unwrap();
+        fs::remove_dir_all(working_dir).unwrap();
+    }
+
+    #[test]
+    fn artifacts_require_a_workspace() {
+        let result = JobArtifacts::validate(&["out/*".to_string()], false);
+
+        assert!(matches!(result, Err(JobError::ArtifactsWithoutWorkspace)));
+        assert!(JobArtifacts::validate(&[], false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn symbolic_links_are_not_collected() {
+        let working_dir = scratch_dir();
+        let secret = scratch_dir().join("secret");
+        fs::write(&secret, b"secret").unwrap();
+        std::os::unix::fs::symlink(&secret, working_dir.join("link")).unwrap();
+        fs::write(working_dir.join("file"), b"file").unwrap();
+        let directory = scratch_dir().join("artifacts");
+        let artifacts = JobArtifacts::new(
+            vec!["*".to_string()],
+            working_dir.clone(),
+            directory.clone(),
+            None,
+            None,
+        );
+
+        artifacts.collect().await.unwrap();
+
+        let names: Vec<String> = artifacts.list().into_iter().map(|a| a.name).collect();
+        assert_eq!(vec!["file"], names);
+        assert!(!directory.join("link").exists());
+        artifacts.remove().await.unwrap();
+        fs::remove_dir_all(working_dir).unwrap();
+        fs::remove_dir_all(secret.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::{
+    self,
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tokio::sync::mpsc;
+
+// Chunks of output that can be waiting for an attachment to read them.
+const ATTACHMENT_CAPACITY: usize = 256;
+
+// Sender of the output to an attachment and whether it has lagged.
+type Attached = (mpsc::Sender<Vec<u8>>, Arc<AtomicBool>);
+
+/// Connection to the terminal of a job that receives its output as it
+/// is produced, byte by byte.  Nothing is dropped: an attachment that
+/// doesn't keep up with the output is ended instead.
+pub struct JobAttachment {
+    output: mpsc::Receiver<Vec<u8>>,
+    lagged: Arc<AtomicBool>,
+}
+
+impl JobAttachment {
+    /// Wait for the next chunk of output of the terminal.  It returns
+    /// `None` once the terminal has been closed or the attachment has
+    /// fallen behind.
+    pub async fn next(&mut self) -> Option<Vec<u8>> {
+        self.output.recv().await
+    }
+
+    /// Whether the attachment has been ended because the output wasn't
+    /// read fast enough.
+    pub fn lagged(&self) -> bool {
+        self.lagged.load(Ordering::SeqCst)
+    }
+}
+
+// Attachments to the terminal of a job.  They are fed by the task
+// that copies the output of the terminal and ended when it finishes.
+#[derive(Clone)]
+pub(crate) struct JobAttachments {
+    senders: Arc<sync::Mutex<Option<Vec<Attached>>>>,
+}
+
+impl JobAttachments {
+    pub(crate) fn new() -> Self {
+        JobAttachments {
+            senders: Arc::new(sync::Mutex::new(Some(Vec::new()))),
+        }
+    }
+
+    // Attachments created once the terminal has been closed end right
+    // away.
+    pub(crate) fn attach(&self) -> JobAttachment {
+        let (sender, output) = mpsc::channel(ATTACHMENT_CAPACITY);
+        let lagged = Arc::new(AtomicBool::new(false));
+        if let Some(senders) = self.senders.lock().unwrap().as_mut() {
+            senders.push((sender, Arc::clone(&lagged)));
+        }
+        JobAttachment { output, lagged }
+    }
+
+    pub(crate) fn send(&self, data: &[u8]) {
+        if let Some(senders) = self.senders.lock().unwrap().as_mut() {
+            senders.retain(|(sender, lagged)| match sender.try_send(data.to_vec()) {
+                Ok(()) => true,
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    lagged.store(true, Ordering::SeqCst);
+                    false
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => false,
+            });
+        }
+    }
+
+    pub(crate) fn close(&self) {
+        self.senders.lock().unwrap().take();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn attachments_receive_the_output_until_it_is_closed() {
+        let attachments = JobAttachments::new();
+        let mut attachment = attachments.attach();
+
+        attachments.send(b"hello");
+        attachments.close();
+
+        assert_eq!(Some(b"hello".to_vec()), attachment.next().await);
+        assert_eq!(None, attachment.next().await);
+        assert!(!attachment.lagged());
+    }
+
+    #[tokio::test]
+    async fn attachments_that_fall_behind_are_ended() {
+        let attachments = JobAttachments::new();
+        let mut attachment = attachments.attach();
+
+        for _ in 0..=ATTACHMENT_CAPACITY {
+            attachments.send(b"x");
+        }
+
+        let mut received = 0;
+        while attachment.next().await.is_some() {
+            received += 1;
+        }
+        assert_eq!(ATTACHMENT_CAPACITY, received);
+        assert!(attachment.lagged());
+    }
+
+    #[tokio::test]
+    async fn attachments_to_a_closed_terminal_end_right_away() {
+        let attachments = JobAttachments::new();
+        attachments.close();
+
+        let mut attachment = attachments.attach();
+
+        assert_eq!(None, attachment.next().await);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::JobStatus;
+use tokio::sync::watch;
+
+/// Handle that allows to wait for a job to reach its final status.
+/// It doesn't borrow the job, so it can be moved to other tasks.
+pub struct JobCompletion {
+    status: watch::Receiver<JobStatus>,
+}
+
+impl JobCompletion {
+    pub(crate) fn new(status: watch::Receiver<JobStatus>) -> JobCompletion {
+        JobCompletion { status }
+    }
+
+    /// Wait until the job is no longer in progress and return its
+    /// final status.
+    pub async fn wait(mut self) -> JobStatus {
+        loop {
+            let status = self.status.borrow().clone();
+            if !matches!(status, JobStatus::InProgress) {
+                return status;
+            }
+            // The final status is always sent before the sender is dropped.
+            if self.status.changed().await.is_err() {
+                return self.status.borrow().clone();
+            }
+        }
+    }
+}
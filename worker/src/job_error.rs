@@ -18,6 +18,29 @@ pub enum JobError {
     /// An unauthorized operation has been requested. Only the onwer
     /// of the job can perform operations on it.
     Unauthorized,
+    /// There is no job with the requested id.
+    NotFound,
+    /// A job with the same id has already been submitted.
+    AlreadyExists,
+    /// The stdin of the job has already been closed or it was never
+    /// opened.
+    StdinClosed,
+    /// The process hasn't read the data written to its stdin in time.
+    StdinTimeout,
+    /// The job hasn't been created with a terminal.
+    NoTerminal,
+    /// The glob pattern of an artifact is invalid or it refers to
+    /// files outside of the working directory.
+    InvalidArtifactPattern(String),
+    /// Artifacts have been requested for a job that doesn't run in its
+    /// own workspace.
+    ArtifactsWithoutWorkspace,
+    /// The archive used to populate the workspace of the job can't be
+    /// extracted.
+    InvalidWorkspaceArchive,
+    /// A subscriber hasn't kept up with the events and some of them
+    /// have been dropped.  It must fetch the status of the jobs again.
+    EventsLagged,
 }
 
 impl error::Error for JobError {}
@@ -29,6 +52,19 @@ impl Display for JobError {
             JobError::IoError(ref err) => write!(f, "I/O error: {}", err),
             JobError::InvalidCommand(ref cmd) => write!(f, "Invalid command {}", cmd),
             JobError::Unauthorized => write!(f, "Unauthorized operation"),
+            JobError::NotFound => write!(f, "Job not found"),
+            JobError::AlreadyExists => write!(f, "Job already exists"),
+            JobError::StdinClosed => write!(f, "Stdin of the job is closed"),
+            JobError::StdinTimeout => write!(f, "Writing to the stdin of the job has timed out"),
+            JobError::NoTerminal => write!(f, "Job doesn't have a terminal"),
+            JobError::InvalidArtifactPattern(ref pattern) => {
+                write!(f, "Invalid artifact pattern {}", pattern)
+            }
+            JobError::ArtifactsWithoutWorkspace => {
+                write!(f, "Artifacts require a workspace")
+            }
+            JobError::InvalidWorkspaceArchive => write!(f, "Invalid workspace archive"),
+            JobError::EventsLagged => write!(f, "Events have been dropped"),
         }
     }
 }
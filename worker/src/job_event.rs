@@ -0,0 +1,190 @@
+use crate::{job_acl::JobAcl, JobError, JobStatus, Permission, Principal, Role};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+/// Stream of the process where some output has been written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputStream {
+    /// Standard output of the process.
+    Stdout,
+    /// Standard error of the process.
+    Stderr,
+}
+
+/// Changes in the lifecycle of a job that can be observed subscribing
+/// to a `JobManager`.
+#[derive(Clone)]
+pub enum JobEvent {
+    /// The process of the job has been spawned.
+    Started { id: Uuid, owner: String },
+    /// The process has written to its standard output or error.
+    Output {
+        id: Uuid,
+        owner: String,
+        stream: OutputStream,
+        data: Vec<u8>,
+    },
+    /// A user has requested to stop the job.  A `Finished` event will
+    /// follow once the process has been killed.
+    Stopped { id: Uuid, owner: String },
+    /// The job has reached its final status and all its output has
+    /// been stored.
+    Finished {
+        id: Uuid,
+        owner: String,
+        status: JobStatus,
+    },
+    /// The finished job, its output and its artifacts have been removed
+    /// according to the retention policy.
+    Removed { id: Uuid, owner: String },
+}
+
+impl JobEvent {
+    /// Id of the job that has produced the event.
+    pub fn id(&self) -> Uuid {
+        match self {
+            JobEvent::Started { id, .. }
+            | JobEvent::Output { id, .. }
+            | JobEvent::Stopped { id, .. }
+            | JobEvent::Finished { id, .. }
+            | JobEvent::Removed { id, .. } => *id,
+        }
+    }
+
+    /// String id of the owner of the job that has produced the event.
+    pub fn owner(&self) -> &str {
+        match self {
+            JobEvent::Started { owner, .. }
+            | JobEvent::Output { owner, .. }
+            | JobEvent::Stopped { owner, .. }
+            | JobEvent::Finished { owner, .. }
+            | JobEvent::Removed { owner, .. } => owner,
+        }
+    }
+}
+
+// Event as it is published, with the access list of its job, so the
+// subscribers can be authorized like the operations on the job.
+pub(crate) type PublishedEvent = (JobEvent, Arc<JobAcl>);
+
+/// Subscription to the events of the jobs that a user can access.
+pub struct JobEvents {
+    receiver: broadcast::Receiver<PublishedEvent>,
+    user_id: String,
+    role: Role,
+    teams: Vec<String>,
+}
+
+impl JobEvents {
+    pub(crate) fn new(
+        receiver: broadcast::Receiver<PublishedEvent>,
+        as_user: Principal<'_>,
+    ) -> JobEvents {
+        JobEvents {
+            receiver,
+            user_id: as_user.id.to_string(),
+            role: as_user.role,
+            teams: as_user.teams.to_vec(),
+        }
+    }
+
+    // The user can read the events of the jobs that it can read, with
+    // the teams and the shares that they have at that moment.
+    fn can_read(&self, acl: &JobAcl) -> bool {
+        let as_user = Principal {
+            id: &self.user_id,
+            role: self.role,
+            teams: &self.teams,
+        };
+        acl.authorize(as_user, Permission::Read).is_ok()
+    }
+
+    /// Wait for the next event.  It returns `None` when no more events
+    /// can be produced.  Events are dropped for subscribers that don't
+    /// keep up with them, and they receive an `EventsLagged` error
+    /// instead, so they can resynchronize before receiving the next
+    /// ones.
+    pub async fn next(&mut self) -> Option<Result<JobEvent, JobError>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok((event, acl)) if self.can_read(&acl) => return Some(Ok(event)),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(_)) => {
+                    return Some(Err(JobError::EventsLagged))
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+// Helper used by the job tasks to publish the events of a job.
+#[derive(Clone)]
+pub(crate) struct EventPublisher {
+    id: Uuid,
+    owner: String,
+    acl: Arc<JobAcl>,
+    sender: broadcast::Sender<PublishedEvent>,
+}
+
+impl EventPublisher {
+    pub(crate) fn new(
+        id: Uuid,
+        acl: Arc<JobAcl>,
+        sender: broadcast::Sender<PublishedEvent>,
+    ) -> Self {
+        EventPublisher {
+            id,
+            owner: acl.owner().to_string(),
+            acl,
+            sender,
+        }
+    }
+
+    pub(crate) fn started(&self) {
+        self.publish(JobEvent::Started {
+            id: self.id,
+            owner: self.owner.clone(),
+        });
+    }
+
+    pub(crate) fn output(&self, stream: OutputStream, data: &[u8]) {
+        // Avoid copying the output if nobody is listening.
+        if self.sender.receiver_count() > 0 {
+            self.publish(JobEvent::Output {
+                id: self.id,
+                owner: self.owner.clone(),
+                stream,
+                data: data.to_vec(),
+            });
+        }
+    }
+
+    pub(crate) fn stopped(&self) {
+        self.publish(JobEvent::Stopped {
+            id: self.id,
+            owner: self.owner.clone(),
+        });
+    }
+
+    pub(crate) fn finished(&self, status: JobStatus) {
+        self.publish(JobEvent::Finished {
+            id: self.id,
+            owner: self.owner.clone(),
+            status,
+        });
+    }
+
+    pub(crate) fn removed(&self) {
+        self.publish(JobEvent::Removed {
+            id: self.id,
+            owner: self.owner.clone(),
+        });
+    }
+
+    fn publish(&self, event: JobEvent) {
+        // Sending fails when there are no subscribers, which is fine.
+        let _ = self.sender.send((event, Arc::clone(&self.acl)));
+    }
+}
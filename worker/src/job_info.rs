@@ -0,0 +1,20 @@
+use crate::{JobStatus, Share};
+use uuid::Uuid;
+
+/// Snapshot of the data of a job and its status at the time it was
+/// requested.
+#[derive(Clone)]
+pub struct JobInfo {
+    /// Id of the job.
+    pub id: Uuid,
+    /// String id of the owner of the job.
+    pub owner: String,
+    /// Name of the team that owns the job, if any.
+    pub team: Option<String>,
+    /// Users and teams that the job is shared with.
+    pub shares: Vec<Share>,
+    /// Command line executed in the job.
+    pub command_line: String,
+    /// Status of the job.
+    pub status: JobStatus,
+}
@@ -0,0 +1,761 @@
+use crate::{
+    job_event::PublishedEvent, job_workspace::JobWorkspace, Artifact, Grantee, Job, JobAttachment,
+    JobCompletion, JobError, JobEvents, JobInfo, JobOptions, OutputFile, Permission, Principal,
+    RetentionPolicy, Role, Share, TerminalSize,
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{broadcast, RwLock},
+    time,
+};
+use uuid::Uuid;
+
+const EVENTS_CAPACITY: usize = 1024;
+// Bounds of the period of the garbage collection, which follows the
+// maximum age of the jobs.
+const MIN_COLLECTION_PERIOD: Duration = Duration::from_secs(1);
+const MAX_COLLECTION_PERIOD: Duration = Duration::from_secs(60);
+
+/// Type that owns the collection of jobs and performs the operations
+/// on them on behalf of their users.
+///
+/// This is the entry point to the domain logic for any interface
+/// (`worker-api` or others) or for programs that embed the library.
+/// All the operations check that the role of the user authorizes
+/// them.  Its clones share the same jobs.
+#[derive(Clone)]
+pub struct JobManager {
+    jobs: Arc<RwLock<HashMap<Uuid, Arc<Job>>>>,
+    events: broadcast::Sender<PublishedEvent>,
+    retention: RetentionPolicy,
+}
+
+impl JobManager {
+    /// Creates a new `JobManager` without any jobs that keeps the
+    /// finished jobs forever.
+    pub fn new() -> JobManager {
+        Self::with_retention(RetentionPolicy::default())
+    }
+
+    /// Creates a new `JobManager` without any jobs that removes the
+    /// finished jobs and their output according to the policy.
+    ///
+    /// The garbage is collected periodically by a task, so it must be
+    /// called from a Tokio runtime if the policy removes any job.  The
+    /// task ends when the manager and all its clones are dropped.
+    ///
+    /// * `retention` - Policy that defines which finished jobs are kept.
+    pub fn with_retention(retention: RetentionPolicy) -> JobManager {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let manager = JobManager {
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            events,
+            retention,
+        };
+        if retention.max_age.is_some() || retention.max_finished_jobs.is_some() {
+            manager.spawn_collector();
+        }
+        manager
+    }
+
+    // Collect the garbage even when no jobs are submitted.  The task
+    // only keeps a weak reference to the jobs.
+    fn spawn_collector(&self) {
+        let jobs = Arc::downgrade(&self.jobs);
+        let retention = self.retention;
+        let period = retention.max_age.map_or(MAX_COLLECTION_PERIOD, |max_age| {
+            max_age.clamp(MIN_COLLECTION_PERIOD, MAX_COLLECTION_PERIOD)
+        });
+        tokio::spawn(async move {
+            // Submitting the jobs already collects the garbage.
+            let mut interval = time::interval_at(time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                match jobs.upgrade() {
+                    Some(jobs) => Self::remove_garbage(&jobs, retention).await,
+                    None => break,
+                };
+            }
+        });
+    }
+
+    /// Create a new job and start it.
+    ///
+    /// * `id` - UUID that will be assigned to the job. It must be
+    /// unique or it will return an `AlreadyExists` error.
+    /// * `as_user` - User that submits the job.  It owns the job.
+    /// * `command_line` - Command line that will be executed in this job.
+    pub async fn submit(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        command_line: &str,
+    ) -> Result<(), JobError> {
+        self.submit_with_options(id, as_user, command_line, JobOptions::default())
+            .await
+    }
+
+    /// Create a new job with the given options and start it.
+    ///
+    /// * `id` - UUID that will be assigned to the job. It must be
+    /// unique or it will return an `AlreadyExists` error.
+    /// * `as_user` - User that submits the job.  It owns the job.
+    /// * `command_line` - Command line that will be executed in this job.
+    /// * `options` - Optional settings of the job, like its input.
+    pub async fn submit_with_options(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        command_line: &str,
+        mut options: JobOptions,
+    ) -> Result<(), JobError> {
+        if !as_user.role.allows(Permission::Submit, true) {
+            return Err(JobError::Unauthorized);
+        }
+        // Only the members of a team, or admins, submit jobs for it.
+        if let Some(team) = &options.team {
+            if !as_user.teams.contains(team) && as_user.role != Role::Admin {
+                return Err(JobError::Unauthorized);
+            }
+        }
+        self.collect_garbage().await;
+        if self.jobs.read().await.contains_key(&id) {
+            return Err(JobError::AlreadyExists);
+        }
+        // Extracting the archive of the workspace may take a while, so
+        // the other jobs aren't locked meanwhile.
+        let workspace =
+            JobWorkspace::prepare(id, options.workspace.take(), options.credentials.clone())
+                .await?;
+        let mut jobs_map = self.jobs.write().await;
+        if jobs_map.contains_key(&id) {
+            workspace.discard();
+            return Err(JobError::AlreadyExists);
+        }
+        let job = match Job::with_events(
+            id,
+            as_user.id,
+            command_line,
+            options,
+            workspace.clone(),
+            self.events.clone(),
+        ) {
+            Ok(job) => job,
+            Err(err) => {
+                workspace.discard();
+                return Err(err);
+            }
+        };
+        jobs_map.insert(id, Arc::new(job));
+        Ok(())
+    }
+
+    /// Return the data and the status of a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn get(&self, id: Uuid, as_user: Principal<'_>) -> Result<JobInfo, JobError> {
+        self.find(id).await?.info(as_user).await
+    }
+
+    /// Return the data and the status of all the jobs that can be
+    /// accessed by the user.
+    ///
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn list(&self, as_user: Principal<'_>) -> Vec<JobInfo> {
+        let jobs: Vec<Arc<Job>> = self.jobs.read().await.values().cloned().collect();
+        let mut infos = Vec::new();
+        for job in jobs {
+            if let Ok(info) = job.info(as_user).await {
+                infos.push(info);
+            }
+        }
+        infos
+    }
+
+    /// Stop a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn stop(&self, id: Uuid, as_user: Principal<'_>) -> Result<(), JobError> {
+        self.find(id).await?.stop(as_user).await
+    }
+
+    /// Return the file that contains the output of a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn output(&self, id: Uuid, as_user: Principal<'_>) -> Result<OutputFile, JobError> {
+        self.find(id).await?.output(as_user).await
+    }
+
+    /// Discard the output stored so far for a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn purge_output(&self, id: Uuid, as_user: Principal<'_>) -> Result<(), JobError> {
+        self.find(id).await?.purge_output(as_user).await
+    }
+
+    /// Return the artifacts collected when the job finished.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn artifacts(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+    ) -> Result<Vec<Artifact>, JobError> {
+        self.find(id).await?.artifacts(as_user)
+    }
+
+    /// Return the path of the stored copy of an artifact of the job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    /// * `name` - Name of the artifact.
+    pub async fn artifact(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        name: &str,
+    ) -> Result<String, JobError> {
+        self.find(id).await?.artifact(as_user, name)
+    }
+
+    /// Share a job with a user or a team.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    /// * `share` - Grantee and the access that is granted to it.
+    pub async fn share(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        share: Share,
+    ) -> Result<(), JobError> {
+        self.find(id).await?.share(as_user, share)
+    }
+
+    /// Stop sharing a job with a user or a team.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    /// * `grantee` - User or team that loses its access.
+    pub async fn unshare(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        grantee: &Grantee,
+    ) -> Result<(), JobError> {
+        self.find(id).await?.unshare(as_user, grantee)
+    }
+
+    /// Remove the finished jobs, their output files and their
+    /// artifacts according to the retention policy and return how
+    /// many have been removed.  It is also done periodically and every
+    /// time a job is submitted.
+    pub async fn collect_garbage(&self) -> usize {
+        Self::remove_garbage(&self.jobs, self.retention).await
+    }
+
+    async fn remove_garbage(
+        jobs: &RwLock<HashMap<Uuid, Arc<Job>>>,
+        retention: RetentionPolicy,
+    ) -> usize {
+        let now = Instant::now();
+        let mut jobs_map = jobs.write().await;
+        let mut finished: Vec<(Instant, Uuid)> = jobs_map
+            .iter()
+            .filter_map(|(id, job)| job.finished_at().map(|finished_at| (finished_at, *id)))
+            .collect();
+        finished.sort();
+        let excess = retention
+            .max_finished_jobs
+            .map_or(0, |max| finished.len().saturating_sub(max));
+        let mut removed = Vec::new();
+        for (index, (finished_at, id)) in finished.into_iter().enumerate() {
+            let expired = matches!(retention.max_age,
+                Some(max_age) if now.duration_since(finished_at) >= max_age);
+            if index < excess || expired {
+                removed.extend(jobs_map.remove(&id));
+            }
+        }
+        // Files are removed without blocking the other operations.
+        drop(jobs_map);
+        for job in &removed {
+            let _ = job.remove_files().await;
+        }
+        removed.len()
+    }
+
+    /// Write data to the stdin of a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    /// * `data` - Bytes that will be written to the stdin.
+    pub async fn write_stdin(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        data: &[u8],
+    ) -> Result<(), JobError> {
+        self.find(id).await?.write_stdin(as_user, data).await
+    }
+
+    /// Close the stdin of a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn close_stdin(&self, id: Uuid, as_user: Principal<'_>) -> Result<(), JobError> {
+        self.find(id).await?.close_stdin(as_user).await
+    }
+
+    /// Change the size of the terminal of a job.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    /// * `size` - New size of the terminal.
+    pub async fn resize_terminal(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+        size: TerminalSize,
+    ) -> Result<(), JobError> {
+        self.find(id).await?.resize_terminal(as_user, size).await
+    }
+
+    /// Attach to the terminal of a job to receive its output.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn attach(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+    ) -> Result<JobAttachment, JobError> {
+        self.find(id).await?.attach(as_user)
+    }
+
+    /// Return a handle to wait for a job to finish.
+    ///
+    /// * `id` - UUID of the job.
+    /// * `as_user` - Perform this operation for this user.
+    pub async fn completion(
+        &self,
+        id: Uuid,
+        as_user: Principal<'_>,
+    ) -> Result<JobCompletion, JobError> {
+        self.find(id).await?.completion(as_user)
+    }
+
+    /// Subscribe to the lifecycle events of the jobs that can be
+    /// accessed by the user.  Only the events produced after the
+    /// subscription are received.
+    ///
+    /// * `as_user` - Perform this operation for this user.
+    pub fn subscribe(&self, as_user: Principal<'_>) -> JobEvents {
+        JobEvents::new(self.events.subscribe(), as_user)
+    }
+
+    // The read lock is only held while the map is accessed, so
+    // operations on different jobs don't block each other.
+    async fn find(&self, id: Uuid) -> Result<Arc<Job>, JobError> {
+        self.jobs
+            .read()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or(JobError::NotFound)
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        JobManager::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Access, JobEvent, JobStatus, OutputStream};
+
+    const OWNER_1: Principal = Principal::submitter("owner 1");
+    const OWNER_2: Principal = Principal::submitter("owner 2");
+
+    #[tokio::test]
+    async fn submitted_job_can_be_retrieved() {
+        let manager = JobManager::new();
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "sleep 1").await.unwrap();
+
+        let info = manager.get(id, OWNER_1).await.unwrap();
+
+        assert_eq!(id, info.id);
+        assert_eq!("sleep 1", info.command_line);
+        assert!(matches!(info.status, JobStatus::InProgress));
+    }
+
+    #[tokio::test]
+    async fn submit_with_duplicated_id_returns_already_exists() {
+        let manager = JobManager::new();
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "true").await.unwrap();
+
+        let result = manager.submit(id, OWNER_2, "true").await;
+
+        assert!(matches!(result, Err(JobError::AlreadyExists)));
+    }
+
+    #[tokio::test]
+    async fn unknown_job_returns_not_found() {
+        let manager = JobManager::new();
+
+        assert!(matches!(
+            manager.get(Uuid::new_v4(), OWNER_1).await,
+            Err(JobError::NotFound)
+        ));
+        assert!(matches!(
+            manager.stop(Uuid::new_v4(), OWNER_1).await,
+            Err(JobError::NotFound)
+        ));
+        assert!(matches!(
+            manager.output(Uuid::new_v4(), OWNER_1).await,
+            Err(JobError::NotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn operations_are_only_available_to_owner() {
+        let manager = JobManager::new();
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "true").await.unwrap();
+
+        assert!(matches!(
+            manager.get(id, OWNER_2).await,
+            Err(JobError::Unauthorized)
+        ));
+        assert!(matches!(
+            manager.stop(id, OWNER_2).await,
+            Err(JobError::Unauthorized)
+        ));
+        assert!(matches!(
+            manager.output(id, OWNER_2).await,
+            Err(JobError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn list_only_contains_jobs_of_the_user() {
+        let manager = JobManager::new();
+        let id_1 = Uuid::new_v4();
+        let id_2 = Uuid::new_v4();
+        manager.submit(id_1, OWNER_1, "true").await.unwrap();
+        manager.submit(id_2, OWNER_2, "true").await.unwrap();
+
+        let infos = manager.list(OWNER_1).await;
+
+        assert_eq!(1, infos.len());
+        assert_eq!(id_1, infos[0].id);
+    }
+
+    #[tokio::test]
+    async fn admins_list_every_job_and_viewers_cannot_submit() {
+        let manager = JobManager::new();
+        manager
+            .submit(Uuid::new_v4(), OWNER_1, "true")
+            .await
+            .unwrap();
+        manager
+            .submit(Uuid::new_v4(), OWNER_2, "true")
+            .await
+            .unwrap();
+        let admin = Principal {
+            id: "admin",
+            role: Role::Admin,
+            teams: &[],
+        };
+        let viewer = Principal {
+            id: "viewer",
+            role: Role::Viewer,
+            teams: &[],
+        };
+
+        assert_eq!(2, manager.list(admin).await.len());
+        assert_eq!(2, manager.list(viewer).await.len());
+        assert!(matches!(
+            manager.submit(Uuid::new_v4(), viewer, "true").await,
+            Err(JobError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn list_contains_jobs_shared_with_the_user() {
+        let manager = JobManager::new();
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "true").await.unwrap();
+        let share = Share {
+            grantee: Grantee::User(OWNER_2.id.to_string()),
+            access: Access::Read,
+        };
+        assert!(matches!(
+            manager.share(id, OWNER_2, share.clone()).await,
+            Err(JobError::Unauthorized)
+        ));
+
+        manager.share(id, OWNER_1, share.clone()).await.unwrap();
+
+        assert_eq!(1, manager.list(OWNER_2).await.len());
+        assert_eq!(vec![share], manager.get(id, OWNER_1).await.unwrap().shares);
+        manager
+            .unshare(id, OWNER_1, &Grantee::User(OWNER_2.id.to_string()))
+            .await
+            .unwrap();
+        assert!(manager.list(OWNER_2).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn only_members_submit_jobs_for_a_team() {
+        let manager = JobManager::new();
+        let options = JobOptions {
+            team: Some("ops".to_string()),
+            ..JobOptions::default()
+        };
+        let teams = vec!["ops".to_string()];
+        let member = Principal {
+            id: "member",
+            role: Role::Submitter,
+            teams: &teams,
+        };
+
+        assert!(matches!(
+            manager
+                .submit_with_options(Uuid::new_v4(), OWNER_1, "true", options.clone())
+                .await,
+            Err(JobError::Unauthorized)
+        ));
+        manager
+            .submit_with_options(Uuid::new_v4(), member, "true", options)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn subscriber_receives_lifecycle_events_of_own_jobs() {
+        let manager = JobManager::new();
+        let mut events = manager.subscribe(OWNER_1);
+        let id = Uuid::new_v4();
+        manager
+            .submit(Uuid::new_v4(), OWNER_2, "echo other")
+            .await
+            .unwrap();
+        manager.submit(id, OWNER_1, "echo hello").await.unwrap();
+
+        let mut received = Vec::new();
+        while let Some(event) = events.next().await {
+            let event = event.unwrap();
+            assert_eq!(id, event.id());
+            let finished = matches!(event, JobEvent::Finished { .. });
+            received.push(event);
+            if finished {
+                break;
+            }
+        }
+
+        assert!(matches!(received.first(), Some(JobEvent::Started { .. })));
+        assert!(received.iter().any(|event| matches!(event,
+            JobEvent::Output { stream: OutputStream::Stdout, data, .. } if data == b"hello\n")));
+        assert!(matches!(
+            received.last(),
+            Some(JobEvent::Finished { status: JobStatus::Done(status), .. }) if status.success()
+        ));
+    }
+
+    #[tokio::test]
+    async fn subscriber_receives_events_of_team_and_shared_jobs() {
+        let manager = JobManager::new();
+        let teams = vec!["ops".to_string()];
+        let member = Principal {
+            id: "member",
+            role: Role::Submitter,
+            teams: &teams,
+        };
+        let colleague = Principal {
+            id: "colleague",
+            ..member
+        };
+        let mut events = manager.subscribe(member);
+        let (team_id, private_id, shared_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let options = JobOptions {
+            team: Some("ops".to_string()),
+            ..JobOptions::default()
+        };
+        manager
+            .submit_with_options(team_id, colleague, "true", options)
+            .await
+            .unwrap();
+        manager.submit(private_id, OWNER_2, "true").await.unwrap();
+        manager
+            .completion(private_id, OWNER_2)
+            .await
+            .unwrap()
+            .wait()
+            .await;
+        manager
+            .submit(shared_id, OWNER_2, "sleep 100")
+            .await
+            .unwrap();
+        let share = Share {
+            grantee: Grantee::User(member.id.to_string()),
+            access: Access::Read,
+        };
+        manager.share(shared_id, OWNER_2, share).await.unwrap();
+
+        manager.stop(shared_id, OWNER_2).await.unwrap();
+
+        let mut received = Vec::new();
+        while let Some(event) = events.next().await {
+            let event = event.unwrap();
+            let finished = matches!(&event, JobEvent::Finished { id, .. } if *id == shared_id);
+            received.push(event.id());
+            if finished {
+                break;
+            }
+        }
+        assert!(received.contains(&team_id));
+        assert!(!received.contains(&private_id));
+        assert!(received.contains(&shared_id));
+    }
+
+    #[tokio::test]
+    async fn stopping_a_job_publishes_stopped_event() {
+        let manager = JobManager::new();
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "sleep 100").await.unwrap();
+        let mut events = manager.subscribe(OWNER_1);
+
+        manager.stop(id, OWNER_1).await.unwrap();
+
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(JobEvent::Stopped { .. }))
+        ));
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(JobEvent::Finished { status: JobStatus::Done(status), .. })) if !status.success()
+        ));
+    }
+
+    #[tokio::test]
+    async fn subscribers_that_lag_are_told_to_resynchronize() {
+        let manager = JobManager::new();
+        let mut events = manager.subscribe(OWNER_1);
+        let id = Uuid::new_v4();
+        manager
+            .submit(id, OWNER_1, "head -c 50000000 /dev/zero")
+            .await
+            .unwrap();
+        manager.completion(id, OWNER_1).await.unwrap().wait().await;
+
+        assert!(matches!(
+            events.next().await,
+            Some(Err(JobError::EventsLagged))
+        ));
+        assert!(matches!(events.next().await, Some(Ok(_))));
+    }
+
+    #[tokio::test]
+    async fn stdin_of_a_submitted_job_can_be_written() {
+        let manager = JobManager::new();
+        let id = Uuid::new_v4();
+        let options = JobOptions {
+            stdin: None,
+            keep_stdin_open: true,
+            ..JobOptions::default()
+        };
+        manager
+            .submit_with_options(id, OWNER_1, "cat", options)
+            .await
+            .unwrap();
+        let mut events = manager.subscribe(OWNER_1);
+
+        manager.write_stdin(id, OWNER_1, b"echoed").await.unwrap();
+        manager.close_stdin(id, OWNER_1).await.unwrap();
+
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(JobEvent::Output { data, .. })) if data == b"echoed"
+        ));
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(JobEvent::Finished { status: JobStatus::Done(status), .. })) if status.success()
+        ));
+    }
+
+    #[tokio::test]
+    async fn only_the_newest_finished_jobs_are_kept() {
+        let manager = JobManager::with_retention(RetentionPolicy {
+            max_age: None,
+            max_finished_jobs: Some(1),
+        });
+        let ids = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+        for id in &ids[..2] {
+            manager.submit(*id, OWNER_1, "true").await.unwrap();
+            manager.completion(*id, OWNER_1).await.unwrap().wait().await;
+        }
+        manager.submit(ids[2], OWNER_1, "sleep 1").await.unwrap();
+
+        manager.collect_garbage().await;
+
+        assert!(matches!(
+            manager.get(ids[0], OWNER_1).await.err(),
+            Some(JobError::NotFound)
+        ));
+        assert!(!std::path::Path::new(&format!("{}.txt", ids[0])).exists());
+        assert!(manager.get(ids[1], OWNER_1).await.is_ok());
+        assert!(manager.get(ids[2], OWNER_1).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn expired_jobs_are_removed() {
+        let manager = JobManager::with_retention(RetentionPolicy {
+            max_age: Some(std::time::Duration::from_millis(0)),
+            max_finished_jobs: None,
+        });
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "true").await.unwrap();
+        manager.completion(id, OWNER_1).await.unwrap().wait().await;
+
+        assert_eq!(1, manager.collect_garbage().await);
+        assert!(manager.list(OWNER_1).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn garbage_is_collected_periodically() {
+        let manager = JobManager::with_retention(RetentionPolicy {
+            max_age: Some(std::time::Duration::from_millis(0)),
+            max_finished_jobs: None,
+        });
+        let mut events = manager.subscribe(OWNER_1);
+        let id = Uuid::new_v4();
+        manager.submit(id, OWNER_1, "true").await.unwrap();
+        manager.completion(id, OWNER_1).await.unwrap().wait().await;
+
+        let removed = time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Some(Ok(JobEvent::Removed { id: removed, .. })) = events.next().await {
+                    return removed;
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(id, removed.expect("The job hasn't been removed"));
+        assert!(manager.list(OWNER_1).await.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+use crate::{Compression, Credentials, HardeningProfile, OutputLimit, TerminalSize, Workspace};
+
+/// Type that contains the optional settings of a job.  The default
+/// value runs the command without any input.
+#[derive(Clone, Debug, Default)]
+pub struct JobOptions {
+    /// Content that is written to the stdin of the job when it starts.
+    pub stdin: Option<Vec<u8>>,
+    /// Keep the stdin of the job open after writing the initial
+    /// content, so more input can be written later.
+    pub keep_stdin_open: bool,
+    /// Run the job in a pseudo terminal of the given size instead of
+    /// using pipes.  Its stdin is always kept open and both outputs
+    /// are merged into stdout.
+    pub terminal: Option<TerminalSize>,
+    /// Maximum size of the stored output.  It is unlimited by default.
+    pub output_limit: Option<OutputLimit>,
+    /// Compress the output once the job has finished.
+    pub output_compression: Option<Compression>,
+    /// Glob patterns, relative to the working directory, of the files
+    /// that are collected as artifacts once the job has finished.
+    pub artifacts: Vec<String>,
+    /// Maximum size of all the artifacts together.  The files that
+    /// would exceed it aren't collected.  It is unlimited by default.
+    pub artifacts_max_bytes: Option<u64>,
+    /// Run the job in a new temporary directory instead of the working
+    /// directory of the process.  It is removed with the job.  The
+    /// jobs with credentials always run in one, empty by default.
+    pub workspace: Option<Workspace>,
+    /// Run the job as this Unix user instead of the user of the
+    /// process.  Changing to a different user requires root.
+    pub credentials: Option<Credentials>,
+    /// Resource limits and seccomp filter applied to the job.  There
+    /// are no restrictions by default.
+    pub hardening: HardeningProfile,
+    /// Team that owns the job together with the user that submits it.
+    /// Its members can read and stop the job.
+    pub team: Option<String>,
+}
+
+impl JobOptions {
+    // Stdin is only connected to a pipe if there is something to write.
+    pub(crate) fn has_stdin(&self) -> bool {
+        self.stdin.is_some() || self.keep_stdin_open || self.terminal.is_some()
+    }
+}
@@ -0,0 +1,352 @@
+use crate::{Compression, OutputFile, OutputLimit, OutputLimitAction};
+use std::{
+    fs::OpenOptions,
+    io::{self, SeekFrom},
+    os::unix::fs::OpenOptionsExt,
+    path::PathBuf,
+    sync::Arc,
+};
+use tokio::{
+    fs::File,
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    sync::{Mutex, Notify},
+};
+
+// Bytes of the output moved at a time when its head is truncated.
+const TAIL_CHUNK_SIZE: usize = 64 * 1024;
+
+// File that stores the output of a job, applying its size limit and
+// its compression.  It is shared by the tasks that copy stdout and
+// stderr.
+pub(crate) struct JobOutput {
+    path: String,
+    limit: Option<OutputLimit>,
+    compression: Option<Compression>,
+    stop_request: Arc<Notify>,
+    state: Mutex<OutputState>,
+}
+
+struct OutputState {
+    file: File,
+    size: u64,
+    // Once the limit of a `StopJob` output is reached, the rest is discarded.
+    full: bool,
+    // The content is in the compressed file once the job has finished.
+    compressed: bool,
+}
+
+impl JobOutput {
+    // Create an empty output file.  The stop request is used when the
+    // job must be killed because of its output size.
+    pub(crate) fn create(
+        path: String,
+        limit: Option<OutputLimit>,
+        compression: Option<Compression>,
+        stop_request: Arc<Notify>,
+    ) -> io::Result<JobOutput> {
+        let file = Self::open(&path)?;
+        Ok(JobOutput {
+            path,
+            limit,
+            compression,
+            stop_request,
+            state: Mutex::new(OutputState {
+                file,
+                size: 0,
+                full: false,
+                compressed: false,
+            }),
+        })
+    }
+
+    // Only the user of the server can read the output.
+    fn open(path: &str) -> io::Result<File> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        Ok(File::from_std(file))
+    }
+
+    // Return the file that currently contains the output.
+    pub(crate) async fn file(&self) -> OutputFile {
+        let state = self.state.lock().await;
+        self.current_file(&state)
+    }
+
+    fn current_file(&self, state: &OutputState) -> OutputFile {
+        match self.compression {
+            Some(compression) if state.compressed => OutputFile {
+                path: format!("{}.{}", self.path, compression.extension()),
+                compression: Some(compression),
+            },
+            _ => OutputFile {
+                path: self.path.clone(),
+                compression: None,
+            },
+        }
+    }
+
+    // Replace the output file with its compressed version, if the job
+    // must be compressed.  No more output can be written afterwards.
+    pub(crate) async fn compress(&self) -> io::Result<()> {
+        let compression = match self.compression {
+            Some(compression) => compression,
+            None => return Ok(()),
+        };
+        let mut state = self.state.lock().await;
+        state.file.flush().await?;
+        let source = PathBuf::from(&self.path);
+        let destination = PathBuf::from(format!("{}.{}", self.path, compression.extension()));
+        tokio::task::spawn_blocking(move || compression.compress(&source, &destination))
+            .await
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))??;
+        tokio::fs::remove_file(&self.path).await?;
+        state.compressed = true;
+        state.full = true;
+        Ok(())
+    }
+
+    // Append a chunk of output to the file.
+    pub(crate) async fn write(&self, data: &[u8]) -> io::Result<()> {
+        let mut state = self.state.lock().await;
+        if state.full {
+            return Ok(());
+        }
+        let limit = match self.limit {
+            Some(limit) if state.size + data.len() as u64 > limit.max_bytes => limit,
+            _ => return state.append(data).await,
+        };
+        match limit.action {
+            OutputLimitAction::StopJob => {
+                let available = (limit.max_bytes - state.size) as usize;
+                state.append(&data[..available]).await?;
+                state.full = true;
+                self.stop_request.notify_one();
+                Ok(())
+            }
+            OutputLimitAction::TruncateHead => state.keep_tail(limit.max_bytes / 2, data).await,
+        }
+    }
+
+    // Discard all the output stored so far.  A compressed output is
+    // replaced by an empty uncompressed one.
+    pub(crate) async fn truncate(&self) -> io::Result<()> {
+        let mut state = self.state.lock().await;
+        if state.compressed {
+            tokio::fs::remove_file(self.current_file(&state).path).await?;
+            state.file = Self::open(&self.path)?;
+            state.compressed = false;
+        } else {
+            state.file.set_len(0).await?;
+            state.file.seek(SeekFrom::Start(0)).await?;
+        }
+        state.size = 0;
+        Ok(())
+    }
+
+    // Delete the output file.
+    pub(crate) async fn remove(&self) -> io::Result<()> {
+        let state = self.state.lock().await;
+        tokio::fs::remove_file(self.current_file(&state).path).await
+    }
+}
+
+impl OutputState {
+    async fn append(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.write_all(data).await?;
+        self.file.flush().await?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    // Rewrite the file with only the newest `keep` bytes of its
+    // content followed by the data.  Keeping half of the limit avoids
+    // rewriting the file for every chunk.  The tail is moved to the
+    // start of the file in chunks, so a large limit doesn't need as
+    // much memory.
+    async fn keep_tail(&mut self, keep: u64, data: &[u8]) -> io::Result<()> {
+        let data = &data[data.len().saturating_sub(keep as usize)..];
+        let from_file = keep.saturating_sub(data.len() as u64).min(self.size);
+        let start = self.size - from_file;
+        let mut chunk = vec![0; TAIL_CHUNK_SIZE.min(from_file as usize)];
+        let mut moved = 0;
+        while moved < from_file {
+            let length = chunk.len().min((from_file - moved) as usize);
+            self.file.seek(SeekFrom::Start(start + moved)).await?;
+            self.file.read_exact(&mut chunk[..length]).await?;
+            self.file.seek(SeekFrom::Start(moved)).await?;
+            self.file.write_all(&chunk[..length]).await?;
+            moved += length as u64;
+        }
+        self.file.set_len(from_file).await?;
+        self.file.seek(SeekFrom::Start(from_file)).await?;
+        self.size = from_file;
+        self.append(data).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::Read, os::unix::fs::PermissionsExt};
+    use uuid::Uuid;
+
+    fn create_output(limit: Option<OutputLimit>) -> (JobOutput, Arc<Notify>) {
+        let stop_request = Arc::new(Notify::new());
+        let path = format!("{}.txt", Uuid::new_v4());
+        let output = JobOutput::create(path, limit, None, Arc::clone(&stop_request)).unwrap();
+        (output, stop_request)
+    }
+
+    async fn read_compressed(output: &JobOutput) -> String {
+        let file = output.file().await;
+        let compressed = std::fs::File::open(&file.path).unwrap();
+        let mut content = String::new();
+        file.compression
+            .unwrap()
+            .decoder(compressed)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[tokio::test]
+    async fn output_is_compressed_with_gzip_and_zstd() {
+        for compression in &[Compression::Gzip, Compression::Zstd] {
+            let path = format!("{}.txt", Uuid::new_v4());
+            let output = JobOutput::create(
+                path.clone(),
+                None,
+                Some(*compression),
+                Arc::new(Notify::new()),
+            )
+            .unwrap();
+            output.write(b"compress me, compress me").await.unwrap();
+
+            output.compress().await.unwrap();
+
+            assert!(!std::path::Path::new(&path).exists());
+            assert_eq!("compress me, compress me", read_compressed(&output).await);
+            output.remove().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn output_is_private() {
+        let (output, _) = create_output(None);
+        output.write(b"0123456789").await.unwrap();
+
+        let path = output.file().await.path;
+
+        let mode = std::fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(0o600, mode & 0o777);
+        output.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncated_compressed_output_is_uncompressed() {
+        let path = format!("{}.txt", Uuid::new_v4());
+        let output = JobOutput::create(
+            path.clone(),
+            None,
+            Some(Compression::Gzip),
+            Arc::new(Notify::new()),
+        )
+        .unwrap();
+        output.write(b"0123456789").await.unwrap();
+        output.compress().await.unwrap();
+        let compressed_path = output.file().await.path;
+
+        output.truncate().await.unwrap();
+
+        let file = output.file().await;
+        assert_eq!(path, file.path);
+        assert!(file.compression.is_none());
+        assert!(!std::path::Path::new(&compressed_path).exists());
+        assert!(std::fs::read_to_string(&path).unwrap().is_empty());
+        output.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unlimited_output_keeps_everything() {
+        let (output, _) = create_output(None);
+
+        output.write(b"0123456789").await.unwrap();
+        output.write(b"abcdef").await.unwrap();
+
+        let content = std::fs::read_to_string(output.file().await.path).unwrap();
+        assert_eq!("0123456789abcdef", content);
+        output.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncate_head_keeps_the_newest_output() {
+        let limit = OutputLimit {
+            max_bytes: 10,
+            action: OutputLimitAction::TruncateHead,
+        };
+        let (output, _) = create_output(Some(limit));
+
+        output.write(b"01234567").await.unwrap();
+        output.write(b"abc").await.unwrap();
+
+        let content = std::fs::read_to_string(output.file().await.path).unwrap();
+        assert_eq!("67abc", content);
+        output.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncate_head_moves_long_tails_in_chunks() {
+        let limit = OutputLimit {
+            max_bytes: 6 * TAIL_CHUNK_SIZE as u64,
+            action: OutputLimitAction::TruncateHead,
+        };
+        let (output, _) = create_output(Some(limit));
+        let data: Vec<u8> = (0..6 * TAIL_CHUNK_SIZE).map(|i| (i % 251) as u8).collect();
+
+        output.write(&data).await.unwrap();
+        output.write(b"abc").await.unwrap();
+
+        let content = std::fs::read(output.file().await.path).unwrap();
+        let mut expected = data[3 * TAIL_CHUNK_SIZE + 3..].to_vec();
+        expected.extend_from_slice(b"abc");
+        assert_eq!(expected, content);
+        output.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stop_job_discards_output_and_requests_stop() {
+        let limit = OutputLimit {
+            max_bytes: 10,
+            action: OutputLimitAction::StopJob,
+        };
+        let (output, stop_request) = create_output(Some(limit));
+
+        output.write(b"01234567").await.unwrap();
+        output.write(b"abc").await.unwrap();
+        output.write(b"def").await.unwrap();
+
+        stop_request.notified().await;
+        let content = std::fs::read_to_string(output.file().await.path).unwrap();
+        assert_eq!("01234567ab", content);
+        output.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn truncated_output_is_empty() {
+        let (output, _) = create_output(None);
+        output.write(b"0123456789").await.unwrap();
+
+        output.truncate().await.unwrap();
+        output.write(b"abc").await.unwrap();
+
+        let content = std::fs::read_to_string(output.file().await.path).unwrap();
+        assert_eq!("abc", content);
+        output.remove().await.unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use crate::{Credentials, JobError, Workspace};
+use std::{
+    env,
+    fs::{self, DirBuilder},
+    io::{self, Cursor},
+    os::unix::fs::DirBuilderExt,
+    path::{Path, PathBuf},
+};
+use uuid::Uuid;
+
+// Working directory of a job.  It is either the working directory of
+// the process that runs the jobs or a temporary directory of the job
+// that is removed with its other files.  The jobs that run as another
+// user always get a temporary directory, so they don't run among the
+// files of the server and of the other jobs.
+#[derive(Clone)]
+pub(crate) struct JobWorkspace {
+    path: PathBuf,
+    temporary: bool,
+}
+
+impl JobWorkspace {
+    // Create the workspace like `create` in a blocking task, since
+    // extracting the archive and changing its owner may take a while.
+    pub(crate) async fn prepare(
+        id: Uuid,
+        workspace: Option<Workspace>,
+        owner: Option<Credentials>,
+    ) -> Result<JobWorkspace, JobError> {
+        tokio::task::spawn_blocking(move || Self::create(id, workspace.as_ref(), owner.as_ref()))
+            .await
+            .map_err(|err| JobError::IoError(io::Error::new(io::ErrorKind::Other, err)))?
+    }
+
+    // Create the temporary directory of the job, if requested, and
+    // extract the archive in it.  The entries of the archive can't be
+    // extracted outside of the directory.  Only its owner can access
+    // the directory, and everything in it belongs to the user that
+    // runs the job, if it is different.  It blocks.
+    pub(crate) fn create(
+        id: Uuid,
+        workspace: Option<&Workspace>,
+        owner: Option<&Credentials>,
+    ) -> Result<JobWorkspace, JobError> {
+        let workspace = match (workspace, owner) {
+            (Some(workspace), _) => workspace,
+            (None, Some(_)) => &Workspace::Empty,
+            (None, None) => {
+                return Ok(JobWorkspace {
+                    path: env::current_dir()?,
+                    temporary: false,
+                })
+            }
+        };
+        let path = Workspace::path(id);
+        DirBuilder::new().mode(0o700).create(&path)?;
+        let job_workspace = JobWorkspace {
+            path,
+            temporary: true,
+        };
+        if let Workspace::Tar(archive) = workspace {
+            if tar::Archive::new(Cursor::new(archive))
+                .unpack(&job_workspace.path)
+                .is_err()
+            {
+                job_workspace.discard();
+                return Err(JobError::InvalidWorkspaceArchive);
+            }
+        }
+        if let Some(owner) = owner {
+            if let Err(err) = owner.own(&job_workspace.path) {
+                job_workspace.discard();
+                return Err(err.into());
+            }
+        }
+        Ok(job_workspace)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn is_temporary(&self) -> bool {
+        self.temporary
+    }
+
+    // Delete the temporary directory of a job that couldn't be
+    // started.
+    pub(crate) fn discard(&self) {
+        if self.temporary {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    // Delete the temporary directory and everything the job left in
+    // it.
+    pub(crate) async fn remove(&self) -> io::Result<()> {
+        if !self.temporary {
+            return Ok(());
+        }
+        tokio::fs::remove_dir_all(&self.path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn archive(path: &str, content: &[u8]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn without_workspace_current_dir_is_used() {
+        let workspace = JobWorkspace::create(Uuid::new_v4(), None, None).unwrap();
+
+        assert_eq!(env::current_dir().unwrap(), workspace.path());
+        workspace.discard();
+        assert!(workspace.path().exists());
+    }
+
+    #[tokio::test]
+    async fn jobs_of_other_users_get_a_temporary_dir() {
+        let id = Uuid::new_v4();
+        // Safe because geteuid and getegid always succeed.
+        let owner = unsafe {
+            Credentials {
+                uid: libc::geteuid(),
+                gid: libc::getegid(),
+                groups: Vec::new(),
+            }
+        };
+
+        let workspace = JobWorkspace::create(id, None, Some(&owner)).unwrap();
+
+        assert_eq!(Workspace::path(id), workspace.path());
+        assert!(fs::read_dir(workspace.path()).unwrap().next().is_none());
+        workspace.remove().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn archive_is_extracted_in_temporary_dir() {
+        let id = Uuid::new_v4();
+        let workspace = Workspace::Tar(archive("src/data.txt", b"some data"));
+
+        let job_workspace = JobWorkspace::prepare(id, Some(workspace), None)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            env::temp_dir().join(format!("worker-{}", id)),
+            job_workspace.path()
+        );
+        let mode = fs::metadata(job_workspace.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(0o700, mode & 0o777);
+        let content = fs::read(job_workspace.path().join("src/data.txt")).unwrap();
+        assert_eq!(b"some data".to_vec(), content);
+        job_workspace.remove().await.unwrap();
+        assert!(!job_workspace.path().exists());
+    }
+
+    #[test]
+    fn invalid_archive_is_rejected() {
+        let id = Uuid::new_v4();
+        let workspace = Workspace::Tar(b"not a tar archive".repeat(64));
+
+        let result = JobWorkspace::create(id, Some(&workspace), None);
+
+        assert!(matches!(result, Err(JobError::InvalidWorkspaceArchive)));
+        assert!(!env::temp_dir().join(format!("worker-{}", id)).exists());
+    }
+}
@@ -2,12 +2,64 @@
 This is a library that allows to run commands, query their state and
 their output and stop them.
 
-It provides an abstraction over a job.
+It provides an abstraction over a job and a `JobManager` that owns
+the jobs, authorizes the operations on them according to the role of
+each user and publishes the events of their lifecycle.
 */
+mod access;
+mod artifact;
+mod compression;
+mod credentials;
+mod grantee;
+mod hardening_profile;
 mod job;
+mod job_acl;
+mod job_artifacts;
+mod job_attachment;
+mod job_completion;
 mod job_error;
+mod job_event;
+mod job_info;
+mod job_manager;
+mod job_options;
+mod job_output;
 mod job_status;
+mod job_workspace;
+mod output_file;
+mod output_limit;
+mod output_limit_action;
+mod permission;
+mod principal;
+mod pty;
+mod retention_policy;
+mod role;
+mod seccomp_filter;
+mod share;
+mod terminal_size;
+mod workspace;
 
+pub use access::Access;
+pub use artifact::Artifact;
+pub use compression::Compression;
+pub use credentials::Credentials;
+pub use grantee::Grantee;
+pub use hardening_profile::HardeningProfile;
 pub use job::Job;
+pub use job_attachment::JobAttachment;
+pub use job_completion::JobCompletion;
 pub use job_error::JobError;
+pub use job_event::{JobEvent, JobEvents, OutputStream};
+pub use job_info::JobInfo;
+pub use job_manager::JobManager;
+pub use job_options::JobOptions;
 pub use job_status::JobStatus;
+pub use output_file::OutputFile;
+pub use output_limit::OutputLimit;
+pub use output_limit_action::OutputLimitAction;
+pub use permission::Permission;
+pub use principal::Principal;
+pub use retention_policy::RetentionPolicy;
+pub use role::Role;
+pub use share::Share;
+pub use terminal_size::TerminalSize;
+pub use workspace::Workspace;
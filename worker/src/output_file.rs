@@ -0,0 +1,10 @@
+use crate::Compression;
+
+/// Type that describes the file where the output of a job is stored.
+#[derive(Clone, Debug)]
+pub struct OutputFile {
+    /// Path of the file.
+    pub path: String,
+    /// Compression of the content of the file, if any.
+    pub compression: Option<Compression>,
+}
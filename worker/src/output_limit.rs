@@ -0,0 +1,10 @@
+use crate::OutputLimitAction;
+
+/// Type that defines the maximum size of the stored output of a job.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputLimit {
+    /// Maximum number of bytes of the output file.
+    pub max_bytes: u64,
+    /// What to do when the output reaches the maximum size.
+    pub action: OutputLimitAction,
+}
@@ -0,0 +1,8 @@
+/// What happens when the output of a job reaches its limit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputLimitAction {
+    /// Discard the oldest half of the output and keep running.
+    TruncateHead,
+    /// Stop storing output and kill the job.
+    StopJob,
+}
@@ -0,0 +1,17 @@
+/// Operations on jobs that are authorized by the `Role` of a user.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    /// Create new jobs.
+    Submit,
+    /// Query the status, the output, the artifacts and the events of a
+    /// job.
+    Read,
+    /// Stop a job.
+    Stop,
+    /// Write to the stdin or resize the terminal of a job.
+    Interact,
+    /// Discard the output of a job.
+    PurgeOutput,
+    /// Share a job with other users or teams, or stop sharing it.
+    Share,
+}
@@ -0,0 +1,27 @@
+use crate::Role;
+
+/// User on whose behalf an operation is performed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Principal<'a> {
+    /// String id of the user.  It is the owner of the jobs it submits.
+    pub id: &'a str,
+    /// Role that authorizes the operations of the user.
+    pub role: Role,
+    /// Names of the teams of the user.  It can read and stop the jobs
+    /// of these teams.
+    pub teams: &'a [String],
+}
+
+impl<'a> Principal<'a> {
+    /// Create a principal with the `Submitter` role and without teams,
+    /// that can only access its own jobs and the ones shared with it.
+    ///
+    /// * `id` - String id of the user.
+    pub const fn submitter(id: &'a str) -> Principal<'a> {
+        Principal {
+            id,
+            role: Role::Submitter,
+            teams: &[],
+        }
+    }
+}
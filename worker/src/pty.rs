@@ -0,0 +1,103 @@
+use crate::TerminalSize;
+use std::{
+    fs::File,
+    io,
+    os::unix::io::{AsRawFd, FromRawFd},
+    process::Stdio,
+};
+use tokio::process::Command;
+
+/// Size of the buffer of the name of the slave side.
+const SLAVE_NAME_LENGTH: usize = 64;
+
+// Pseudo terminal used to run interactive jobs.  The process uses the
+// slave side as its controlling terminal and the job keeps the master
+// side to read its output, write its input and resize it.
+pub(crate) struct Pty {
+    master: File,
+    slave: File,
+}
+
+impl Pty {
+    // Open a new pseudo terminal with the given size.  Both sides are
+    // opened with `O_CLOEXEC`, so the processes of other jobs that are
+    // spawned meanwhile don't inherit them.
+    pub(crate) fn open(size: TerminalSize) -> io::Result<Pty> {
+        // Safe because the file descriptors are owned by the `File`s
+        // right after being created and the name of the slave side is
+        // written to a buffer of the given length.
+        unsafe {
+            let master = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC);
+            if master < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let master = File::from_raw_fd(master);
+            if libc::grantpt(master.as_raw_fd()) < 0 || libc::unlockpt(master.as_raw_fd()) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut name: [libc::c_char; SLAVE_NAME_LENGTH] = [0; SLAVE_NAME_LENGTH];
+            let result = libc::ptsname_r(master.as_raw_fd(), name.as_mut_ptr(), name.len());
+            if result != 0 {
+                return Err(io::Error::from_raw_os_error(result));
+            }
+            let slave = libc::open(
+                name.as_ptr(),
+                libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC,
+            );
+            if slave < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let pty = Pty {
+                master,
+                slave: File::from_raw_fd(slave),
+            };
+            Self::resize(&pty.master, size)?;
+            Ok(pty)
+        }
+    }
+
+    // Make the slave side the stdin, stdout, stderr and controlling
+    // terminal of the command.  The pseudo terminal can be dropped
+    // once the command has been spawned.
+    pub(crate) fn attach(&self, command: &mut Command) -> io::Result<()> {
+        command
+            .stdin(Stdio::from(self.slave.try_clone()?))
+            .stdout(Stdio::from(self.slave.try_clone()?))
+            .stderr(Stdio::from(self.slave.try_clone()?));
+        // Safe because only async-signal-safe functions are called
+        // between fork and exec.
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    // Keep only the master side, closing the slave one.
+    pub(crate) fn into_master(self) -> File {
+        self.master
+    }
+
+    // Change the size of the terminal.  The process receives a SIGWINCH.
+    pub(crate) fn resize(master: &File, size: TerminalSize) -> io::Result<()> {
+        let winsize = Self::winsize(size);
+        // Safe because the ioctl only reads the given struct.
+        if unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ, &winsize) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn winsize(size: TerminalSize) -> libc::winsize {
+        libc::winsize {
+            ws_row: size.rows,
+            ws_col: size.cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        }
+    }
+}
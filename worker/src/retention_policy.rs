@@ -0,0 +1,12 @@
+use std::time::Duration;
+
+/// Type that defines how long finished jobs and their output are kept
+/// by a `JobManager`.  The default value keeps them forever.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetentionPolicy {
+    /// Remove the jobs that finished longer than this ago.
+    pub max_age: Option<Duration>,
+    /// Keep at most this number of finished jobs, removing the oldest
+    /// ones first.
+    pub max_finished_jobs: Option<usize>,
+}
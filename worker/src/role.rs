@@ -0,0 +1,71 @@
+use crate::Permission;
+
+/// Role of a user.  It defines the operations that the user can
+/// perform on its own jobs and on the jobs of other users.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Role {
+    /// Every operation on every job.
+    Admin,
+    /// Every operation on its own jobs.  It can also read and stop
+    /// the jobs of other users.
+    Operator,
+    /// Every operation on its own jobs.
+    Submitter,
+    /// It can only read the jobs of every user.
+    Viewer,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Role::Submitter
+    }
+}
+
+impl Role {
+    /// Check if the role allows an operation.  This is the permission
+    /// matrix of the roles.
+    ///
+    /// * `permission` - Operation that will be performed.
+    /// * `own` - The job is owned by the user.
+    pub fn allows(&self, permission: Permission, own: bool) -> bool {
+        match (self, permission) {
+            (Role::Admin, _) => true,
+            (Role::Operator, Permission::Read) | (Role::Operator, Permission::Stop) => true,
+            (Role::Operator, _) | (Role::Submitter, _) => own,
+            (Role::Viewer, Permission::Read) => true,
+            (Role::Viewer, _) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_admins_interact_with_jobs_of_others() {
+        for role in &[Role::Admin, Role::Operator, Role::Submitter, Role::Viewer] {
+            assert_eq!(
+                *role == Role::Admin,
+                role.allows(Permission::Interact, false)
+            );
+        }
+    }
+
+    #[test]
+    fn viewers_are_read_only() {
+        assert!(Role::Viewer.allows(Permission::Read, false));
+        assert!(!Role::Viewer.allows(Permission::Submit, true));
+        assert!(!Role::Viewer.allows(Permission::Stop, true));
+        assert!(!Role::Viewer.allows(Permission::PurgeOutput, true));
+    }
+
+    #[test]
+    fn operators_read_and_stop_jobs_of_others() {
+        assert!(Role::Operator.allows(Permission::Read, false));
+        assert!(Role::Operator.allows(Permission::Stop, false));
+        assert!(!Role::Operator.allows(Permission::PurgeOutput, false));
+        assert!(!Role::Submitter.allows(Permission::Read, false));
+        assert!(Role::Submitter.allows(Permission::Stop, true));
+    }
+}
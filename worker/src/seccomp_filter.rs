@@ -0,0 +1,269 @@
+use std::io;
+
+// Classic BPF instructions and seccomp constants that aren't defined
+// by the version of libc in use.
+// BPF_LD | BPF_W | BPF_ABS
+const BPF_LD_W_ABS: u16 = 0x20;
+// BPF_JMP | BPF_JEQ | BPF_K
+const BPF_JMP_JEQ_K: u16 = 0x15;
+// BPF_JMP | BPF_JGE | BPF_K
+const BPF_JMP_JGE_K: u16 = 0x35;
+// BPF_JMP | BPF_JSET | BPF_K
+const BPF_JMP_JSET_K: u16 = 0x45;
+// BPF_RET | BPF_K
+const BPF_RET_K: u16 = 0x06;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+// Offsets in `struct seccomp_data`.  The lower half of the first
+// argument is used, because both architectures are little endian.
+const SYSCALL_NR_OFFSET: u32 = 0;
+const ARCH_OFFSET: u32 = 4;
+const FIRST_ARG_OFFSET: u32 = 16;
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_003e);
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: Option<u32> = Some(0xc000_00b7);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const AUDIT_ARCH: Option<u32> = None;
+// The x32 ABI uses the same architecture with this bit in the number.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+// System calls that administer the system or inspect other
+// processes.  They fail with `EPERM`.
+const DENIED_SYSCALLS: &[libc::c_long] = &[
+    libc::SYS_acct,
+    libc::SYS_add_key,
+    libc::SYS_bpf,
+    libc::SYS_clock_settime,
+    libc::SYS_delete_module,
+    libc::SYS_finit_module,
+    libc::SYS_init_module,
+    libc::SYS_io_uring_enter,
+    libc::SYS_io_uring_register,
+    libc::SYS_io_uring_setup,
+    libc::SYS_kexec_load,
+    libc::SYS_keyctl,
+    libc::SYS_mount,
+    libc::SYS_open_by_handle_at,
+    libc::SYS_perf_event_open,
+    libc::SYS_pivot_root,
+    libc::SYS_process_vm_readv,
+    libc::SYS_process_vm_writev,
+    libc::SYS_ptrace,
+    libc::SYS_reboot,
+    libc::SYS_request_key,
+    libc::SYS_setns,
+    libc::SYS_settimeofday,
+    libc::SYS_swapoff,
+    libc::SYS_swapon,
+    libc::SYS_umount2,
+    libc::SYS_unshare,
+    libc::SYS_userfaultfd,
+];
+
+// Flags of `clone` that create namespaces, like `unshare` does.
+const CLONE_NAMESPACE_FLAGS: u32 = (libc::CLONE_NEWCGROUP
+    | libc::CLONE_NEWIPC
+    | libc::CLONE_NEWNET
+    | libc::CLONE_NEWNS
+    | libc::CLONE_NEWPID
+    | libc::CLONE_NEWUSER
+    | libc::CLONE_NEWUTS) as u32;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SockFilter {
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}
+
+#[repr(C)]
+struct SockFprog {
+    len: libc::c_ushort,
+    filter: *const SockFilter,
+}
+
+// Seccomp-bpf program that denies some system calls.  It is built
+// before forking, so installing it in the child doesn't allocate.
+pub(crate) struct SeccompFilter {
+    program: Vec<SockFilter>,
+}
+
+impl SeccompFilter {
+    // Build the program.  Calls for other architectures kill the
+    // process, so the filter can't be bypassed with them.
+    //
+    // * `deny_network` - Deny the sockets that aren't Unix sockets.
+    pub(crate) fn new(deny_network: bool) -> SeccompFilter {
+        let deny = SECCOMP_RET_ERRNO | libc::EPERM as u32;
+        let mut program = vec![
+            Self::load(ARCH_OFFSET),
+            Self::jump_if_equal(AUDIT_ARCH.unwrap_or_default(), 1, 0),
+            Self::ret(SECCOMP_RET_KILL_PROCESS),
+            Self::load(SYSCALL_NR_OFFSET),
+        ];
+        if cfg!(target_arch = "x86_64") {
+            program.push(SockFilter {
+                code: BPF_JMP_JGE_K,
+                jt: 0,
+                jf: 1,
+                k: X32_SYSCALL_BIT,
+            });
+            program.push(Self::ret(SECCOMP_RET_KILL_PROCESS));
+        }
+        for &syscall in DENIED_SYSCALLS {
+            program.push(Self::jump_if_equal(syscall as u32, 0, 1));
+            program.push(Self::ret(deny));
+        }
+        // The flags of `clone3` are in memory, where the filter can't
+        // read them.  It is reported as not implemented, so the C
+        // libraries fall back to `clone`, whose flags are checked.
+        program.push(Self::jump_if_equal(libc::SYS_clone3 as u32, 0, 1));
+        program.push(Self::ret(SECCOMP_RET_ERRNO | libc::ENOSYS as u32));
+        program.push(Self::jump_if_equal(libc::SYS_clone as u32, 0, 4));
+        program.push(Self::load(FIRST_ARG_OFFSET));
+        program.push(SockFilter {
+            code: BPF_JMP_JSET_K,
+            jt: 0,
+            jf: 1,
+            k: CLONE_NAMESPACE_FLAGS,
+        });
+        program.push(Self::ret(deny));
+        program.push(Self::ret(SECCOMP_RET_ALLOW));
+        if deny_network {
+            program.push(Self::jump_if_equal(libc::SYS_socket as u32, 0, 3));
+            program.push(Self::load(FIRST_ARG_OFFSET));
+            program.push(Self::jump_if_equal(libc::AF_UNIX as u32, 1, 0));
+            program.push(Self::ret(deny));
+        }
+        program.push(Self::ret(SECCOMP_RET_ALLOW));
+        SeccompFilter { program }
+    }
+
+    fn load(offset: u32) -> SockFilter {
+        SockFilter {
+            code: BPF_LD_W_ABS,
+            jt: 0,
+            jf: 0,
+            k: offset,
+        }
+    }
+
+    fn jump_if_equal(value: u32, jt: u8, jf: u8) -> SockFilter {
+        SockFilter {
+            code: BPF_JMP_JEQ_K,
+            jt,
+            jf,
+            k: value,
+        }
+    }
+
+    fn ret(value: u32) -> SockFilter {
+        SockFilter {
+            code: BPF_RET_K,
+            jt: 0,
+            jf: 0,
+            k: value,
+        }
+    }
+
+    // Install the filter in the current process.  It is meant to be
+    // called between fork and exec, so it is async-signal-safe.  The
+    // process can't gain privileges afterwards, which is required to
+    // install it without them.
+    pub(crate) fn install(&self) -> io::Result<()> {
+        if AUDIT_ARCH.is_none() {
+            return Err(io::Error::from_raw_os_error(libc::ENOSYS));
+        }
+        let program = SockFprog {
+            len: self.program.len() as libc::c_ushort,
+            filter: self.program.as_ptr(),
+        };
+        // Safe because the program outlives the calls and the kernel
+        // copies it.
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) < 0
+                || libc::prctl(
+                    libc::PR_SET_SECCOMP,
+                    libc::SECCOMP_MODE_FILTER,
+                    &program as *const SockFprog,
+                ) < 0
+            {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run the system call in a child process with the filter installed
+    // and return the error number of its failure, or 0 if it succeeds.
+    fn errno_of_filtered(syscall: fn() -> libc::c_long) -> i32 {
+        let filter = SeccompFilter::new(false);
+        // Safe because the child only makes system calls and exits.
+        unsafe {
+            match libc::fork() {
+                0 => {
+                    let errno = match filter.install() {
+                        Ok(()) if syscall() < 0 => io::Error::last_os_error().raw_os_error(),
+                        Ok(()) => Some(0),
+                        Err(_) => None,
+                    };
+                    libc::_exit(errno.unwrap_or(255));
+                }
+                pid => {
+                    let mut status = 0;
+                    libc::waitpid(pid, &mut status, 0);
+                    libc::WEXITSTATUS(status)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn io_uring_is_denied() {
+        if AUDIT_ARCH.is_none() {
+            return;
+        }
+
+        let errno = errno_of_filtered(|| unsafe {
+            libc::syscall(libc::SYS_io_uring_setup, 1, std::ptr::null_mut::<u8>())
+        });
+
+        assert_eq!(libc::EPERM, errno);
+    }
+
+    #[test]
+    fn clone3_is_not_implemented() {
+        if AUDIT_ARCH.is_none() {
+            return;
+        }
+
+        let errno = errno_of_filtered(|| unsafe {
+            libc::syscall(libc::SYS_clone3, std::ptr::null_mut::<u8>(), 0)
+        });
+
+        assert_eq!(libc::ENOSYS, errno);
+    }
+
+    #[test]
+    fn clone_of_namespaces_is_denied() {
+        if AUDIT_ARCH.is_none() {
+            return;
+        }
+
+        let errno = errno_of_filtered(|| unsafe {
+            let flags = libc::CLONE_NEWUSER | libc::SIGCHLD;
+            libc::syscall(libc::SYS_clone, flags, 0, 0, 0, 0)
+        });
+
+        assert_eq!(libc::EPERM, errno);
+    }
+}
@@ -0,0 +1,10 @@
+use crate::{Access, Grantee};
+
+/// Entry of the access control list of a job.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Share {
+    /// User or team that the job is shared with.
+    pub grantee: Grantee,
+    /// Operations that the grantee can perform on the job.
+    pub access: Access,
+}
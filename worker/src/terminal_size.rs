@@ -0,0 +1,14 @@
+/// Type that defines the size of the terminal of a job in characters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerminalSize {
+    /// Number of rows.
+    pub rows: u16,
+    /// Number of columns.
+    pub cols: u16,
+}
+
+impl Default for TerminalSize {
+    fn default() -> Self {
+        TerminalSize { rows: 24, cols: 80 }
+    }
+}
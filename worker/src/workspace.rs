@@ -0,0 +1,23 @@
+use std::{env, path::PathBuf};
+use uuid::Uuid;
+
+/// Content of the temporary directory that is used as the working
+/// directory of a job.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Workspace {
+    /// Start with an empty directory.
+    Empty,
+    /// Extract this tar archive in the directory before starting the
+    /// job.
+    Tar(Vec<u8>),
+}
+
+impl Workspace {
+    /// Path of the temporary directory of a job, where its workspace
+    /// is created.
+    ///
+    /// * `id` - UUID of the job.
+    pub fn path(id: Uuid) -> PathBuf {
+        env::temp_dir().join(format!("worker-{}", id))
+    }
+}